@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use deepkey_verify::{from_json, KeyProof, RecoveryManifest, SignedRevocationList};
+use holochain_serialized_bytes::decode;
+use serde::de::{DeserializeOwned, IgnoredAny};
+
+// Something Deepkey exports, told apart by its fields.
+pub enum Artifact {
+    KeyProof(Box<KeyProof>),
+    RevocationList(SignedRevocationList),
+    RecoveryManifest(RecoveryManifest),
+}
+
+// Exports are saved either as JSON or as the MessagePack a zome call returns.
+fn parse<T: DeserializeOwned + std::fmt::Debug>(bytes: &[u8]) -> Result<T> {
+    if bytes.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{') {
+        Ok(from_json(bytes)?)
+    } else {
+        decode(bytes).map_err(|error| anyhow!("{error:?}"))
+    }
+}
+
+impl Artifact {
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+        // Tell the kind apart by a field only it has, so that a damaged export reports
+        // what is wrong with it rather than that it is none of them.
+        let fields = parse::<BTreeMap<String, IgnoredAny>>(&bytes).ok();
+        let has_field = |field: &str| {
+            fields
+                .as_ref()
+                .is_some_and(|fields| fields.contains_key(field))
+        };
+        if has_field("key_registration") {
+            let key_proof = parse(&bytes)
+                .with_context(|| format!("{} is not a valid key proof", path.display()))?;
+            Ok(Self::KeyProof(Box::new(key_proof)))
+        } else if has_field("revocation_list") {
+            let revocation_list = parse(&bytes)
+                .with_context(|| format!("{} is not a valid revocation list", path.display()))?;
+            Ok(Self::RevocationList(revocation_list))
+        } else if has_field("recovery_shares") {
+            let recovery_manifest = parse(&bytes)
+                .with_context(|| format!("{} is not a valid recovery manifest", path.display()))?;
+            Ok(Self::RecoveryManifest(recovery_manifest))
+        } else {
+            Err(anyhow!(
+                "{} is not a key proof, revocation list or recovery manifest",
+                path.display()
+            ))
+        }
+    }
+
+    pub fn read_key_proof(path: &Path) -> Result<KeyProof> {
+        match Self::read(path)? {
+            Self::KeyProof(key_proof) => Ok(*key_proof),
+            _ => Err(anyhow!("{} is not a key proof", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use deepkey_verify::{RevocationList, RevocationReason, RevokedKey};
+    use holo_hash::{ActionHash, AgentPubKey, DnaHash, EntryHash, HoloHashed};
+    use holochain_integrity_types::prelude::{
+        Action, AppEntryBytes, AppEntryDef, Create, Dna, Entry, EntryRateWeight, EntryType,
+        EntryVisibility, Record, Signature, SignedHashed, Timestamp,
+    };
+    use holochain_serialized_bytes::{encode, SerializedBytes, UnsafeBytes};
+
+    use super::*;
+
+    fn agent(byte: u8) -> AgentPubKey {
+        AgentPubKey::from_raw_36(vec![byte; 36])
+    }
+
+    fn dna_hash() -> DnaHash {
+        DnaHash::from_raw_36(vec![0xdb; 36])
+    }
+
+    // A Create of `entry`, or a Dna action when there is none.
+    fn record(byte: u8, entry: Option<Entry>) -> Record {
+        let action = match entry {
+            Some(_) => Action::Create(Create {
+                author: agent(byte),
+                timestamp: Timestamp::from_micros(byte as i64),
+                action_seq: 4,
+                prev_action: ActionHash::from_raw_36(vec![byte - 1; 36]),
+                entry_type: EntryType::App(AppEntryDef {
+                    entry_index: 0.into(),
+                    zome_index: 0.into(),
+                    visibility: EntryVisibility::Public,
+                }),
+                entry_hash: EntryHash::from_raw_36(vec![byte; 36]),
+                weight: EntryRateWeight::default(),
+            }),
+            None => Action::Dna(Dna {
+                author: agent(byte),
+                timestamp: Timestamp::from_micros(byte as i64),
+                hash: dna_hash(),
+            }),
+        };
+        let hashed = HoloHashed::with_pre_hashed(action, ActionHash::from_raw_36(vec![byte; 36]));
+        Record::new(
+            SignedHashed::with_presigned(hashed, Signature([byte; 64])),
+            entry,
+        )
+    }
+
+    fn entry() -> Entry {
+        Entry::App(AppEntryBytes(SerializedBytes::from(UnsafeBytes::from(
+            vec![0x92, 0x01, 0x02],
+        ))))
+    }
+
+    fn key_proof() -> KeyProof {
+        KeyProof {
+            version: deepkey_verify::KEY_PROOF_VERSION,
+            dna_hash: dna_hash(),
+            key: agent(1),
+            keyset_root: record(2, None),
+            change_rules: vec![record(3, None)],
+            invites: Vec::new(),
+            key_registration: record(4, Some(entry())),
+            exported_at: Timestamp::from_micros(5),
+        }
+    }
+
+    fn revocation_list() -> SignedRevocationList {
+        SignedRevocationList {
+            revocation_list: RevocationList {
+                version: deepkey_verify::REVOCATION_LIST_VERSION,
+                keyset_root: ActionHash::from_raw_36(vec![2; 36]),
+                dna_hash: dna_hash(),
+                issued_at: Timestamp::from_micros(6),
+                revoked_keys: vec![RevokedKey {
+                    key: agent(1),
+                    key_revocation: ActionHash::from_raw_36(vec![7; 36]),
+                    revoked_at: Timestamp::from_micros(7),
+                    reason: RevocationReason::Compromised,
+                    compromised_since: Some(Timestamp::from_micros(3)),
+                    replaced_by: Some(agent(8)),
+                }],
+            },
+            signer: agent(4),
+            signature: Signature([9; 64]),
+        }
+    }
+
+    fn recovery_manifest() -> RecoveryManifest {
+        RecoveryManifest {
+            version: deepkey_verify::RECOVERY_MANIFEST_VERSION,
+            dna_hash: dna_hash(),
+            owner: agent(4),
+            threshold: 2,
+            recovery_shares: vec![record(10, None), record(11, None)],
+            exported_at: Timestamp::from_micros(12),
+        }
+    }
+
+    // Writes `bytes` to a file of its own in the temporary directory.
+    fn write(name: &str, bytes: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("deepkey-cli-{}-{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn read(name: &str, bytes: &[u8]) -> Result<Artifact> {
+        let path = write(name, bytes);
+        let artifact = Artifact::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        artifact
+    }
+
+    #[test]
+    fn key_proof_round_trips() {
+        let json = serde_json::to_vec_pretty(&key_proof()).unwrap();
+        let msgpack = encode(&key_proof()).unwrap();
+        for (name, bytes) in [("proof.json", json), ("proof.msgpack", msgpack)] {
+            match read(name, &bytes).unwrap() {
+                Artifact::KeyProof(read) => assert_eq!(*read, key_proof()),
+                _ => panic!("{name} was not read as a key proof"),
+            }
+        }
+    }
+
+    #[test]
+    fn revocation_list_round_trips() {
+        let json = serde_json::to_vec(&revocation_list()).unwrap();
+        let msgpack = encode(&revocation_list()).unwrap();
+        for (name, bytes) in [("list.json", json), ("list.msgpack", msgpack)] {
+            match read(name, &bytes).unwrap() {
+                Artifact::RevocationList(read) => assert_eq!(read, revocation_list()),
+                _ => panic!("{name} was not read as a revocation list"),
+            }
+        }
+    }
+
+    #[test]
+    fn recovery_manifest_round_trips() {
+        let json = serde_json::to_vec(&recovery_manifest()).unwrap();
+        let msgpack = encode(&recovery_manifest()).unwrap();
+        for (name, bytes) in [("manifest.json", json), ("manifest.msgpack", msgpack)] {
+            match read(name, &bytes).unwrap() {
+                Artifact::RecoveryManifest(read) => assert_eq!(read, recovery_manifest()),
+                _ => panic!("{name} was not read as a recovery manifest"),
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_key_proof_reports_its_decode_error() {
+        let mut json = serde_json::to_value(key_proof()).unwrap();
+        json["version"] = serde_json::json!("one");
+        let error = read("corrupt.json", &serde_json::to_vec(&json).unwrap())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("is not a valid key proof"));
+        assert!(format!("{error:#}").contains("invalid type"));
+    }
+
+    #[test]
+    fn unknown_artifact_is_rejected() {
+        let error = read("unknown.json", br#"{"something": "else"}"#)
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains("is not a key proof, revocation list or recovery manifest"));
+        assert!(read("empty.msgpack", &[]).is_err());
+    }
+}
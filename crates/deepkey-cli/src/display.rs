@@ -0,0 +1,185 @@
+use anyhow::Result;
+use deepkey_verify::{
+    entry_of, AuthorityOperation, AuthorityPolicy, AuthoritySpec, ChangeRule, DeviceInvite,
+    KeyProof, KeysetRoot, RecoveryManifest, RecoveryShare, SignedRevocationList,
+};
+
+/// A policy over signer positions, e.g. `any of (#0, 2 of (#1, #2, #3))`.
+pub fn describe_policy(policy: &AuthorityPolicy) -> String {
+    let describe_all = |policies: &[AuthorityPolicy]| {
+        policies
+            .iter()
+            .map(describe_policy)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match policy {
+        AuthorityPolicy::Signer(position) => format!("#{}", position),
+        AuthorityPolicy::All(policies) => format!("all of ({})", describe_all(policies)),
+        AuthorityPolicy::Any(policies) => format!("any of ({})", describe_all(policies)),
+        AuthorityPolicy::Threshold { required, policies } => {
+            format!("{} of ({})", required, describe_all(policies))
+        }
+        AuthorityPolicy::WeightedThreshold { required, policies } => format!(
+            "weight {} of ({})",
+            required,
+            policies
+                .iter()
+                .map(|(weight, policy)| format!("{} x {}", weight, describe_policy(policy)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Prints the policy of `spec` and its signers by position, each line after `prefix`.
+pub fn print_spec(prefix: &str, spec: &AuthoritySpec) {
+    println!("{}policy {}", prefix, describe_policy(&spec.policy()));
+    for (position, signer) in spec.authorized_signers.iter().enumerate() {
+        println!("{}  #{} {}", prefix, position, signer);
+    }
+}
+
+fn operation_name(operation: AuthorityOperation) -> &'static str {
+    match operation {
+        AuthorityOperation::ChangeRuleUpdate => "change rule update",
+        AuthorityOperation::KeyRevocation => "key revocation",
+        AuthorityOperation::KeyReplacement => "key replacement",
+        AuthorityOperation::DeviceInvite => "device invite",
+    }
+}
+
+// The default spec of a ChangeRule, then any spec set for a single operation.
+fn print_change_rule_specs(prefix: &str, change_rule: &ChangeRule) {
+    print_spec(prefix, &change_rule.spec_change.new_spec);
+    for operation in AuthorityOperation::ALL {
+        if let Some(spec) = change_rule.spec_change.operation_specs.get(operation) {
+            println!("{}for {}:", prefix, operation_name(operation));
+            print_spec(&format!("{}  ", prefix), spec);
+        }
+    }
+}
+
+pub fn print_key_proof(key_proof: &KeyProof) -> Result<()> {
+    let keyset_root: KeysetRoot = entry_of(&key_proof.keyset_root)?;
+    println!(
+        "Key proof (version {}), exported {}",
+        key_proof.version, key_proof.exported_at
+    );
+    println!("  network      {}", key_proof.dna_hash);
+    println!("  key          {}", key_proof.key);
+    println!("  keyset root  {}", key_proof.keyset_root.action_address());
+    println!("  root key     {}", keyset_root.root_pub_key());
+    println!(
+        "  registered   by {} in {}",
+        key_proof.key_registration.action().author(),
+        key_proof.key_registration.action_address()
+    );
+    println!(
+        "  {} ChangeRules and {} invites below the first deepkey agent",
+        key_proof.change_rules.len(),
+        key_proof.invites.len()
+    );
+    Ok(())
+}
+
+/// The keyset as far as a key proof shows it: the root, its ChangeRules, and the path of
+/// invites from the first deepkey agent down to the device that registered the key.
+pub fn print_keyset_tree(key_proof: &KeyProof) -> Result<()> {
+    let keyset_root: KeysetRoot = entry_of(&key_proof.keyset_root)?;
+    println!("Keyset {}", key_proof.keyset_root.action_address());
+    println!("├── root key {}", keyset_root.root_pub_key());
+    println!("├── ChangeRules");
+    for (index, record) in key_proof.change_rules.iter().enumerate() {
+        let change_rule: ChangeRule = entry_of(record)?;
+        let in_force = index + 1 == key_proof.change_rules.len();
+        let (branch, prefix) = if in_force {
+            ("└──", "│       ")
+        } else {
+            ("├──", "│   │   ")
+        };
+        println!(
+            "│   {} revision {} {}{}",
+            branch,
+            change_rule.revision,
+            record.action_address(),
+            if in_force { " (in force)" } else { "" }
+        );
+        print_change_rule_specs(prefix, &change_rule);
+    }
+    println!(
+        "└── {} (first deepkey agent)",
+        keyset_root.first_deepkey_agent
+    );
+    let mut indent = String::from("    ");
+    for invite in key_proof.invites.iter() {
+        let device_invite: DeviceInvite = entry_of(&invite.device_invite)?;
+        println!(
+            "{}└── {} ({} in {}, accepted in {})",
+            indent,
+            device_invite.invitee,
+            match device_invite.recovery {
+                Some(_) => "recovery invite",
+                None => "invited",
+            },
+            invite.device_invite.action_address(),
+            invite.device_invite_acceptance.action_address()
+        );
+        indent.push_str("    ");
+    }
+    println!(
+        "{}└── key {} (registered in {})",
+        indent,
+        key_proof.key,
+        key_proof.key_registration.action_address()
+    );
+    Ok(())
+}
+
+pub fn print_revocation_list(signed_revocation_list: &SignedRevocationList) {
+    let revocation_list = &signed_revocation_list.revocation_list;
+    println!(
+        "Revocation list (version {}), issued {}",
+        revocation_list.version, revocation_list.issued_at
+    );
+    println!("  network      {}", revocation_list.dna_hash);
+    println!("  keyset root  {}", revocation_list.keyset_root);
+    println!("  signed by    {}", signed_revocation_list.signer);
+    println!("  {} revoked keys", revocation_list.revoked_keys.len());
+    for revoked_key in revocation_list.revoked_keys.iter() {
+        println!("  - {}", revoked_key.key);
+        println!(
+            "      {:?}, revoked {} in {}",
+            revoked_key.reason, revoked_key.revoked_at, revoked_key.key_revocation
+        );
+        if let Some(compromised_since) = revoked_key.compromised_since {
+            println!("      compromised since {}", compromised_since);
+        }
+        if let Some(replaced_by) = &revoked_key.replaced_by {
+            println!("      replaced by {}", replaced_by);
+        }
+    }
+}
+
+pub fn print_recovery_manifest(recovery_manifest: &RecoveryManifest) -> Result<()> {
+    println!(
+        "Recovery manifest (version {}), exported {}",
+        recovery_manifest.version, recovery_manifest.exported_at
+    );
+    println!("  network  {}", recovery_manifest.dna_hash);
+    println!("  owner    {}", recovery_manifest.owner);
+    println!(
+        "  any {} of {} guardians can rebuild the revocation seed",
+        recovery_manifest.threshold,
+        recovery_manifest.recovery_shares.len()
+    );
+    for record in recovery_manifest.recovery_shares.iter() {
+        let recovery_share: RecoveryShare = entry_of(record)?;
+        println!(
+            "  - {} (share {})",
+            recovery_share.recipient,
+            record.action_address()
+        );
+    }
+    Ok(())
+}
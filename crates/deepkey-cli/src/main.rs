@@ -0,0 +1,114 @@
+//! Inspects and verifies the artifacts Deepkey exports, without a conductor: key proofs,
+//! signed revocation lists and recovery manifests, as JSON or MessagePack.
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use deepkey_verify::{verify_key_proof, verify_recovery_manifest};
+
+mod artifact;
+mod display;
+mod payload;
+
+use artifact::Artifact;
+
+#[derive(Parser)]
+#[command(
+    name = "deepkey-cli",
+    about = "Inspect and verify exported Deepkey artifacts"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a key proof, revocation list or recovery manifest.
+    Show { artifact: PathBuf },
+    /// Check the signatures of a key proof, revocation list or recovery manifest.
+    Verify {
+        artifact: PathBuf,
+        /// For a revocation list: a proof of a key registered by its signer, to check that
+        /// the signer is a device of the keyset.
+        #[arg(long)]
+        signer_proof: Option<PathBuf>,
+    },
+    /// Print the keyset a key proof shows, from its root down to the key.
+    Tree { proof: PathBuf },
+    /// Compute what the ChangeRule signers must sign for an operation on the keyset.
+    Payload(payload::PayloadArgs),
+}
+
+fn verify(artifact: Artifact, signer_proof: Option<PathBuf>) -> Result<()> {
+    match artifact {
+        Artifact::KeyProof(key_proof) => {
+            let verified_key = verify_key_proof(&key_proof)?;
+            println!(
+                "Valid: {} belongs to keyset {}",
+                verified_key.key, verified_key.keyset_root
+            );
+            println!(
+                "  registered by {} under {}",
+                verified_key.device, verified_key.keyset_leaf
+            );
+            println!("  ChangeRule in force {}", verified_key.change_rule);
+        }
+        Artifact::RevocationList(signed_revocation_list) => {
+            signed_revocation_list.verify()?;
+            println!("Valid: signed by {}", signed_revocation_list.signer);
+            match signer_proof {
+                Some(path) => {
+                    let verified_key = verify_key_proof(&Artifact::read_key_proof(&path)?)?;
+                    let revocation_list = &signed_revocation_list.revocation_list;
+                    if verified_key.device != signed_revocation_list.signer
+                        || verified_key.keyset_root != revocation_list.keyset_root
+                        || verified_key.dna_hash != revocation_list.dna_hash
+                    {
+                        bail!(
+                            "The key proof is not of a key registered by the signer in the keyset"
+                        );
+                    }
+                    println!(
+                        "  the signer is a device of keyset {}",
+                        revocation_list.keyset_root
+                    );
+                }
+                None => println!(
+                    "  pass --signer-proof to check that the signer is a device of the keyset"
+                ),
+            }
+        }
+        Artifact::RecoveryManifest(recovery_manifest) => {
+            verify_recovery_manifest(&recovery_manifest)?;
+            println!(
+                "Valid: {} shares committed by {}, any {} of which rebuild the seed",
+                recovery_manifest.recovery_shares.len(),
+                recovery_manifest.owner,
+                recovery_manifest.threshold
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Show { artifact } => match Artifact::read(&artifact)? {
+            Artifact::KeyProof(key_proof) => display::print_key_proof(&key_proof),
+            Artifact::RevocationList(signed_revocation_list) => {
+                display::print_revocation_list(&signed_revocation_list);
+                Ok(())
+            }
+            Artifact::RecoveryManifest(recovery_manifest) => {
+                display::print_recovery_manifest(&recovery_manifest)
+            }
+        },
+        Command::Verify {
+            artifact,
+            signer_proof,
+        } => verify(Artifact::read(&artifact)?, signer_proof),
+        Command::Tree { proof } => display::print_keyset_tree(&Artifact::read_key_proof(&proof)?),
+        Command::Payload(args) => payload::compute_payload(args),
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use deepkey_verify::{
+    entry_of, verify_key_proof, AuthorityOperation, AuthoritySpec, ChangeRule,
+    ChangeRuleVetoPayload, KeyRevocationPayload, KeysetRecoveryPayload, KeysetUnfreezePayload,
+    OperationSpecs, RevocationReason, SigningPayload, SpecChangePayload,
+};
+use holo_hash::{ActionHash, ActionHashB64, AgentPubKey, AgentPubKeyB64};
+use holochain_integrity_types::prelude::Timestamp;
+use serde::Deserialize;
+
+use crate::{artifact::Artifact, display::print_spec};
+
+fn parse_agent(value: &str) -> Result<AgentPubKey, String> {
+    AgentPubKeyB64::from_b64_str(value)
+        .map(AgentPubKey::from)
+        .map_err(|e| e.to_string())
+}
+
+fn parse_action_hash(value: &str) -> Result<ActionHash, String> {
+    ActionHashB64::from_b64_str(value)
+        .map(ActionHash::from)
+        .map_err(|e| e.to_string())
+}
+
+fn parse_reason(value: &str) -> Result<RevocationReason, String> {
+    match value {
+        "superseded" => Ok(RevocationReason::Superseded),
+        "compromised" => Ok(RevocationReason::Compromised),
+        "lost" => Ok(RevocationReason::Lost),
+        "retired" => Ok(RevocationReason::Retired),
+        _ => Err(String::from(
+            "expected superseded, compromised, lost or retired",
+        )),
+    }
+}
+
+#[derive(Args)]
+pub struct PayloadArgs {
+    /// A key proof from the keyset, for its root, its network and the ChangeRule in force.
+    #[arg(long)]
+    proof: PathBuf,
+    /// Also write the bytes to sign to this file.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    #[command(subcommand)]
+    command: PayloadCommand,
+}
+
+#[derive(Subcommand)]
+enum PayloadCommand {
+    /// Revoke the proof's key, or replace it with a new key.
+    RevokeKey {
+        #[arg(long, value_parser = parse_agent)]
+        new_key: Option<AgentPubKey>,
+        /// superseded, compromised, lost or retired. Defaults to superseded when there is a
+        /// new key and retired otherwise.
+        #[arg(long, value_parser = parse_reason)]
+        reason: Option<RevocationReason>,
+        /// For a compromised key, when the compromise began, in microseconds since the
+        /// Unix epoch.
+        #[arg(long)]
+        compromised_since: Option<i64>,
+    },
+    /// Admit a new device to a keyset none of whose devices remain.
+    RecoverKeyset {
+        #[arg(long, value_parser = parse_agent)]
+        invitee: AgentPubKey,
+    },
+    /// Veto an update of the ChangeRule in force before it takes effect.
+    Veto {
+        #[arg(long, value_parser = parse_action_hash)]
+        change_rule_update: ActionHash,
+    },
+    /// Lift a freeze of the keyset.
+    Unfreeze {
+        #[arg(long, value_parser = parse_action_hash)]
+        keyset_freeze: ActionHash,
+    },
+    /// Replace the ChangeRule in force with the specs in a JSON file.
+    SpecChange {
+        #[arg(long)]
+        specs: PathBuf,
+    },
+}
+
+// The specs a ChangeRule update adopts, in the form they take in an AuthorizedSpecChange.
+#[derive(Deserialize)]
+struct NewSpecs {
+    new_spec: AuthoritySpec,
+    #[serde(default)]
+    operation_specs: OperationSpecs,
+    #[serde(default)]
+    update_delay_secs: u64,
+}
+
+/// Prints the payload the signers of the keyset's ChangeRule in force must sign for an
+/// operation, the bytes they sign, and which of them are needed.
+pub fn compute_payload(args: PayloadArgs) -> Result<()> {
+    let key_proof = Artifact::read_key_proof(&args.proof)?;
+    let verified_key = verify_key_proof(&key_proof)?;
+    let change_rule_record = key_proof
+        .change_rules
+        .last()
+        .context("The key proof has no ChangeRules")?;
+    let change_rule: ChangeRule = entry_of(change_rule_record)?;
+    let keyset_root = verified_key.keyset_root;
+    let dna_hash = verified_key.dna_hash;
+
+    let (operation, payload) = match args.command {
+        PayloadCommand::RevokeKey {
+            new_key,
+            reason,
+            compromised_since,
+        } => {
+            let operation = match new_key {
+                Some(_) => AuthorityOperation::KeyReplacement,
+                None => AuthorityOperation::KeyRevocation,
+            };
+            let reason = reason.unwrap_or(match new_key {
+                Some(_) => RevocationReason::Superseded,
+                None => RevocationReason::Retired,
+            });
+            let payload = SigningPayload::from(KeyRevocationPayload {
+                keyset_root,
+                dna_hash,
+                prior_key_registration: key_proof.key_registration.action_address().clone(),
+                change_rule: verified_key.change_rule,
+                new_key,
+                reason,
+                compromised_since: compromised_since.map(Timestamp::from_micros),
+            });
+            (operation, payload)
+        }
+        PayloadCommand::RecoverKeyset { invitee } => {
+            let payload = SigningPayload::from(KeysetRecoveryPayload {
+                keyset_root,
+                dna_hash,
+                change_rule: verified_key.change_rule,
+                invitee,
+            });
+            (AuthorityOperation::DeviceInvite, payload)
+        }
+        PayloadCommand::Veto { change_rule_update } => {
+            let payload = SigningPayload::from(ChangeRuleVetoPayload {
+                keyset_root,
+                dna_hash,
+                change_rule_update,
+            });
+            (AuthorityOperation::ChangeRuleUpdate, payload)
+        }
+        PayloadCommand::Unfreeze { keyset_freeze } => {
+            let payload = SigningPayload::from(KeysetUnfreezePayload {
+                keyset_root,
+                dna_hash,
+                keyset_freeze,
+                change_rule: verified_key.change_rule,
+            });
+            (AuthorityOperation::ChangeRuleUpdate, payload)
+        }
+        PayloadCommand::SpecChange { specs } => {
+            let json = std::fs::read_to_string(&specs)
+                .with_context(|| format!("Reading {}", specs.display()))?;
+            let new_specs: NewSpecs = serde_json::from_str(&json)
+                .with_context(|| format!("Parsing {}", specs.display()))?;
+            let payload = SigningPayload::from(SpecChangePayload {
+                keyset_root: Some(keyset_root),
+                dna_hash,
+                prev_change_rule: Some(verified_key.change_rule),
+                revision: change_rule.revision + 1,
+                new_spec: new_specs.new_spec,
+                operation_specs: new_specs.operation_specs,
+                update_delay_secs: new_specs.update_delay_secs,
+            });
+            (AuthorityOperation::ChangeRuleUpdate, payload)
+        }
+    };
+
+    let bytes = payload.signable_bytes()?;
+    println!("Payload {}", payload.domain());
+    println!("{:#?}", payload);
+    println!(
+        "Signers of ChangeRule revision {} {}",
+        change_rule.revision,
+        change_rule_record.action_address()
+    );
+    print_spec("  ", change_rule.spec_change.spec_for(operation));
+    println!("Bytes to sign");
+    println!(
+        "{}",
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    );
+    if let Some(out) = args.out {
+        std::fs::write(&out, &bytes).with_context(|| format!("Writing {}", out.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reasons_parse() {
+        assert_eq!(parse_reason("lost"), Ok(RevocationReason::Lost));
+        assert_eq!(parse_reason("retired"), Ok(RevocationReason::Retired));
+        assert!(parse_reason("Lost").is_err());
+    }
+
+    #[test]
+    fn hashes_parse_from_base64() {
+        let agent = AgentPubKey::from_raw_32(vec![1; 32]);
+        let b64 = AgentPubKeyB64::from(agent.clone()).to_string();
+        assert_eq!(parse_agent(&b64), Ok(agent));
+        let action_hash = ActionHash::from_raw_32(vec![2; 32]);
+        let b64 = ActionHashB64::from(action_hash.clone()).to_string();
+        assert_eq!(parse_action_hash(&b64), Ok(action_hash));
+        assert!(parse_agent(&b64).is_err());
+        assert!(parse_action_hash("not base64").is_err());
+    }
+
+    #[test]
+    fn new_specs_default_their_operation_specs_and_delay() {
+        let new_spec = AuthoritySpec::new(1, vec![AgentPubKey::from_raw_32(vec![1; 32])]);
+        let json = serde_json::json!({ "new_spec": new_spec }).to_string();
+        let new_specs: NewSpecs = serde_json::from_str(&json).unwrap();
+        assert_eq!(new_specs.new_spec, new_spec);
+        assert_eq!(new_specs.operation_specs, OperationSpecs::default());
+        assert_eq!(new_specs.update_delay_secs, 0);
+    }
+}
@@ -0,0 +1,273 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use std::collections::BTreeSet;
+
+use holo_hash::AgentPubKey;
+use holochain_integrity_types::prelude::Signature;
+use serde::{Deserialize, Serialize};
+
+/// Signers are addressed by a u8 position, so a spec can list no more than this many.
+pub const MAX_AUTHORIZED_SIGNERS: usize = u8::MAX as usize;
+
+// Represents an M:N multisignature spec.
+// The trivial case 1:1 represents a single agent to sign.
+// We need an entry to define the rules of authority
+// (for authorizing or revoking) keys in the space under a KeysetRoot.
+// This is only committed by the first Deepkey agent.
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct AuthoritySpec {
+    // set to 1 for a single signer scenario
+    pub sigs_required: u8,
+    // These signers may not exist on the DHT.
+    // E.g. a revocation key used to create the first change rule.
+    pub authorized_signers: Vec<AgentPubKey>,
+    // Replaces the flat `sigs_required` of `authorized_signers` when set.
+    #[serde(default)]
+    pub policy: Option<AuthorityPolicy>,
+    // When set, a ChangeRule update adopting this spec is invalid until enough of the
+    // signers to satisfy it have committed a GuardianAcceptance of it.
+    #[serde(default)]
+    pub requires_consent: bool,
+}
+impl AuthoritySpec {
+    pub fn new(sigs_required: u8, authorized_signers: Vec<AgentPubKey>) -> Self {
+        Self {
+            sigs_required,
+            authorized_signers,
+            policy: None,
+            requires_consent: false,
+        }
+    }
+    pub fn new_with_policy(authorized_signers: Vec<AgentPubKey>, policy: AuthorityPolicy) -> Self {
+        Self {
+            sigs_required: 0,
+            authorized_signers,
+            policy: Some(policy),
+            requires_consent: false,
+        }
+    }
+
+    pub fn with_required_consent(mut self) -> Self {
+        self.requires_consent = true;
+        self
+    }
+
+    /// The policy in force: the explicit one, or `sigs_required` of all the signers.
+    /// Signers beyond `MAX_AUTHORIZED_SIGNERS`, which validation refuses, are left out.
+    pub fn policy(&self) -> AuthorityPolicy {
+        match &self.policy {
+            Some(policy) => policy.clone(),
+            None => AuthorityPolicy::Threshold {
+                required: self.sigs_required,
+                policies: (0..self.authorized_signers.len().min(MAX_AUTHORIZED_SIGNERS))
+                    .map(|position| AuthorityPolicy::Signer(position as u8))
+                    .collect(),
+            },
+        }
+    }
+}
+
+// The operations a ChangeRule authorizes, each of which may have its own spec.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorityOperation {
+    ChangeRuleUpdate,
+    KeyRevocation,
+    KeyReplacement,
+    DeviceInvite,
+}
+
+impl AuthorityOperation {
+    pub const ALL: [AuthorityOperation; 4] = [
+        AuthorityOperation::ChangeRuleUpdate,
+        AuthorityOperation::KeyRevocation,
+        AuthorityOperation::KeyReplacement,
+        AuthorityOperation::DeviceInvite,
+    ];
+}
+
+// Specs for individual operations, e.g. one signature for routine key rotation but three
+// guardians for changing the guardians. An unset operation falls back to the default spec.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct OperationSpecs {
+    pub change_rule_update: Option<AuthoritySpec>,
+    pub key_revocation: Option<AuthoritySpec>,
+    pub key_replacement: Option<AuthoritySpec>,
+    pub device_invite: Option<AuthoritySpec>,
+}
+
+impl OperationSpecs {
+    pub fn get(&self, operation: AuthorityOperation) -> Option<&AuthoritySpec> {
+        match operation {
+            AuthorityOperation::ChangeRuleUpdate => self.change_rule_update.as_ref(),
+            AuthorityOperation::KeyRevocation => self.key_revocation.as_ref(),
+            AuthorityOperation::KeyReplacement => self.key_replacement.as_ref(),
+            AuthorityOperation::DeviceInvite => self.device_invite.as_ref(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AuthoritySpec> {
+        [
+            &self.change_rule_update,
+            &self.key_revocation,
+            &self.key_replacement,
+            &self.device_invite,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+// An expression over the positions of a spec's authorized signers.
+// "The revocation key (0), or 2 of 3 guardians (1, 2, 3) and the hardware key (4)" is:
+//
+// Any([
+//     Signer(0),
+//     All([Threshold { required: 2, policies: [Signer(1), Signer(2), Signer(3)] }, Signer(4)]),
+// ])
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AuthorityPolicy {
+    // Met by a signature of the authorized signer at this position.
+    Signer(u8),
+    // Met when every sub-policy is met.
+    All(Vec<AuthorityPolicy>),
+    // Met when any sub-policy is met.
+    Any(Vec<AuthorityPolicy>),
+    // Met when at least `required` of the sub-policies are met.
+    Threshold {
+        required: u8,
+        policies: Vec<AuthorityPolicy>,
+    },
+    // Met when the weights of the met sub-policies add up to at least `required`.
+    WeightedThreshold {
+        required: u32,
+        policies: Vec<(u32, AuthorityPolicy)>,
+    },
+}
+
+impl AuthorityPolicy {
+    /// Whether the signers at the `signed` positions meet this policy.
+    pub fn is_satisfied_by(&self, signed: &BTreeSet<u8>) -> bool {
+        match self {
+            Self::Signer(position) => signed.contains(position),
+            Self::All(policies) => policies.iter().all(|policy| policy.is_satisfied_by(signed)),
+            Self::Any(policies) => policies.iter().any(|policy| policy.is_satisfied_by(signed)),
+            Self::Threshold { required, policies } => {
+                policies
+                    .iter()
+                    .filter(|policy| policy.is_satisfied_by(signed))
+                    .count()
+                    >= *required as usize
+            }
+            Self::WeightedThreshold { required, policies } => {
+                policies
+                    .iter()
+                    .filter(|(_, policy)| policy.is_satisfied_by(signed))
+                    .map(|(weight, _)| *weight as u64)
+                    .sum::<u64>()
+                    >= *required as u64
+            }
+        }
+    }
+}
+
+pub type Authorization = (u8, Signature);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(positions: &[u8]) -> BTreeSet<u8> {
+        positions.iter().copied().collect()
+    }
+
+    fn signers(count: usize) -> Vec<AgentPubKey> {
+        (0..count)
+            .map(|i| AgentPubKey::from_raw_36(vec![i as u8; 36]))
+            .collect()
+    }
+
+    // The revocation key (0), or 2 of 3 guardians (1, 2, 3) and the hardware key (4).
+    fn nested_policy() -> AuthorityPolicy {
+        AuthorityPolicy::Any(vec![
+            AuthorityPolicy::Signer(0),
+            AuthorityPolicy::All(vec![
+                AuthorityPolicy::Threshold {
+                    required: 2,
+                    policies: vec![
+                        AuthorityPolicy::Signer(1),
+                        AuthorityPolicy::Signer(2),
+                        AuthorityPolicy::Signer(3),
+                    ],
+                },
+                AuthorityPolicy::Signer(4),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn nested_policy_is_met_by_either_branch() {
+        let policy = nested_policy();
+        assert!(policy.is_satisfied_by(&signed(&[0])));
+        assert!(policy.is_satisfied_by(&signed(&[1, 3, 4])));
+        assert!(!policy.is_satisfied_by(&signed(&[1, 2])));
+        assert!(!policy.is_satisfied_by(&signed(&[3, 4])));
+        assert!(!policy.is_satisfied_by(&signed(&[])));
+    }
+
+    #[test]
+    fn weighted_threshold_adds_up_met_weights() {
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: 5,
+            policies: vec![
+                (3, AuthorityPolicy::Signer(0)),
+                (2, AuthorityPolicy::Signer(1)),
+                (1, AuthorityPolicy::Signer(2)),
+            ],
+        };
+        assert!(policy.is_satisfied_by(&signed(&[0, 1])));
+        assert!(!policy.is_satisfied_by(&signed(&[0, 2])));
+        assert!(!policy.is_satisfied_by(&signed(&[1, 2])));
+        assert!(policy.is_satisfied_by(&signed(&[0, 1, 2])));
+    }
+
+    #[test]
+    fn weights_do_not_overflow() {
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: u32::MAX,
+            policies: vec![
+                (u32::MAX, AuthorityPolicy::Signer(0)),
+                (u32::MAX, AuthorityPolicy::Signer(1)),
+            ],
+        };
+        assert!(policy.is_satisfied_by(&signed(&[0, 1])));
+    }
+
+    #[test]
+    fn flat_spec_requires_sigs_required_of_its_signers() {
+        let policy = AuthoritySpec::new(2, signers(3)).policy();
+        assert!(policy.is_satisfied_by(&signed(&[0, 2])));
+        assert!(!policy.is_satisfied_by(&signed(&[1])));
+        // Positions outside the spec count for nothing.
+        assert!(!policy.is_satisfied_by(&signed(&[1, 7])));
+    }
+
+    #[test]
+    fn explicit_policy_replaces_sigs_required() {
+        let spec = AuthoritySpec::new_with_policy(signers(5), nested_policy());
+        assert_eq!(spec.policy(), nested_policy());
+    }
+
+    #[test]
+    fn flat_policy_stops_at_the_last_addressable_signer() {
+        let spec = AuthoritySpec::new(1, signers(MAX_AUTHORIZED_SIGNERS + 2));
+        match spec.policy() {
+            AuthorityPolicy::Threshold { policies, .. } => {
+                assert_eq!(policies.len(), MAX_AUTHORIZED_SIGNERS);
+                assert_eq!(policies.last(), Some(&AuthorityPolicy::Signer(u8::MAX - 1)));
+            }
+            policy => panic!("unexpected policy {:?}", policy),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::ActionHash;
+use serde::{Deserialize, Serialize};
+
+use crate::{AuthorityOperation, AuthoritySpec, Authorization, OperationSpecs};
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct AuthorizedSpecChange {
+    // The default spec, for every operation without one in `operation_specs`.
+    pub new_spec: AuthoritySpec,
+    #[serde(default)]
+    pub operation_specs: OperationSpecs,
+    // How long an update replacing these specs waits before it takes effect, if longer
+    // than the network's delay.
+    #[serde(default)]
+    pub update_delay_secs: u64,
+    // Signature of the content of the authority_spec field,
+    // signed by throwaway RootKey on Create,
+    // or according to previous AuthSpec upon Update.
+    pub authorization_of_new_spec: Vec<Authorization>,
+}
+impl AuthorizedSpecChange {
+    pub fn new(new_spec: AuthoritySpec, authorization_of_new_spec: Vec<Authorization>) -> Self {
+        Self {
+            new_spec,
+            operation_specs: OperationSpecs::default(),
+            update_delay_secs: 0,
+            authorization_of_new_spec,
+        }
+    }
+    pub fn new_with_operation_specs(
+        new_spec: AuthoritySpec,
+        operation_specs: OperationSpecs,
+        authorization_of_new_spec: Vec<Authorization>,
+    ) -> Self {
+        Self {
+            new_spec,
+            operation_specs,
+            update_delay_secs: 0,
+            authorization_of_new_spec,
+        }
+    }
+    pub fn with_update_delay_secs(mut self, update_delay_secs: u64) -> Self {
+        self.update_delay_secs = update_delay_secs;
+        self
+    }
+    /// The spec governing `operation`, falling back to the default spec.
+    pub fn spec_for(&self, operation: AuthorityOperation) -> &AuthoritySpec {
+        self.operation_specs
+            .get(operation)
+            .unwrap_or(&self.new_spec)
+    }
+    pub fn as_new_spec_ref(&self) -> &AuthoritySpec {
+        &self.new_spec
+    }
+    pub fn as_authorization_of_new_spec_ref(&self) -> &Vec<Authorization> {
+        &self.authorization_of_new_spec
+    }
+}
+
+// The author needs to be linked from the KeysetRoot
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct ChangeRule {
+    pub keyset_root: ActionHash,
+    pub keyset_leaf: ActionHash,
+    // 0 for the first ChangeRule of a keyset, incremented by every update.
+    pub revision: u32,
+    pub spec_change: AuthorizedSpecChange,
+    // GuardianAcceptances of the new specs, for those that require their signers'
+    // consent. The latest cited decision of each signer counts.
+    #[serde(default)]
+    pub guardian_acceptances: Vec<ActionHash>,
+    // ChangeRuleVetoes of the rule being replaced or of its other updates. Only cited vetoes
+    // and those on the author's chain are enforced; any other veto is advisory.
+    #[serde(default)]
+    pub vetoes: Vec<ActionHash>,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+}
+
+impl ChangeRule {
+    pub fn new(
+        keyset_root: ActionHash,
+        keyset_leaf: ActionHash,
+        revision: u32,
+        spec_change: AuthorizedSpecChange,
+    ) -> Self {
+        Self {
+            keyset_root,
+            keyset_leaf,
+            revision,
+            spec_change,
+            guardian_acceptances: Vec::new(),
+            vetoes: Vec::new(),
+            keyset_freezes: Vec::new(),
+        }
+    }
+}
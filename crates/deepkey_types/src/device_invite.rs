@@ -0,0 +1,112 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::{ActionHash, AgentPubKey};
+use holochain_integrity_types::prelude::Signature;
+use serde::{Deserialize, Serialize};
+
+use crate::Authorization;
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct DeviceInvite {
+    pub keyset_root: ActionHash,
+    // Either the KeysetRoot or the DeviceInviteAcceptance
+    pub parent: ActionHash,
+    pub invitee: AgentPubKey,
+    // Set when the keyset's ChangeRule signers issue the invite directly, so a keyset
+    // whose devices are all lost can be recovered, e.g. with the offline revocation key.
+    pub recovery: Option<RecoveryAuthorization>,
+    // The inviting device's signature of a DeviceInvitePayload, on any other invite.
+    #[serde(default)]
+    pub inviter_signature: Option<Signature>,
+    // On any other invite, the ChangeRule in force when it was issued. If that rule has its
+    // own `device_invite` spec, `authorization` holds its signers' signatures of the
+    // DeviceInvitePayload.
+    #[serde(default)]
+    pub change_rule: Option<ActionHash>,
+    #[serde(default)]
+    pub authorization: Vec<Authorization>,
+    // ChangeRuleVetoes of the ChangeRule the invite cites, or of its updates.
+    #[serde(default)]
+    pub vetoes: Vec<ActionHash>,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+}
+
+// The ChangeRule of the keyset whose signers approved a recovery invite, and their
+// signatures of a KeysetRecoveryPayload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecoveryAuthorization {
+    pub change_rule: ActionHash,
+    pub authorization: Vec<Authorization>,
+}
+
+impl DeviceInvite {
+    /// An invite of `invitee` below `parent` under `change_rule`, signed by the inviting
+    /// device and, if the rule asks for it, approved by its `device_invite` signers.
+    pub fn new(
+        keyset_root: ActionHash,
+        parent: ActionHash,
+        invitee: AgentPubKey,
+        inviter_signature: Signature,
+        change_rule: ActionHash,
+        authorization: Vec<Authorization>,
+    ) -> Self {
+        Self {
+            keyset_root,
+            parent,
+            invitee,
+            recovery: None,
+            inviter_signature: Some(inviter_signature),
+            change_rule: Some(change_rule),
+            authorization,
+            vetoes: Vec::new(),
+            keyset_freezes: Vec::new(),
+        }
+    }
+
+    /// An invite of `invitee` straight under the KeysetRoot, approved by the ChangeRule signers.
+    pub fn new_recovery(
+        keyset_root: ActionHash,
+        invitee: AgentPubKey,
+        recovery: RecoveryAuthorization,
+    ) -> Self {
+        Self {
+            parent: keyset_root.clone(),
+            keyset_root,
+            invitee,
+            recovery: Some(recovery),
+            inviter_signature: None,
+            change_rule: None,
+            authorization: Vec::new(),
+            vetoes: Vec::new(),
+            keyset_freezes: Vec::new(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct DeviceInviteAcceptance {
+    /// The KSRA for the invite being accepted.
+    /// Not strictly required for validation as this is on the DeviceInvite.
+    /// This is here as it may save network hops other than during.
+    pub keyset_root_authority: ActionHash,
+    pub invite: ActionHash,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+}
+
+impl DeviceInviteAcceptance {
+    pub fn new(keyset_root_authority: ActionHash, invite: ActionHash) -> Self {
+        Self {
+            keyset_root_authority,
+            invite,
+            keyset_freezes: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use holochain_serialized_bytes::SerializedBytesError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("Unsupported RevocationList version {0}")]
+    UnsupportedRevocationListVersion(u8),
+
+    #[error("The RevocationList has an invalid signature")]
+    BadRevocationListSignature,
+
+    #[error("Unsupported KeyProof version {0}")]
+    UnsupportedKeyProofVersion(u8),
+
+    #[error("Malformed KeyProof: {0}")]
+    MalformedKeyProof(String),
+
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+}
+
+impl From<SerializedBytesError> for Error {
+    fn from(e: SerializedBytesError) -> Error {
+        Error::Serialization(e.to_string())
+    }
+}
@@ -0,0 +1,64 @@
+use holo_hash::{AgentPubKey, DnaHash};
+use holochain_integrity_types::prelude::{Record, Timestamp};
+use holochain_serialized_bytes::{decode, encode};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, from_json};
+
+/// Bumped whenever the layout of a KeyProof changes.
+pub const KEY_PROOF_VERSION: u8 = 1;
+
+// One hop down the keyset tree: an invite and the acceptance that made its invitee a
+// device of the keyset.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InviteProof {
+    pub device_invite: Record,
+    pub device_invite_acceptance: Record,
+}
+
+// Everything needed to check, without network access, that `key` belongs to the keyset:
+// the signed records from the KeysetRoot down to the registration of the key.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyProof {
+    pub version: u8,
+    pub dna_hash: DnaHash,
+    pub key: AgentPubKey,
+    pub keyset_root: Record,
+    // The keyset's ChangeRules, from the one created with the KeysetRoot to the one in
+    // force when the proof was exported.
+    pub change_rules: Vec<Record>,
+    // From the KeysetRoot down to the device that registered the key. Empty when that
+    // device is the first deepkey agent.
+    pub invites: Vec<InviteProof>,
+    pub key_registration: Record,
+    pub exported_at: Timestamp,
+}
+
+impl KeyProof {
+    /// The binary form: the proof encoded as MessagePack, like every Holochain entry.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        encode(self).map_err(|e| Error::MalformedKeyProof(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let key_proof: Self = decode(bytes).map_err(|e| Error::MalformedKeyProof(e.to_string()))?;
+        key_proof.check_version()
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::MalformedKeyProof(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let key_proof: Self =
+            from_json(json.as_bytes()).map_err(|e| Error::MalformedKeyProof(e.to_string()))?;
+        key_proof.check_version()
+    }
+
+    fn check_version(self) -> Result<Self, Error> {
+        if self.version != KEY_PROOF_VERSION {
+            return Err(Error::UnsupportedKeyProofVersion(self.version));
+        }
+        Ok(self)
+    }
+}
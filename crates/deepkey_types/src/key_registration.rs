@@ -0,0 +1,75 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::{ActionHash, AgentPubKey};
+use holochain_integrity_types::prelude::{Signature, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::Authorization;
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct KeyGeneration {
+    pub new_key: AgentPubKey,
+    // The new key signing a KeyGenerationPayload of the author, their keyset and the
+    // timestamp.
+    pub new_key_signing_of_author: Signature,
+    // When the new key made that signature.
+    pub timestamp: Timestamp,
+    // The update or delete of the new key's KeyAnchor that released it from its last
+    // registration, on any chain, or None if it was never registered.
+    #[serde(default)]
+    pub prior_key_anchor: Option<ActionHash>,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+    // TODO
+    // generator: ActionHash, // This is the key authorized to generate new keys on this chain
+    // generator_signature: Signature, // The generator key signing the new key
+}
+
+// Why a key was revoked, so that a key rotated in the normal course can be told apart from
+// one whose recent signatures must not be trusted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevocationReason {
+    // Replaced by a new key in the normal course.
+    Superseded,
+    // The private key is known or suspected to be in other hands.
+    Compromised,
+    // The private key is no longer available, but not known to be in other hands.
+    Lost,
+    // No longer used.
+    Retired,
+}
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct KeyRevocation {
+    pub prior_key_registration: ActionHash,
+    // The ChangeRule of the revoked key's keyset whose signers authorize this revocation.
+    pub change_rule: ActionHash,
+    // Signatures of a KeyRevocationPayload by the ChangeRule's authorized signers.
+    pub revocation_authorization: Vec<Authorization>,
+    pub reason: RevocationReason,
+    // For a compromised key, when the compromise began. Signatures made from then on are
+    // not to be trusted, even if they predate this revocation.
+    #[serde(default)]
+    pub compromised_since: Option<Timestamp>,
+    // ChangeRuleVetoes of `change_rule` or of its updates.
+    #[serde(default)]
+    pub vetoes: Vec<ActionHash>,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+}
+
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub enum KeyRegistration {
+    Create(KeyGeneration), // Creates a key under management of current KSR on this chain
+    CreateOnly(KeyGeneration), // Keys for hosted web users may be of this type, cannot replace/revoke
+    Update(KeyRevocation, KeyGeneration), // revokes a key and replaces it with a newly generated one
+    Delete(KeyRevocation), // permanently revokes a key (Note: still uses an update action.)
+}
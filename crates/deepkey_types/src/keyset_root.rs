@@ -0,0 +1,38 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::AgentPubKey;
+use holochain_integrity_types::prelude::Signature;
+use serde::{Deserialize, Serialize};
+
+// A keyset, created by the first Deepkey agent with a throwaway root key.
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct KeysetRoot {
+    pub first_deepkey_agent: AgentPubKey,
+    /// The private key is thrown away.
+    root_pub_key: AgentPubKey,
+    fda_pubkey_signed_by_root_key: Signature,
+}
+impl KeysetRoot {
+    pub fn new(
+        first_deepkey_agent: AgentPubKey,
+        root_pub_key: AgentPubKey,
+        fda_pubkey_signed_by_root_key: Signature,
+    ) -> Self {
+        Self {
+            first_deepkey_agent,
+            root_pub_key,
+            fda_pubkey_signed_by_root_key,
+        }
+    }
+
+    pub fn root_pub_key(&self) -> &AgentPubKey {
+        &self.root_pub_key
+    }
+
+    /// The root key's signature of the `KeysetRootPayload` naming the first deepkey agent.
+    pub fn fda_pubkey_signed_by_root_key(&self) -> &Signature {
+        &self.fda_pubkey_signed_by_root_key
+    }
+}
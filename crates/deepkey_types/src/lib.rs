@@ -0,0 +1,28 @@
+//! The data types of Deepkey: its entries, the payloads its keys sign, and the documents
+//! it exports, such as a `KeyProof` or a `SignedRevocationList`.
+//!
+//! `deepkey_integrity` builds its entry types from these, and `deepkey_verify` checks
+//! exported records against them without a conductor, so the two cannot disagree about
+//! the bytes that were signed. With the `hdi` feature the entries also get the Holochain
+//! entry helpers; without it they are plain serde data, and nothing here needs the host.
+pub mod authority_spec;
+pub use authority_spec::*;
+pub mod change_rule;
+pub use change_rule::*;
+pub mod device_invite;
+pub use device_invite::*;
+pub mod error;
+pub mod json;
+pub use json::*;
+pub mod key_proof;
+pub use key_proof::*;
+pub mod key_registration;
+pub use key_registration::*;
+pub mod keyset_root;
+pub use keyset_root::*;
+pub mod recovery_share;
+pub use recovery_share::*;
+pub mod revocation_list;
+pub use revocation_list::*;
+pub mod signing;
+pub use signing::*;
@@ -0,0 +1,47 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+use holochain_integrity_types::prelude::{Record, Timestamp};
+use serde::{Deserialize, Serialize};
+
+// An X25519 public key an agent holds in its keystore, published so that recovery shares
+// can be encrypted to it.
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct RecoveryEncryptionKey {
+    pub encryption_key: [u8; 32],
+}
+
+// One share of a secret-shared revocation seed, encrypted from the author's
+// RecoveryEncryptionKey to the recipient's. The owner of the seed hands a share to each
+// guardian; a guardian releases theirs by re-encrypting it to a recovering device, naming
+// the share it was entrusted with in `released_from`.
+#[cfg_attr(feature = "hdi", hdk_entry_helper)]
+#[cfg_attr(not(feature = "hdi"), derive(Serialize, Deserialize, Debug))]
+#[derive(Clone, PartialEq)]
+pub struct RecoveryShare {
+    pub recipient: AgentPubKey,
+    pub recipient_encryption_key: ActionHash,
+    pub sender_encryption_key: ActionHash,
+    pub nonce: [u8; 24],
+    pub encrypted_share: Vec<u8>,
+    pub released_from: Option<ActionHash>,
+}
+
+/// Bumped whenever the layout of a RecoveryManifest changes.
+pub const RECOVERY_MANIFEST_VERSION: u8 = 1;
+
+// The guardians entrusted with shares of a revocation seed, for its owner to keep with
+// their recovery instructions. The shares stay encrypted; the manifest lists the signed
+// RecoveryShare records and the threshold the seed was split with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecoveryManifest {
+    pub version: u8,
+    pub dna_hash: DnaHash,
+    pub owner: AgentPubKey,
+    // As declared by the owner, since it cannot be read from the encrypted shares.
+    pub threshold: u8,
+    pub recovery_shares: Vec<Record>,
+    pub exported_at: Timestamp,
+}
@@ -0,0 +1,58 @@
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+use holochain_integrity_types::prelude::{Signature, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, verify_ed25519, RevocationReason, SigningPayload};
+
+/// Bumped whenever the layout of a RevocationList changes.
+pub const REVOCATION_LIST_VERSION: u8 = 1;
+
+// A key of the keyset that was revoked or replaced, as recorded by the KeyRegistration
+// update that did so.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RevokedKey {
+    pub key: AgentPubKey,
+    pub key_revocation: ActionHash,
+    pub revoked_at: Timestamp,
+    pub reason: RevocationReason,
+    pub compromised_since: Option<Timestamp>,
+    pub replaced_by: Option<AgentPubKey>,
+}
+
+// Every key of a keyset revoked or replaced as of `issued_at`, oldest revocation first.
+// Of two lists for the same keyset, the one issued later supersedes the other.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RevocationList {
+    pub version: u8,
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub issued_at: Timestamp,
+    pub revoked_keys: Vec<RevokedKey>,
+}
+
+// A RevocationList with the signature of the device that exported it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedRevocationList {
+    pub revocation_list: RevocationList,
+    pub signer: AgentPubKey,
+    // The signer's signature of `SigningPayload::RevocationList(revocation_list)`.
+    pub signature: Signature,
+}
+
+impl SignedRevocationList {
+    /// Checks the format version and the signature, without a conductor.
+    /// That the signer is a device of the keyset has to be established separately, e.g.
+    /// from a proof that its key belongs to the keyset.
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.revocation_list.version != REVOCATION_LIST_VERSION {
+            return Err(Error::UnsupportedRevocationListVersion(
+                self.revocation_list.version,
+            ));
+        }
+        let data = SigningPayload::from(self.revocation_list.clone()).signable_bytes()?;
+        if !verify_ed25519(&self.signer, &self.signature, &data) {
+            return Err(Error::BadRevocationListSignature);
+        }
+        Ok(())
+    }
+}
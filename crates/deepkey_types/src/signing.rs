@@ -0,0 +1,245 @@
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+use holochain_integrity_types::prelude::{Signature, Timestamp};
+use holochain_serialized_bytes::{encode, SerializedBytesError};
+use serde::{Deserialize, Serialize};
+
+use crate::{AuthoritySpec, OperationSpecs, RevocationList, RevocationReason};
+
+/// Bumped whenever the encoding of any signing payload changes, so signatures
+/// over an older encoding can never verify against a newer one.
+pub const SIGNING_PAYLOAD_VERSION: u8 = 1;
+
+// Everything Deepkey asks a key to sign.
+// Each operation has its own payload carrying the keyset root, the DNA hash and
+// the hash of the state it moves on from, and the signed bytes are prefixed with
+// a tag naming the operation and version. A signature collected for one purpose,
+// keyset or network therefore cannot be replayed for another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SigningPayload {
+    KeysetRoot(KeysetRootPayload),
+    SpecChange(Box<SpecChangePayload>),
+    KeyRevocation(KeyRevocationPayload),
+    DeviceInvite(DeviceInvitePayload),
+    KeyGeneration(KeyGenerationPayload),
+    ExternalAuthority(ExternalAuthorityPayload),
+    KeysetRecovery(KeysetRecoveryPayload),
+    ChangeRuleVeto(ChangeRuleVetoPayload),
+    KeysetUnfreeze(KeysetUnfreezePayload),
+    RevocationList(RevocationList),
+}
+
+impl SigningPayload {
+    pub fn operation(&self) -> &'static str {
+        match self {
+            Self::KeysetRoot(_) => "keyset_root",
+            Self::SpecChange(_) => "spec_change",
+            Self::KeyRevocation(_) => "key_revocation",
+            Self::DeviceInvite(_) => "device_invite",
+            Self::KeyGeneration(_) => "key_generation",
+            Self::ExternalAuthority(_) => "external_authority",
+            Self::KeysetRecovery(_) => "keyset_recovery",
+            Self::ChangeRuleVeto(_) => "change_rule_veto",
+            Self::KeysetUnfreeze(_) => "keyset_unfreeze",
+            Self::RevocationList(_) => "revocation_list",
+        }
+    }
+
+    /// The domain tag, e.g. `deepkey/spec_change/v1`.
+    pub fn domain(&self) -> String {
+        format!("deepkey/{}/v{}", self.operation(), SIGNING_PAYLOAD_VERSION)
+    }
+
+    /// The exact bytes to be signed: the domain tag followed by the MessagePack encoding of
+    /// the payload.
+    pub fn signable_bytes(&self) -> Result<Vec<u8>, SerializedBytesError> {
+        let mut bytes = self.domain().into_bytes();
+        bytes.extend(encode(self)?);
+        Ok(bytes)
+    }
+
+    /// `signable_bytes` as an `ExternResult`, for validation and zome calls.
+    #[cfg(feature = "hdi")]
+    pub fn to_signable_bytes(&self) -> ExternResult<Vec<u8>> {
+        self.signable_bytes().map_err(|e| wasm_error!(e))
+    }
+}
+
+/// Checks an Ed25519 signature in plain Rust, for verifying exported documents without a
+/// conductor. Validation uses `verify_signature_raw` instead.
+pub fn verify_ed25519(key: &AgentPubKey, signature: &Signature, data: &[u8]) -> bool {
+    let key_bytes: &[u8; 32] = match key.get_raw_32().try_into() {
+        Ok(key_bytes) => key_bytes,
+        Err(_) => return false,
+    };
+    match ed25519_dalek::VerifyingKey::from_bytes(key_bytes) {
+        Ok(verifying_key) => verifying_key
+            .verify_strict(data, &ed25519_dalek::Signature::from_bytes(&signature.0))
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// The throwaway root key signing the first Deepkey agent when a keyset is created.
+// There is no keyset root or previous state yet; the root key itself is unique
+// to the keyset.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeysetRootPayload {
+    pub dna_hash: DnaHash,
+    pub first_deepkey_agent: AgentPubKey,
+}
+
+// The authorizers of a ChangeRule approving the spec that replaces it, as the
+// given revision of the keyset's ChangeRule.
+// The genesis spec is signed by the root key before the KeysetRoot exists, so
+// only then are `keyset_root` and `prev_change_rule` None.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpecChangePayload {
+    pub keyset_root: Option<ActionHash>,
+    pub dna_hash: DnaHash,
+    pub prev_change_rule: Option<ActionHash>,
+    pub revision: u32,
+    pub new_spec: AuthoritySpec,
+    pub operation_specs: OperationSpecs,
+    pub update_delay_secs: u64,
+}
+
+// The authorizers of a ChangeRule approving the revocation of a registered key,
+// and its replacement by `new_key` if there is one, for the given reason.
+// Bound to the ChangeRule in force so the approval lapses when the rule is replaced.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyRevocationPayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub prior_key_registration: ActionHash,
+    pub change_rule: ActionHash,
+    pub new_key: Option<AgentPubKey>,
+    pub reason: RevocationReason,
+    pub compromised_since: Option<Timestamp>,
+}
+
+// Approval of an invite of `invitee` into the keyset below `parent`, under the ChangeRule
+// in force.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeviceInvitePayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub parent: ActionHash,
+    pub invitee: AgentPubKey,
+    pub change_rule: ActionHash,
+}
+
+// What a new key signs when it is registered, proving that the registrant
+// holds its private half.
+// Bound to the registering author and the keyset leaf their chain is under,
+// so the signature cannot be presented by another agent, keyset or network, and to
+// the prior state of the key's KeyAnchor it is registered against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyGenerationPayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub keyset_leaf: ActionHash,
+    pub author: AgentPubKey,
+    pub timestamp: Timestamp,
+    pub prior_key_anchor: Option<ActionHash>,
+}
+
+// An external authority admitting `agent` to the network until `expires_at`.
+// There is no keyset yet when an agent joins, so only the network is bound.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExternalAuthorityPayload {
+    pub dna_hash: DnaHash,
+    pub agent: AgentPubKey,
+    pub expires_at: Timestamp,
+}
+
+// The authorizers of a ChangeRule admitting `invitee` to a keyset none of whose devices
+// remain to invite it.
+// Bound to the ChangeRule in force, like a revocation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeysetRecoveryPayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub change_rule: ActionHash,
+    pub invitee: AgentPubKey,
+}
+
+// The signers of a ChangeRule vetoing an update that would replace it, before the update
+// takes effect.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChangeRuleVetoPayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub change_rule_update: ActionHash,
+}
+
+// The authorizers of a ChangeRule lifting a freeze of their keyset.
+// Bound to the ChangeRule in force, like a revocation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeysetUnfreezePayload {
+    pub keyset_root: ActionHash,
+    pub dna_hash: DnaHash,
+    pub keyset_freeze: ActionHash,
+    pub change_rule: ActionHash,
+}
+
+impl From<KeysetRootPayload> for SigningPayload {
+    fn from(payload: KeysetRootPayload) -> Self {
+        Self::KeysetRoot(payload)
+    }
+}
+
+impl From<SpecChangePayload> for SigningPayload {
+    fn from(payload: SpecChangePayload) -> Self {
+        Self::SpecChange(Box::new(payload))
+    }
+}
+
+impl From<KeyRevocationPayload> for SigningPayload {
+    fn from(payload: KeyRevocationPayload) -> Self {
+        Self::KeyRevocation(payload)
+    }
+}
+
+impl From<DeviceInvitePayload> for SigningPayload {
+    fn from(payload: DeviceInvitePayload) -> Self {
+        Self::DeviceInvite(payload)
+    }
+}
+
+impl From<KeyGenerationPayload> for SigningPayload {
+    fn from(payload: KeyGenerationPayload) -> Self {
+        Self::KeyGeneration(payload)
+    }
+}
+
+impl From<ExternalAuthorityPayload> for SigningPayload {
+    fn from(payload: ExternalAuthorityPayload) -> Self {
+        Self::ExternalAuthority(payload)
+    }
+}
+
+impl From<KeysetRecoveryPayload> for SigningPayload {
+    fn from(payload: KeysetRecoveryPayload) -> Self {
+        Self::KeysetRecovery(payload)
+    }
+}
+
+impl From<ChangeRuleVetoPayload> for SigningPayload {
+    fn from(payload: ChangeRuleVetoPayload) -> Self {
+        Self::ChangeRuleVeto(payload)
+    }
+}
+
+impl From<KeysetUnfreezePayload> for SigningPayload {
+    fn from(payload: KeysetUnfreezePayload) -> Self {
+        Self::KeysetUnfreeze(payload)
+    }
+}
+
+impl From<RevocationList> for SigningPayload {
+    fn from(payload: RevocationList) -> Self {
+        Self::RevocationList(payload)
+    }
+}
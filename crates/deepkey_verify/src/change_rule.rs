@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+
+use holo_hash::{ActionHash, DnaHash};
+use holochain_integrity_types::prelude::{Action, Record};
+
+use crate::{
+    error::Error, verify_ed25519, verify_entry_record, AuthorityOperation, Authorization,
+    ChangeRule, KeysetRoot, SigningPayload, SpecChangePayload,
+};
+
+/// Checks that the signers of `change_rule`'s spec for `operation` signed `payload` as its
+/// policy requires, as `authorize_change_rule` does in validation.
+pub fn verify_authorization(
+    change_rule: &ChangeRule,
+    operation: AuthorityOperation,
+    authorization: &[Authorization],
+    payload: &SigningPayload,
+) -> Result<(), Error> {
+    let spec = change_rule.spec_change.spec_for(operation);
+    if spec.policy.is_none() && authorization.len() != spec.sigs_required as usize {
+        return Err(Error::WrongNumberOfSignatures);
+    }
+    let data = payload.signable_bytes()?;
+    let mut signed = BTreeSet::new();
+    for (position, signature) in authorization.iter() {
+        match spec.authorized_signers.get(*position as usize) {
+            Some(agent) => {
+                if !signed.insert(*position) {
+                    return Err(Error::DuplicateAuthorization(*position));
+                }
+                if !verify_ed25519(agent, signature, &data) {
+                    return Err(Error::BadUpdateSignature);
+                }
+            }
+            None => return Err(Error::AuthorizedPositionOutOfBounds),
+        }
+    }
+    if !spec.policy().is_satisfied_by(&signed) {
+        return Err(Error::AuthorityPolicyNotMet);
+    }
+    Ok(())
+}
+
+// The first ChangeRule: committed by the first deepkey agent right after the KeysetRoot,
+// with its spec signed by the root key.
+fn verify_genesis_change_rule(
+    record: &Record,
+    keyset_root_hash: &ActionHash,
+    keyset_root: &KeysetRoot,
+    dna_hash: &DnaHash,
+) -> Result<(ActionHash, ChangeRule), Error> {
+    let (change_rule_hash, change_rule) = verify_entry_record::<ChangeRule>(record)?;
+    let action = record.action();
+    if *action.author() != keyset_root.first_deepkey_agent {
+        return Err(Error::AuthorNotFda);
+    }
+    if !matches!(action, Action::Create(_)) || action.prev_action() != Some(keyset_root_hash) {
+        return Err(Error::CreateNotAfterKeysetRoot);
+    }
+    if change_rule.keyset_root != *keyset_root_hash {
+        return Err(Error::KeysetRootMismatch);
+    }
+    if change_rule.revision != 0 {
+        return Err(Error::WrongChangeRuleRevision);
+    }
+    let signature = match change_rule.spec_change.authorization_of_new_spec.as_slice() {
+        [(_, signature)] => signature,
+        [] => return Err(Error::NoCreateSignature),
+        _ => return Err(Error::MultipleCreateSignatures),
+    };
+    let payload = SigningPayload::from(SpecChangePayload {
+        keyset_root: None,
+        dna_hash: dna_hash.clone(),
+        prev_change_rule: None,
+        revision: 0,
+        new_spec: change_rule.spec_change.new_spec.clone(),
+        operation_specs: change_rule.spec_change.operation_specs.clone(),
+        update_delay_secs: change_rule.spec_change.update_delay_secs,
+    });
+    if !verify_ed25519(
+        keyset_root.root_pub_key(),
+        signature,
+        &payload.signable_bytes()?,
+    ) {
+        return Err(Error::BadCreateSignature);
+    }
+    Ok((change_rule_hash, change_rule))
+}
+
+// An update of the previous ChangeRule, as its next revision, authorized by it.
+fn verify_change_rule_update(
+    record: &Record,
+    previous: &(ActionHash, ChangeRule),
+    dna_hash: &DnaHash,
+) -> Result<(ActionHash, ChangeRule), Error> {
+    let (change_rule_hash, change_rule) = verify_entry_record::<ChangeRule>(record)?;
+    let (previous_hash, previous_change_rule) = previous;
+    match record.action() {
+        Action::Update(update) if update.original_action_address == *previous_hash => {}
+        _ => return Err(Error::NotAnUpdateOfPreviousChangeRule),
+    }
+    if change_rule.keyset_root != previous_change_rule.keyset_root {
+        return Err(Error::KeysetRootMismatch);
+    }
+    if Some(change_rule.revision) != previous_change_rule.revision.checked_add(1) {
+        return Err(Error::WrongChangeRuleRevision);
+    }
+    let payload = SigningPayload::from(SpecChangePayload {
+        keyset_root: Some(change_rule.keyset_root.clone()),
+        dna_hash: dna_hash.clone(),
+        prev_change_rule: Some(previous_hash.clone()),
+        revision: change_rule.revision,
+        new_spec: change_rule.spec_change.new_spec.clone(),
+        operation_specs: change_rule.spec_change.operation_specs.clone(),
+        update_delay_secs: change_rule.spec_change.update_delay_secs,
+    });
+    verify_authorization(
+        previous_change_rule,
+        AuthorityOperation::ChangeRuleUpdate,
+        &change_rule.spec_change.authorization_of_new_spec,
+        &payload,
+    )?;
+    Ok((change_rule_hash, change_rule))
+}
+
+/// Checks a keyset's ChangeRules, oldest first: the first one created with the KeysetRoot
+/// and signed by its root key, and each later one an update of the one before it, with
+/// the next revision, authorized by it.
+/// Returns them with their hashes; the last one is the rule in force when they were
+/// exported.
+pub fn verify_change_rules(
+    keyset_root_hash: &ActionHash,
+    keyset_root: &KeysetRoot,
+    dna_hash: &DnaHash,
+    change_rules: &[Record],
+) -> Result<Vec<(ActionHash, ChangeRule)>, Error> {
+    let (genesis, updates) = change_rules.split_first().ok_or(Error::NoChangeRules)?;
+    let mut previous =
+        verify_genesis_change_rule(genesis, keyset_root_hash, keyset_root, dna_hash)?;
+    let mut verified = Vec::with_capacity(change_rules.len());
+    for update in updates {
+        let change_rule = verify_change_rule_update(update, &previous, dna_hash)?;
+        verified.push(std::mem::replace(&mut previous, change_rule));
+    }
+    verified.push(previous);
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{action_hash, dna_hash, TestKey},
+        AuthorityPolicy, AuthoritySpec, AuthorizedSpecChange, KeysetRecoveryPayload,
+    };
+
+    fn guardians() -> Vec<TestKey> {
+        (1..=3).map(TestKey::new).collect()
+    }
+
+    fn change_rule(spec: AuthoritySpec) -> ChangeRule {
+        ChangeRule::new(
+            action_hash(1),
+            action_hash(1),
+            0,
+            AuthorizedSpecChange::new(spec, vec![]),
+        )
+    }
+
+    fn payload() -> SigningPayload {
+        SigningPayload::from(KeysetRecoveryPayload {
+            keyset_root: action_hash(1),
+            dna_hash: dna_hash(),
+            change_rule: action_hash(2),
+            invitee: TestKey::new(9).agent(),
+        })
+    }
+
+    fn authorize(keys: &[TestKey], positions: &[u8]) -> Vec<Authorization> {
+        let data = payload().signable_bytes().unwrap();
+        positions
+            .iter()
+            .map(|position| (*position, keys[*position as usize].sign(&data)))
+            .collect()
+    }
+
+    fn verify(spec: AuthoritySpec, authorization: &[Authorization]) -> Result<(), Error> {
+        verify_authorization(
+            &change_rule(spec),
+            AuthorityOperation::DeviceInvite,
+            authorization,
+            &payload(),
+        )
+    }
+
+    #[test]
+    fn flat_spec_takes_exactly_sigs_required() {
+        let keys = guardians();
+        let spec = AuthoritySpec::new(2, keys.iter().map(TestKey::agent).collect());
+        assert_eq!(verify(spec.clone(), &authorize(&keys, &[0, 2])), Ok(()));
+        assert_eq!(
+            verify(spec.clone(), &authorize(&keys, &[0])),
+            Err(Error::WrongNumberOfSignatures)
+        );
+        assert_eq!(
+            verify(spec, &authorize(&keys, &[0, 1, 2])),
+            Err(Error::WrongNumberOfSignatures)
+        );
+    }
+
+    #[test]
+    fn duplicate_positions_are_refused() {
+        let keys = guardians();
+        let spec = AuthoritySpec::new(2, keys.iter().map(TestKey::agent).collect());
+        assert_eq!(
+            verify(spec, &authorize(&keys, &[1, 1])),
+            Err(Error::DuplicateAuthorization(1))
+        );
+    }
+
+    #[test]
+    fn out_of_range_positions_are_refused() {
+        let keys = guardians();
+        let spec = AuthoritySpec::new(1, keys[..2].iter().map(TestKey::agent).collect());
+        assert_eq!(
+            verify(spec, &authorize(&keys, &[2])),
+            Err(Error::AuthorizedPositionOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn signature_of_another_signer_is_refused() {
+        let keys = guardians();
+        let spec = AuthoritySpec::new(1, keys.iter().map(TestKey::agent).collect());
+        let mut authorization = authorize(&keys, &[0]);
+        authorization[0].0 = 1;
+        assert_eq!(verify(spec, &authorization), Err(Error::BadUpdateSignature));
+    }
+
+    #[test]
+    fn policy_decides_which_signers_suffice() {
+        let keys = guardians();
+        // The first signer alone, or both of the others.
+        let policy = AuthorityPolicy::Any(vec![
+            AuthorityPolicy::Signer(0),
+            AuthorityPolicy::All(vec![AuthorityPolicy::Signer(1), AuthorityPolicy::Signer(2)]),
+        ]);
+        let spec =
+            AuthoritySpec::new_with_policy(keys.iter().map(TestKey::agent).collect(), policy);
+        assert_eq!(verify(spec.clone(), &authorize(&keys, &[0])), Ok(()));
+        assert_eq!(verify(spec.clone(), &authorize(&keys, &[2, 1])), Ok(()));
+        assert_eq!(
+            verify(spec, &authorize(&keys, &[1])),
+            Err(Error::AuthorityPolicyNotMet)
+        );
+    }
+
+    #[test]
+    fn weighted_policy_adds_up_signer_weights() {
+        let keys = guardians();
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: 3,
+            policies: vec![
+                (2, AuthorityPolicy::Signer(0)),
+                (1, AuthorityPolicy::Signer(1)),
+                (1, AuthorityPolicy::Signer(2)),
+            ],
+        };
+        let spec =
+            AuthoritySpec::new_with_policy(keys.iter().map(TestKey::agent).collect(), policy);
+        assert_eq!(verify(spec.clone(), &authorize(&keys, &[0, 2])), Ok(()));
+        assert_eq!(
+            verify(spec, &authorize(&keys, &[1, 2])),
+            Err(Error::AuthorityPolicyNotMet)
+        );
+    }
+
+    #[test]
+    fn operation_spec_replaces_the_default_spec() {
+        let keys = guardians();
+        let mut rule = change_rule(AuthoritySpec::new(
+            2,
+            keys.iter().map(TestKey::agent).collect(),
+        ));
+        rule.spec_change.operation_specs.device_invite =
+            Some(AuthoritySpec::new(1, vec![keys[2].agent()]));
+        let data = payload().signable_bytes().unwrap();
+        assert_eq!(
+            verify_authorization(
+                &rule,
+                AuthorityOperation::DeviceInvite,
+                &[(0, keys[2].sign(&data))],
+                &payload(),
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify_authorization(
+                &rule,
+                AuthorityOperation::KeyRevocation,
+                &[(0, keys[2].sign(&data))],
+                &payload(),
+            ),
+            Err(Error::WrongNumberOfSignatures)
+        );
+    }
+}
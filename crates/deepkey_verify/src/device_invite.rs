@@ -0,0 +1,134 @@
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+
+use crate::{
+    error::Error, verify_authorization, verify_ed25519, verify_entry_record, AuthorityOperation,
+    ChangeRule, DeviceInvite, DeviceInviteAcceptance, DeviceInvitePayload, InviteProof,
+    KeysetRecoveryPayload, KeysetRoot, RecoveryAuthorization, SigningPayload,
+};
+
+// A recovery invite is committed by its invitee straight under the KeysetRoot, approved by
+// the signers of one of the keyset's ChangeRules.
+fn verify_recovery_invite(
+    author: &AgentPubKey,
+    device_invite: &DeviceInvite,
+    recovery: &RecoveryAuthorization,
+    change_rules: &[(ActionHash, ChangeRule)],
+    dna_hash: &DnaHash,
+) -> Result<(), Error> {
+    if *author != device_invite.invitee {
+        return Err(Error::RecoveryInviteNotByInvitee);
+    }
+    if device_invite.parent != device_invite.keyset_root {
+        return Err(Error::RecoveryInviteNotUnderKeysetRoot);
+    }
+    let (_, change_rule) = change_rules
+        .iter()
+        .find(|(change_rule_hash, _)| *change_rule_hash == recovery.change_rule)
+        .ok_or(Error::UnknownRecoveryChangeRule)?;
+    let payload = SigningPayload::from(KeysetRecoveryPayload {
+        keyset_root: device_invite.keyset_root.clone(),
+        dna_hash: dna_hash.clone(),
+        change_rule: recovery.change_rule.clone(),
+        invitee: device_invite.invitee.clone(),
+    });
+    match verify_authorization(
+        change_rule,
+        AuthorityOperation::DeviceInvite,
+        &recovery.authorization,
+        &payload,
+    ) {
+        Err(Error::BadUpdateSignature) => Err(Error::BadRecoverySignature),
+        result => result,
+    }
+}
+
+// Any other invite is signed by the device that committed it, under one of the keyset's
+// ChangeRules, and approved by the signers of that rule's spec for `DeviceInvite`.
+fn verify_invite_signature(
+    inviter: &AgentPubKey,
+    device_invite: &DeviceInvite,
+    change_rules: &[(ActionHash, ChangeRule)],
+    dna_hash: &DnaHash,
+) -> Result<(), Error> {
+    let inviter_signature = device_invite
+        .inviter_signature
+        .as_ref()
+        .ok_or(Error::InviteNotSigned)?;
+    let change_rule_hash = device_invite
+        .change_rule
+        .as_ref()
+        .ok_or(Error::InviteChangeRuleMissing)?;
+    let payload = SigningPayload::from(DeviceInvitePayload {
+        keyset_root: device_invite.keyset_root.clone(),
+        dna_hash: dna_hash.clone(),
+        parent: device_invite.parent.clone(),
+        invitee: device_invite.invitee.clone(),
+        change_rule: change_rule_hash.clone(),
+    });
+    if !verify_ed25519(inviter, inviter_signature, &payload.signable_bytes()?) {
+        return Err(Error::BadInviteSignature);
+    }
+    let (_, change_rule) = change_rules
+        .iter()
+        .find(|(hash, _)| hash == change_rule_hash)
+        .ok_or(Error::UnknownInviteChangeRule)?;
+    verify_authorization(
+        change_rule,
+        AuthorityOperation::DeviceInvite,
+        &device_invite.authorization,
+        &payload,
+    )
+}
+
+/// Checks the invites from the KeysetRoot down to a device: each one issued below the
+/// device before it, by that device under one of the verified `change_rules` or, for a
+/// recovery, with the approval of one of them, and accepted by its invitee.
+/// Returns the device at the end of the path and its keyset leaf, which is the KeysetRoot
+/// itself when there are no invites.
+pub fn verify_invites(
+    keyset_root_hash: &ActionHash,
+    keyset_root: &KeysetRoot,
+    dna_hash: &DnaHash,
+    change_rules: &[(ActionHash, ChangeRule)],
+    invites: &[InviteProof],
+) -> Result<(AgentPubKey, ActionHash), Error> {
+    let mut device = keyset_root.first_deepkey_agent.clone();
+    let mut keyset_leaf = keyset_root_hash.clone();
+    for invite in invites {
+        let (device_invite_hash, device_invite) =
+            verify_entry_record::<DeviceInvite>(&invite.device_invite)?;
+        if device_invite.keyset_root != *keyset_root_hash {
+            return Err(Error::InviteOutsideKeyset);
+        }
+        if device_invite.parent != keyset_leaf {
+            return Err(Error::InviteParentMismatch);
+        }
+        let inviter = invite.device_invite.action().author();
+        match &device_invite.recovery {
+            Some(recovery) => {
+                verify_recovery_invite(inviter, &device_invite, recovery, change_rules, dna_hash)?
+            }
+            None => {
+                if *inviter != device {
+                    return Err(Error::InviteNotByParentDevice);
+                }
+                verify_invite_signature(inviter, &device_invite, change_rules, dna_hash)?;
+            }
+        }
+
+        let (device_invite_acceptance_hash, device_invite_acceptance) =
+            verify_entry_record::<DeviceInviteAcceptance>(&invite.device_invite_acceptance)?;
+        if device_invite_acceptance.invite != device_invite_hash {
+            return Err(Error::AcceptanceOfAnotherInvite);
+        }
+        if *invite.device_invite_acceptance.action().author() != device_invite.invitee {
+            return Err(Error::InviteForAnotherAgent);
+        }
+        if device_invite_acceptance.keyset_root_authority != device_invite.keyset_root {
+            return Err(Error::InviteKeysetRootMismatch);
+        }
+        device = device_invite.invitee;
+        keyset_leaf = device_invite_acceptance_hash;
+    }
+    Ok((device, keyset_leaf))
+}
@@ -0,0 +1,143 @@
+use holochain_serialized_bytes::SerializedBytesError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("Malformed record: {0}")]
+    MalformedRecord(String),
+
+    #[error("The record's action hash does not match its action")]
+    ActionHashMismatch,
+
+    #[error("The record's action has an invalid signature by its author")]
+    BadActionSignature,
+
+    #[error("The record's entry does not match its action")]
+    EntryHashMismatch,
+
+    #[error("The KeysetRoot author is not its first deepkey agent")]
+    KeysetRootAuthorNotFda,
+
+    #[error("The KeysetRoot has an invalid signature by its root key")]
+    BadKeysetRootSignature,
+
+    #[error("There are no ChangeRules for the keyset")]
+    NoChangeRules,
+
+    #[error("The ChangeRule author is not the FDA on the KeysetRoot")]
+    AuthorNotFda,
+
+    #[error("The ChangeRule created does not immediately follow its KeysetRoot")]
+    CreateNotAfterKeysetRoot,
+
+    #[error("The ChangeRule is not an update of the ChangeRule before it")]
+    NotAnUpdateOfPreviousChangeRule,
+
+    #[error("Multiple creation signatures found")]
+    MultipleCreateSignatures,
+
+    #[error("No creation signature found")]
+    NoCreateSignature,
+
+    #[error("Invalid creation signature")]
+    BadCreateSignature,
+
+    #[error("The ChangeRule belongs to a different KeysetRoot")]
+    KeysetRootMismatch,
+
+    #[error("The ChangeRule revision does not follow the revision it supersedes")]
+    WrongChangeRuleRevision,
+
+    #[error("The authorization has the wrong number of signatures")]
+    WrongNumberOfSignatures,
+
+    #[error("The authorization referenced an authorizor position that doesn't exist")]
+    AuthorizedPositionOutOfBounds,
+
+    #[error("The authorization has more than one signature from position {0}")]
+    DuplicateAuthorization(u8),
+
+    #[error("The authorization has an invalid signature")]
+    BadUpdateSignature,
+
+    #[error("The authorization does not meet the ChangeRule's AuthorityPolicy")]
+    AuthorityPolicyNotMet,
+
+    #[error("The DeviceInvite is into a different keyset")]
+    InviteOutsideKeyset,
+
+    #[error("The DeviceInvite's parent is not the device before it")]
+    InviteParentMismatch,
+
+    #[error("The DeviceInvite was not issued by the device it was issued below")]
+    InviteNotByParentDevice,
+
+    #[error("The DeviceInvite was issued to another agent")]
+    InviteForAnotherAgent,
+
+    #[error("The DeviceInviteAcceptance accepts a different DeviceInvite")]
+    AcceptanceOfAnotherInvite,
+
+    #[error("The DeviceInviteAcceptance names a different KeysetRoot than its DeviceInvite")]
+    InviteKeysetRootMismatch,
+
+    #[error("A recovery DeviceInvite must be committed by its invitee")]
+    RecoveryInviteNotByInvitee,
+
+    #[error("A recovery DeviceInvite must have the KeysetRoot as its parent")]
+    RecoveryInviteNotUnderKeysetRoot,
+
+    #[error("The recovery DeviceInvite is authorized by a ChangeRule not among the keyset's")]
+    UnknownRecoveryChangeRule,
+
+    #[error("The recovery DeviceInvite has an invalid signature")]
+    BadRecoverySignature,
+
+    #[error("The DeviceInvite is not signed by its inviter")]
+    InviteNotSigned,
+
+    #[error("The DeviceInvite does not name the ChangeRule it was issued under")]
+    InviteChangeRuleMissing,
+
+    #[error("The DeviceInvite is issued under a ChangeRule not among the keyset's")]
+    UnknownInviteChangeRule,
+
+    #[error("The DeviceInvite has an invalid signature")]
+    BadInviteSignature,
+
+    #[error("The KeyRegistration was not committed by the device")]
+    KeyRegistrationNotByDevice,
+
+    #[error("The KeyRegistration does not register the key")]
+    KeyNotRegistered,
+
+    #[error("The KeyGeneration has an invalid signature by the new key")]
+    BadKeyGenerationSignature,
+
+    #[error("Unsupported RecoveryManifest version {0}")]
+    UnsupportedRecoveryManifestVersion(u8),
+
+    #[error("The RecoveryShare was not committed by the owner of the RecoveryManifest")]
+    RecoveryShareNotByOwner,
+
+    #[error("The RecoveryShare was released by a guardian rather than split from the seed")]
+    ReleasedRecoveryShare,
+
+    #[error("The RecoveryManifest has more than one share for a guardian")]
+    DuplicateGuardian,
+
+    #[error("The RecoveryManifest threshold must be between 1 and the number of guardians")]
+    UnsatisfiableRecoveryThreshold,
+
+    #[error("Serialization failed: {0}")]
+    Serialization(String),
+
+    #[error(transparent)]
+    Types(#[from] deepkey_types::error::Error),
+}
+
+impl From<SerializedBytesError> for Error {
+    fn from(e: SerializedBytesError) -> Error {
+        Error::Serialization(e.to_string())
+    }
+}
@@ -0,0 +1,101 @@
+//! Keys, hashes and records for the tests, so signatures can be made without a keystore.
+
+use ed25519_dalek::{Signer, SigningKey};
+use holo_hash::{ActionHash, AgentPubKey, DnaHash, EntryHash};
+use holochain_integrity_types::prelude::{
+    Action, ActionHashed, AppEntryBytes, AppEntryDef, Create, Entry, EntryRateWeight, EntryType,
+    EntryVisibility, Record, Signature, SignedActionHashed, Timestamp, Update,
+};
+use holochain_serialized_bytes::{encode, SerializedBytes, UnsafeBytes};
+use serde::Serialize;
+
+pub struct TestKey(SigningKey);
+
+impl TestKey {
+    pub fn new(seed: u8) -> Self {
+        Self(SigningKey::from_bytes(&[seed; 32]))
+    }
+
+    pub fn agent(&self) -> AgentPubKey {
+        AgentPubKey::from_raw_32(self.0.verifying_key().to_bytes().to_vec())
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        Signature(self.0.sign(data).to_bytes())
+    }
+}
+
+pub fn action_hash(byte: u8) -> ActionHash {
+    ActionHash::from_raw_32(vec![byte; 32])
+}
+
+pub fn dna_hash() -> DnaHash {
+    DnaHash::from_raw_32(vec![0xdb; 32])
+}
+
+pub fn app_entry<T: Serialize + std::fmt::Debug>(entry: &T) -> Entry {
+    Entry::App(AppEntryBytes(SerializedBytes::from(UnsafeBytes::from(
+        encode(entry).unwrap(),
+    ))))
+}
+
+/// `action` signed by `author`, as the conductor commits it, with `entry` if any.
+pub fn sign_record(author: &TestKey, action: Action, entry: Option<Entry>) -> Record {
+    let signature = author.sign(&encode(&action).unwrap());
+    Record::new(
+        SignedActionHashed::with_presigned(ActionHashed::from_content_sync(action), signature),
+        entry,
+    )
+}
+
+fn app_entry_type() -> EntryType {
+    EntryType::App(AppEntryDef::new(
+        0.into(),
+        0.into(),
+        EntryVisibility::Public,
+    ))
+}
+
+/// `author` creating `entry` at `action_seq` of their chain, after `prev_action`.
+pub fn create_record<T: Serialize + std::fmt::Debug>(
+    author: &TestKey,
+    action_seq: u32,
+    prev_action: ActionHash,
+    entry: &T,
+) -> Record {
+    let entry = app_entry(entry);
+    let action = Action::Create(Create {
+        author: author.agent(),
+        timestamp: Timestamp::from_micros(action_seq as i64),
+        action_seq,
+        prev_action,
+        entry_type: app_entry_type(),
+        entry_hash: EntryHash::with_data_sync(&entry),
+        weight: EntryRateWeight::default(),
+    });
+    sign_record(author, action, Some(entry))
+}
+
+/// `author` updating `original` to `entry` at `action_seq` of their chain, after
+/// `prev_action`.
+pub fn update_record<T: Serialize + std::fmt::Debug>(
+    author: &TestKey,
+    action_seq: u32,
+    prev_action: ActionHash,
+    original: &Record,
+    entry: &T,
+) -> Record {
+    let entry = app_entry(entry);
+    let action = Action::Update(Update {
+        author: author.agent(),
+        timestamp: Timestamp::from_micros(action_seq as i64),
+        action_seq,
+        prev_action,
+        original_action_address: original.action_address().clone(),
+        original_entry_address: original.action().entry_hash().unwrap().clone(),
+        entry_type: app_entry_type(),
+        entry_hash: EntryHash::with_data_sync(&entry),
+        weight: EntryRateWeight::default(),
+    });
+    sign_record(author, action, Some(entry))
+}
@@ -0,0 +1,563 @@
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+
+use crate::{
+    error::Error, verify_change_rules, verify_invites, verify_key_registration, verify_keyset_root,
+    KeyProof, KEY_PROOF_VERSION,
+};
+
+// What a verified KeyProof establishes about its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedKey {
+    pub key: AgentPubKey,
+    pub dna_hash: DnaHash,
+    pub keyset_root: ActionHash,
+    // The device that registered the key, and its place in the keyset tree.
+    pub device: AgentPubKey,
+    pub keyset_leaf: ActionHash,
+    // The ChangeRule in force when the proof was exported.
+    pub change_rule: ActionHash,
+}
+
+/// Checks every record of a KeyProof and how they chain together, from the KeysetRoot
+/// through the ChangeRules and invites down to the registration of the key.
+/// The proof is only as current as `exported_at`: check a revocation list issued later to
+/// learn whether the key has been revoked since.
+pub fn verify_key_proof(key_proof: &KeyProof) -> Result<VerifiedKey, Error> {
+    if key_proof.version != KEY_PROOF_VERSION {
+        return Err(
+            deepkey_types::error::Error::UnsupportedKeyProofVersion(key_proof.version).into(),
+        );
+    }
+    let dna_hash = &key_proof.dna_hash;
+    let (keyset_root_hash, keyset_root) = verify_keyset_root(&key_proof.keyset_root, dna_hash)?;
+    let change_rules = verify_change_rules(
+        &keyset_root_hash,
+        &keyset_root,
+        dna_hash,
+        &key_proof.change_rules,
+    )?;
+    let (device, keyset_leaf) = verify_invites(
+        &keyset_root_hash,
+        &keyset_root,
+        dna_hash,
+        &change_rules,
+        &key_proof.invites,
+    )?;
+    verify_key_registration(
+        &key_proof.key_registration,
+        &key_proof.key,
+        &device,
+        &keyset_root_hash,
+        &keyset_leaf,
+        dna_hash,
+    )?;
+    let (change_rule, _) = change_rules.last().ok_or(Error::NoChangeRules)?;
+    Ok(VerifiedKey {
+        key: key_proof.key.clone(),
+        dna_hash: dna_hash.clone(),
+        keyset_root: keyset_root_hash,
+        device,
+        keyset_leaf,
+        change_rule: change_rule.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{action_hash, create_record, dna_hash, update_record, TestKey},
+        AuthoritySpec, AuthorizedSpecChange, ChangeRule, DeviceInvite, DeviceInviteAcceptance,
+        DeviceInvitePayload, InviteProof, KeyGeneration, KeyGenerationPayload, KeyRegistration,
+        KeysetRoot, KeysetRootPayload, OperationSpecs, SigningPayload, SpecChangePayload,
+    };
+    use holochain_integrity_types::prelude::{Record, Timestamp};
+
+    // A keyset whose first deepkey agent invites one more device, with the key each of
+    // them registers.
+    struct Keyset {
+        root: TestKey,
+        fda: TestKey,
+        guardian: TestKey,
+        device: TestKey,
+        key: TestKey,
+    }
+
+    impl Keyset {
+        fn new() -> Self {
+            Self {
+                root: TestKey::new(1),
+                fda: TestKey::new(2),
+                guardian: TestKey::new(3),
+                device: TestKey::new(4),
+                key: TestKey::new(5),
+            }
+        }
+
+        fn keyset_root(&self, author: &TestKey, root_key: &TestKey) -> Record {
+            let payload = SigningPayload::from(KeysetRootPayload {
+                dna_hash: dna_hash(),
+                first_deepkey_agent: self.fda.agent(),
+            });
+            let keyset_root = KeysetRoot::new(
+                self.fda.agent(),
+                self.root.agent(),
+                root_key.sign(&payload.signable_bytes().unwrap()),
+            );
+            create_record(author, 3, action_hash(2), &keyset_root)
+        }
+
+        fn spec_change(
+            &self,
+            keyset_root: Option<&Record>,
+            prev_change_rule: Option<&Record>,
+            revision: u32,
+            signer: &TestKey,
+        ) -> AuthorizedSpecChange {
+            let new_spec = AuthoritySpec::new(1, vec![self.guardian.agent()]);
+            let payload = SigningPayload::from(SpecChangePayload {
+                keyset_root: keyset_root.map(|record| record.action_address().clone()),
+                dna_hash: dna_hash(),
+                prev_change_rule: prev_change_rule.map(|record| record.action_address().clone()),
+                revision,
+                new_spec: new_spec.clone(),
+                operation_specs: OperationSpecs::default(),
+                update_delay_secs: 0,
+            });
+            let signature = signer.sign(&payload.signable_bytes().unwrap());
+            AuthorizedSpecChange::new(new_spec, vec![(0, signature)])
+        }
+
+        fn genesis_change_rule(
+            &self,
+            keyset_root: &Record,
+            prev_action: &Record,
+            signer: &TestKey,
+        ) -> Record {
+            let keyset_root_hash = keyset_root.action_address();
+            let change_rule = ChangeRule::new(
+                keyset_root_hash.clone(),
+                keyset_root_hash.clone(),
+                0,
+                self.spec_change(None, None, 0, signer),
+            );
+            create_record(
+                &self.fda,
+                4,
+                prev_action.action_address().clone(),
+                &change_rule,
+            )
+        }
+
+        fn change_rule_update(
+            &self,
+            keyset_root: &Record,
+            previous: &Record,
+            revision: u32,
+            signer: &TestKey,
+        ) -> Record {
+            let keyset_root_hash = keyset_root.action_address();
+            let change_rule = ChangeRule::new(
+                keyset_root_hash.clone(),
+                keyset_root_hash.clone(),
+                revision,
+                self.spec_change(Some(keyset_root), Some(previous), revision, signer),
+            );
+            update_record(
+                &self.fda,
+                5,
+                previous.action_address().clone(),
+                previous,
+                &change_rule,
+            )
+        }
+
+        fn invite(
+            &self,
+            keyset_root: &Record,
+            change_rule: &Record,
+            inviter: &TestKey,
+            authorizer: &TestKey,
+            acceptor: &TestKey,
+        ) -> InviteProof {
+            let keyset_root_hash = keyset_root.action_address();
+            let payload = SigningPayload::from(DeviceInvitePayload {
+                keyset_root: keyset_root_hash.clone(),
+                dna_hash: dna_hash(),
+                parent: keyset_root_hash.clone(),
+                invitee: self.device.agent(),
+                change_rule: change_rule.action_address().clone(),
+            });
+            let device_invite = DeviceInvite::new(
+                keyset_root_hash.clone(),
+                keyset_root_hash.clone(),
+                self.device.agent(),
+                inviter.sign(&payload.signable_bytes().unwrap()),
+                change_rule.action_address().clone(),
+                vec![(0, authorizer.sign(&payload.signable_bytes().unwrap()))],
+            );
+            let device_invite = create_record(
+                inviter,
+                6,
+                change_rule.action_address().clone(),
+                &device_invite,
+            );
+            let device_invite_acceptance = DeviceInviteAcceptance::new(
+                keyset_root_hash.clone(),
+                device_invite.action_address().clone(),
+            );
+            let device_invite_acceptance =
+                create_record(acceptor, 3, action_hash(7), &device_invite_acceptance);
+            InviteProof {
+                device_invite,
+                device_invite_acceptance,
+            }
+        }
+
+        fn key_registration(
+            &self,
+            author: &TestKey,
+            keyset_root: &Record,
+            keyset_leaf: &ActionHash,
+            key: &TestKey,
+        ) -> Record {
+            let timestamp = Timestamp::from_micros(1_000);
+            let payload = SigningPayload::from(KeyGenerationPayload {
+                keyset_root: keyset_root.action_address().clone(),
+                dna_hash: dna_hash(),
+                keyset_leaf: keyset_leaf.clone(),
+                author: author.agent(),
+                timestamp,
+                prior_key_anchor: None,
+            });
+            let key_registration = KeyRegistration::Create(KeyGeneration {
+                new_key: self.key.agent(),
+                new_key_signing_of_author: key.sign(&payload.signable_bytes().unwrap()),
+                timestamp,
+                prior_key_anchor: None,
+                keyset_freezes: Vec::new(),
+            });
+            create_record(author, 8, action_hash(8), &key_registration)
+        }
+
+        // The key registered by the first deepkey agent, under the genesis ChangeRule.
+        fn key_proof(&self) -> KeyProof {
+            let keyset_root = self.keyset_root(&self.fda, &self.root);
+            let change_rule = self.genesis_change_rule(&keyset_root, &keyset_root, &self.root);
+            let key_registration = self.key_registration(
+                &self.fda,
+                &keyset_root,
+                keyset_root.action_address(),
+                &self.key,
+            );
+            KeyProof {
+                version: KEY_PROOF_VERSION,
+                dna_hash: dna_hash(),
+                key: self.key.agent(),
+                keyset_root,
+                change_rules: vec![change_rule],
+                invites: Vec::new(),
+                key_registration,
+                exported_at: Timestamp::from_micros(2_000),
+            }
+        }
+
+        // The key registered by the invited device, after the genesis ChangeRule is
+        // updated by its guardian.
+        fn invited_key_proof(&self) -> KeyProof {
+            let mut key_proof = self.key_proof();
+            let update = self.change_rule_update(
+                &key_proof.keyset_root,
+                &key_proof.change_rules[0],
+                1,
+                &self.guardian,
+            );
+            let invite = self.invite(
+                &key_proof.keyset_root,
+                &update,
+                &self.fda,
+                &self.guardian,
+                &self.device,
+            );
+            key_proof.key_registration = self.key_registration(
+                &self.device,
+                &key_proof.keyset_root,
+                invite.device_invite_acceptance.action_address(),
+                &self.key,
+            );
+            key_proof.change_rules.push(update);
+            key_proof.invites.push(invite);
+            key_proof
+        }
+    }
+
+    #[test]
+    fn key_of_first_deepkey_agent_is_verified() {
+        let keyset = Keyset::new();
+        let key_proof = keyset.key_proof();
+        let keyset_root = key_proof.keyset_root.action_address().clone();
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Ok(VerifiedKey {
+                key: keyset.key.agent(),
+                dna_hash: dna_hash(),
+                keyset_root: keyset_root.clone(),
+                device: keyset.fda.agent(),
+                keyset_leaf: keyset_root,
+                change_rule: key_proof.change_rules[0].action_address().clone(),
+            })
+        );
+    }
+
+    #[test]
+    fn key_of_invited_device_is_verified() {
+        let keyset = Keyset::new();
+        let key_proof = keyset.invited_key_proof();
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Ok(VerifiedKey {
+                key: keyset.key.agent(),
+                dna_hash: dna_hash(),
+                keyset_root: key_proof.keyset_root.action_address().clone(),
+                device: keyset.device.agent(),
+                keyset_leaf: key_proof.invites[0]
+                    .device_invite_acceptance
+                    .action_address()
+                    .clone(),
+                change_rule: key_proof.change_rules[1].action_address().clone(),
+            })
+        );
+    }
+
+    #[test]
+    fn unsupported_version_is_refused() {
+        let mut key_proof = Keyset::new().key_proof();
+        key_proof.version = KEY_PROOF_VERSION + 1;
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(
+                deepkey_types::error::Error::UnsupportedKeyProofVersion(KEY_PROOF_VERSION + 1)
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn proof_for_another_network_is_refused() {
+        let mut key_proof = Keyset::new().key_proof();
+        key_proof.dna_hash = DnaHash::from_raw_32(vec![0xdc; 32]);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::BadKeysetRootSignature)
+        );
+    }
+
+    #[test]
+    fn keyset_root_must_be_committed_by_first_deepkey_agent() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        key_proof.keyset_root = keyset.keyset_root(&keyset.device, &keyset.root);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::KeysetRootAuthorNotFda)
+        );
+    }
+
+    #[test]
+    fn keyset_root_must_be_signed_by_root_key() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        key_proof.keyset_root = keyset.keyset_root(&keyset.fda, &keyset.fda);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::BadKeysetRootSignature)
+        );
+    }
+
+    #[test]
+    fn tampered_record_is_refused() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        let other_proof = keyset.invited_key_proof();
+        key_proof.change_rules[0].entry = other_proof.change_rules[1].entry.clone();
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::EntryHashMismatch));
+    }
+
+    #[test]
+    fn change_rules_are_required() {
+        let mut key_proof = Keyset::new().key_proof();
+        key_proof.change_rules.clear();
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::NoChangeRules));
+    }
+
+    #[test]
+    fn genesis_change_rule_must_follow_keyset_root() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        let genesis = key_proof.change_rules[0].clone();
+        key_proof.change_rules[0] =
+            keyset.genesis_change_rule(&key_proof.keyset_root, &genesis, &keyset.root);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::CreateNotAfterKeysetRoot)
+        );
+    }
+
+    #[test]
+    fn genesis_change_rule_must_be_signed_by_root_key() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        key_proof.change_rules[0] =
+            keyset.genesis_change_rule(&key_proof.keyset_root, &key_proof.keyset_root, &keyset.fda);
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::BadCreateSignature));
+    }
+
+    #[test]
+    fn change_rule_update_must_take_next_revision() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.change_rules[1] = keyset.change_rule_update(
+            &key_proof.keyset_root,
+            &key_proof.change_rules[0],
+            2,
+            &keyset.guardian,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::WrongChangeRuleRevision)
+        );
+    }
+
+    #[test]
+    fn change_rule_update_must_be_authorized_by_previous_rule() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.change_rules[1] = keyset.change_rule_update(
+            &key_proof.keyset_root,
+            &key_proof.change_rules[0],
+            1,
+            &keyset.root,
+        );
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::BadUpdateSignature));
+    }
+
+    #[test]
+    fn change_rule_update_must_update_previous_rule() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.change_rules.swap(0, 1);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::CreateNotAfterKeysetRoot)
+        );
+        key_proof.change_rules.swap(0, 1);
+        let update = key_proof.change_rules[1].clone();
+        key_proof.change_rules.push(update);
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::NotAnUpdateOfPreviousChangeRule)
+        );
+    }
+
+    #[test]
+    fn invite_must_be_issued_by_parent_device() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.invites[0] = keyset.invite(
+            &key_proof.keyset_root,
+            &key_proof.change_rules[1],
+            &keyset.guardian,
+            &keyset.guardian,
+            &keyset.device,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::InviteNotByParentDevice)
+        );
+    }
+
+    #[test]
+    fn invite_must_be_approved_by_change_rule_signers() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.invites[0] = keyset.invite(
+            &key_proof.keyset_root,
+            &key_proof.change_rules[1],
+            &keyset.fda,
+            &keyset.fda,
+            &keyset.device,
+        );
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::BadUpdateSignature));
+    }
+
+    #[test]
+    fn invite_must_be_accepted_by_invitee() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.invites[0] = keyset.invite(
+            &key_proof.keyset_root,
+            &key_proof.change_rules[1],
+            &keyset.fda,
+            &keyset.guardian,
+            &keyset.guardian,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::InviteForAnotherAgent)
+        );
+    }
+
+    #[test]
+    fn key_must_be_the_one_registered() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.key_proof();
+        key_proof.key = keyset.guardian.agent();
+        assert_eq!(verify_key_proof(&key_proof), Err(Error::KeyNotRegistered));
+    }
+
+    #[test]
+    fn key_must_be_registered_by_device_at_end_of_invites() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.key_registration = keyset.key_registration(
+            &keyset.fda,
+            &key_proof.keyset_root,
+            key_proof.invites[0]
+                .device_invite_acceptance
+                .action_address(),
+            &keyset.key,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::KeyRegistrationNotByDevice)
+        );
+    }
+
+    #[test]
+    fn key_generation_must_be_signed_by_key_for_device_leaf() {
+        let keyset = Keyset::new();
+        let mut key_proof = keyset.invited_key_proof();
+        key_proof.key_registration = keyset.key_registration(
+            &keyset.device,
+            &key_proof.keyset_root,
+            key_proof.keyset_root.action_address(),
+            &keyset.key,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::BadKeyGenerationSignature)
+        );
+        key_proof.key_registration = keyset.key_registration(
+            &keyset.device,
+            &key_proof.keyset_root,
+            key_proof.invites[0]
+                .device_invite_acceptance
+                .action_address(),
+            &keyset.device,
+        );
+        assert_eq!(
+            verify_key_proof(&key_proof),
+            Err(Error::BadKeyGenerationSignature)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use holo_hash::{ActionHash, AgentPubKey, DnaHash};
+use holochain_integrity_types::prelude::Record;
+
+use crate::{
+    error::Error, verify_ed25519, verify_entry_record, KeyGenerationPayload, KeyRegistration,
+    SigningPayload,
+};
+
+/// Checks that `device`, under the keyset at `keyset_leaf`, registered `key`: the record
+/// is a KeyRegistration creating `key` or replacing another key with it, carrying the new
+/// key's signature of the device, the keyset and the leaf.
+pub fn verify_key_registration(
+    record: &Record,
+    key: &AgentPubKey,
+    device: &AgentPubKey,
+    keyset_root_hash: &ActionHash,
+    keyset_leaf: &ActionHash,
+    dna_hash: &DnaHash,
+) -> Result<(), Error> {
+    let (_, key_registration) = verify_entry_record::<KeyRegistration>(record)?;
+    let key_generation = match key_registration {
+        KeyRegistration::Create(key_generation)
+        | KeyRegistration::CreateOnly(key_generation)
+        | KeyRegistration::Update(_, key_generation) => key_generation,
+        KeyRegistration::Delete(_) => return Err(Error::KeyNotRegistered),
+    };
+    if key_generation.new_key != *key {
+        return Err(Error::KeyNotRegistered);
+    }
+    if record.action().author() != device {
+        return Err(Error::KeyRegistrationNotByDevice);
+    }
+    let payload = SigningPayload::from(KeyGenerationPayload {
+        keyset_root: keyset_root_hash.clone(),
+        dna_hash: dna_hash.clone(),
+        keyset_leaf: keyset_leaf.clone(),
+        author: device.clone(),
+        timestamp: key_generation.timestamp,
+        prior_key_anchor: key_generation.prior_key_anchor.clone(),
+    });
+    if !verify_ed25519(
+        &key_generation.new_key,
+        &key_generation.new_key_signing_of_author,
+        &payload.signable_bytes()?,
+    ) {
+        return Err(Error::BadKeyGenerationSignature);
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use holo_hash::{ActionHash, DnaHash};
+use holochain_integrity_types::prelude::Record;
+
+use crate::{
+    error::Error, verify_ed25519, verify_entry_record, KeysetRoot, KeysetRootPayload,
+    SigningPayload,
+};
+
+/// Checks a KeysetRoot record: committed by its first deepkey agent, whose key the
+/// throwaway root key signed for the network `dna_hash`.
+/// Returns the hash the keyset is known by, with the KeysetRoot.
+pub fn verify_keyset_root(
+    record: &Record,
+    dna_hash: &DnaHash,
+) -> Result<(ActionHash, KeysetRoot), Error> {
+    let (keyset_root_hash, keyset_root) = verify_entry_record::<KeysetRoot>(record)?;
+    if *record.action().author() != keyset_root.first_deepkey_agent {
+        return Err(Error::KeysetRootAuthorNotFda);
+    }
+    let payload = SigningPayload::from(KeysetRootPayload {
+        dna_hash: dna_hash.clone(),
+        first_deepkey_agent: keyset_root.first_deepkey_agent.clone(),
+    });
+    if !verify_ed25519(
+        keyset_root.root_pub_key(),
+        keyset_root.fda_pubkey_signed_by_root_key(),
+        &payload.signable_bytes()?,
+    ) {
+        return Err(Error::BadKeysetRootSignature);
+    }
+    Ok((keyset_root_hash, keyset_root))
+}
@@ -0,0 +1,29 @@
+//! Checks the records Deepkey exports, such as a `KeyProof` or a `SignedRevocationList`,
+//! without a conductor: for servers, command-line tools and other languages' bindings that
+//! have to trust a key without joining the network.
+//!
+//! Ed25519 signatures are checked here rather than by the host, against the same signing
+//! payloads validation uses. The data types are those of `deepkey_integrity`, from the
+//! `deepkey_types` crate both depend on, so anything a zome exports deserializes here as is.
+//!
+//! An export only carries the records it is made of. What depends on the rest of the DHT,
+//! such as vetoes, freezes and update delays, is left to the network's own validation.
+pub use deepkey_types::*;
+pub mod change_rule;
+pub use change_rule::*;
+pub mod device_invite;
+pub use device_invite::*;
+pub mod error;
+pub use error::*;
+#[cfg(test)]
+mod fixtures;
+pub mod key_proof;
+pub use key_proof::*;
+pub mod key_registration;
+pub use key_registration::*;
+pub mod keyset_root;
+pub use keyset_root::*;
+pub mod record;
+pub use record::*;
+pub mod recovery_manifest;
+pub use recovery_manifest::*;
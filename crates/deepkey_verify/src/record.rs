@@ -0,0 +1,146 @@
+use holo_hash::{ActionHash, EntryHash};
+use holochain_integrity_types::prelude::{Entry, Record};
+use holochain_serialized_bytes::{decode, encode};
+use serde::de::DeserializeOwned;
+
+use crate::{error::Error, verify_ed25519};
+
+/// Checks that a record is as its author committed it: the action hash is that of the
+/// action, the author signed the action, and the entry, if any, is the one it commits.
+/// Returns the action hash, by which later records refer to this one.
+pub fn verify_record(record: &Record) -> Result<ActionHash, Error> {
+    let action = record.action();
+    let action_hash = ActionHash::with_data_sync(action);
+    if action_hash != *record.action_address() {
+        return Err(Error::ActionHashMismatch);
+    }
+    // Authors sign the encoded action, not its hash.
+    if !verify_ed25519(action.author(), record.signature(), &encode(action)?) {
+        return Err(Error::BadActionSignature);
+    }
+    match (action.entry_hash(), record.entry().as_option()) {
+        (Some(entry_hash), Some(entry)) if EntryHash::with_data_sync(entry) != *entry_hash => {
+            return Err(Error::EntryHashMismatch)
+        }
+        (None, Some(_)) => return Err(Error::EntryHashMismatch),
+        _ => {}
+    }
+    Ok(action_hash)
+}
+
+/// The app entry of a record, decoded as `T`. Only trust it once `verify_record` passes.
+pub fn entry_of<T: DeserializeOwned + std::fmt::Debug>(record: &Record) -> Result<T, Error> {
+    match record.entry().as_option() {
+        Some(Entry::App(app_entry_bytes)) => {
+            decode(app_entry_bytes.0.bytes()).map_err(|e| Error::MalformedRecord(e.to_string()))
+        }
+        _ => Err(Error::MalformedRecord(String::from(
+            "The record has no app entry",
+        ))),
+    }
+}
+
+/// `verify_record` for a record committing an app entry, which is decoded as `T`.
+pub fn verify_entry_record<T: DeserializeOwned + std::fmt::Debug>(
+    record: &Record,
+) -> Result<(ActionHash, T), Error> {
+    let action_hash = verify_record(record)?;
+    Ok((action_hash, entry_of(record)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{action_hash, app_entry, create_record, dna_hash, sign_record, TestKey},
+        KeysetRoot,
+    };
+    use holochain_integrity_types::prelude::{
+        Action, ActionHashed, Dna, RecordEntry, SignedActionHashed, Timestamp,
+    };
+
+    fn keyset_root() -> KeysetRoot {
+        let key = TestKey::new(1);
+        KeysetRoot::new(key.agent(), key.agent(), key.sign(b"keyset root"))
+    }
+
+    fn record() -> Record {
+        create_record(&TestKey::new(1), 3, action_hash(2), &keyset_root())
+    }
+
+    #[test]
+    fn committed_record_is_verified() {
+        let record = record();
+        assert_eq!(verify_record(&record), Ok(record.action_address().clone()));
+        assert_eq!(
+            verify_entry_record::<KeysetRoot>(&record),
+            Ok((record.action_address().clone(), keyset_root()))
+        );
+    }
+
+    #[test]
+    fn action_hash_must_be_of_the_action() {
+        let record = record();
+        let (signed_action, entry) = record.into_inner();
+        let signature = signed_action.signature().clone();
+        let tampered = Record::new(
+            SignedActionHashed::with_presigned(
+                ActionHashed::with_pre_hashed(signed_action.action().clone(), action_hash(9)),
+                signature,
+            ),
+            entry.into_option(),
+        );
+        assert_eq!(verify_record(&tampered), Err(Error::ActionHashMismatch));
+    }
+
+    #[test]
+    fn action_must_be_signed_by_its_author() {
+        let record = record();
+        let (signed_action, entry) = record.into_inner();
+        let forged = Record::new(
+            SignedActionHashed::with_presigned(
+                signed_action.hashed.clone(),
+                TestKey::new(2).sign(&encode(signed_action.action()).unwrap()),
+            ),
+            entry.into_option(),
+        );
+        assert_eq!(verify_record(&forged), Err(Error::BadActionSignature));
+    }
+
+    #[test]
+    fn entry_must_be_the_one_committed() {
+        let record = record();
+        let (signed_action, _) = record.into_inner();
+        let other_root = KeysetRoot::new(
+            TestKey::new(2).agent(),
+            TestKey::new(2).agent(),
+            TestKey::new(2).sign(b"keyset root"),
+        );
+        let swapped = Record::new(signed_action, Some(app_entry(&other_root)));
+        assert_eq!(verify_record(&swapped), Err(Error::EntryHashMismatch));
+    }
+
+    #[test]
+    fn entry_must_not_be_attached_to_an_action_without_one() {
+        let key = TestKey::new(1);
+        let dna = Action::Dna(Dna {
+            author: key.agent(),
+            timestamp: Timestamp::from_micros(0),
+            hash: dna_hash(),
+        });
+        let record = sign_record(&key, dna, None);
+        assert_eq!(verify_record(&record), Ok(record.action_address().clone()));
+        assert_eq!(
+            verify_entry_record::<KeysetRoot>(&record),
+            Err(Error::MalformedRecord(String::from(
+                "The record has no app entry"
+            )))
+        );
+        // Record::new refuses this, but a deserialized record can carry it.
+        let record = Record {
+            signed_action: record.signed_action,
+            entry: RecordEntry::Present(app_entry(&keyset_root())),
+        };
+        assert_eq!(verify_record(&record), Err(Error::EntryHashMismatch));
+    }
+}
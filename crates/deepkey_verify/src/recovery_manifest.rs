@@ -0,0 +1,33 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    error::Error, verify_entry_record, RecoveryManifest, RecoveryShare, RECOVERY_MANIFEST_VERSION,
+};
+
+/// Checks that every share of a RecoveryManifest is one its owner committed when splitting
+/// the seed, each to a different guardian, and that the threshold can be met.
+/// Whether the shares decrypt, and to the owner's seed, only the guardians can find out.
+pub fn verify_recovery_manifest(recovery_manifest: &RecoveryManifest) -> Result<(), Error> {
+    if recovery_manifest.version != RECOVERY_MANIFEST_VERSION {
+        return Err(Error::UnsupportedRecoveryManifestVersion(
+            recovery_manifest.version,
+        ));
+    }
+    let mut guardians = BTreeSet::new();
+    for record in recovery_manifest.recovery_shares.iter() {
+        let (_, recovery_share) = verify_entry_record::<RecoveryShare>(record)?;
+        if *record.action().author() != recovery_manifest.owner {
+            return Err(Error::RecoveryShareNotByOwner);
+        }
+        if recovery_share.released_from.is_some() {
+            return Err(Error::ReleasedRecoveryShare);
+        }
+        if !guardians.insert(recovery_share.recipient) {
+            return Err(Error::DuplicateGuardian);
+        }
+    }
+    if recovery_manifest.threshold == 0 || recovery_manifest.threshold as usize > guardians.len() {
+        return Err(Error::UnsatisfiableRecoveryThreshold);
+    }
+    Ok(())
+}
@@ -18,6 +18,14 @@ pub fn create_authority_spec(authority_spec: AuthoritySpec) -> ExternResult<Reco
     ))?;
     Ok(record)
 }
+/// Commits `authority_spec`, linking it from its signers, unless it is already on the DHT.
+pub fn publish_authority_spec(authority_spec: &AuthoritySpec) -> ExternResult<EntryHash> {
+    let authority_spec_hash = hash_entry(authority_spec)?;
+    if get(authority_spec_hash.clone(), GetOptions::default())?.is_none() {
+        create_authority_spec(authority_spec.clone())?;
+    }
+    Ok(authority_spec_hash)
+}
 #[hdk_extern]
 pub fn get_authority_spec(authority_spec_hash: ActionHash) -> ExternResult<Option<Record>> {
     get(authority_spec_hash, GetOptions::default())
@@ -32,7 +40,7 @@ pub fn get_authority_specs_for_signer(signer: AgentPubKey) -> ExternResult<Vec<R
     let records: Vec<Record> = HDK
         .with(|hdk| hdk.borrow().get(get_input))?
         .into_iter()
-        .filter_map(|r| r)
+        .flatten()
         .collect();
     Ok(records)
 }
@@ -1,28 +1,294 @@
 use deepkey_integrity::*;
 use hdk::prelude::*;
+
+use crate::{authority_spec::publish_authority_spec, keyset_freeze::ensure_keyset_not_frozen};
+/// Publishes the specs of a committed ChangeRule and links them to it, so that their
+/// signers can find the keysets they guard.
+pub fn link_change_rule_specs(
+    change_rule_hash: ActionHash,
+    change_rule: &ChangeRule,
+) -> ExternResult<()> {
+    for authority_spec in std::iter::once(&change_rule.spec_change.new_spec)
+        .chain(change_rule.spec_change.operation_specs.iter())
+    {
+        create_link(
+            publish_authority_spec(authority_spec)?,
+            change_rule_hash.clone(),
+            LinkTypes::AuthoritySpecToChangeRules,
+            (),
+        )?;
+    }
+    Ok(())
+}
 #[hdk_extern]
 pub fn create_change_rule(change_rule: ChangeRule) -> ExternResult<Record> {
     let change_rule_hash = create_entry(&EntryTypes::ChangeRule(change_rule.clone()))?;
+    link_change_rule_specs(change_rule_hash.clone(), &change_rule)?;
     let record = get(change_rule_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
         WasmErrorInner::Guest(String::from("Could not find the newly created ChangeRule"))
     ))?;
     Ok(record)
 }
-#[hdk_extern]
-pub fn get_change_rule(original_change_rule_hash: ActionHash) -> ExternResult<Option<Record>> {
+/// The outcome of walking a ChangeRule's update chain.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ChangeRuleResolution {
+    // The rule with no updates at the end of an unbranched update chain.
+    Current(Record),
+    // The rule still in force, and the update that replaces it once its veto window has
+    // passed at `effective_from`.
+    Pending {
+        current: Record,
+        update: ActionHash,
+        effective_from: Timestamp,
+    },
+    // Two or more devices updated the same rule concurrently. Which branch governs the
+    // keyset is for its authorizers to settle; none is picked here.
+    Conflict {
+        superseded: Record,
+        updates: Vec<ActionHash>,
+    },
+}
+/// Whether a ChangeRuleVeto has been linked from `change_rule_update`.
+pub fn is_change_rule_update_vetoed(change_rule_update: ActionHash) -> ExternResult<bool> {
     let links = get_links(
-        original_change_rule_hash.clone(),
-        LinkTypes::ChangeRuleUpdates,
+        change_rule_update,
+        LinkTypes::ChangeRuleUpdateToVetoes,
         None,
     )?;
-    let latest_link = links
+    Ok(!links.is_empty())
+}
+/// The ChangeRuleVetoes of `change_rule_hash` and of its updates, for the entries acting
+/// under that rule to cite so that validators see vetoes committed on other chains.
+pub fn get_change_rule_vetoes(change_rule_hash: ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let record_details = get_change_rule_details(change_rule_hash.clone())?;
+    let mut vetoes = Vec::new();
+    for vetoed in std::iter::once(change_rule_hash).chain(
+        record_details
+            .updates
+            .into_iter()
+            .map(|update| update.hashed.hash),
+    ) {
+        for link in get_links(vetoed, LinkTypes::ChangeRuleUpdateToVetoes, None)? {
+            vetoes.push(ActionHash::from(link.target));
+        }
+    }
+    Ok(vetoes)
+}
+fn get_unvetoed_updates(updates: Vec<SignedActionHashed>) -> ExternResult<Vec<SignedActionHashed>> {
+    let mut unvetoed = Vec::new();
+    for update in updates {
+        if !is_change_rule_update_vetoed(update.hashed.hash.clone())? {
+            unvetoed.push(update);
+        }
+    }
+    Ok(unvetoed)
+}
+fn get_change_rule_details(change_rule_hash: ActionHash) -> ExternResult<RecordDetails> {
+    match get_details(change_rule_hash, GetOptions::default())? {
+        Some(Details::Record(record_details)) => Ok(record_details),
+        _ => Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the ChangeRule"
+        )))),
+    }
+}
+/// Walks the update chain from `change_rule_hash`, following each rule's single unvetoed
+/// update until reaching a rule without updates, one whose update has not yet taken effect,
+/// or one with competing updates.
+#[hdk_extern]
+pub fn resolve_change_rule(
+    change_rule_hash: ActionHash,
+) -> ExternResult<Option<ChangeRuleResolution>> {
+    let now = sys_time()?;
+    let mut change_rule_hash = change_rule_hash;
+    loop {
+        let record_details = match get_details(change_rule_hash, GetOptions::default())? {
+            Some(Details::Record(record_details)) => record_details,
+            _ => return Ok(None),
+        };
+        let mut updates = get_unvetoed_updates(record_details.updates)?;
+        match updates.len() {
+            0 => return Ok(Some(ChangeRuleResolution::Current(record_details.record))),
+            1 => {
+                let update = updates.remove(0);
+                let effective_from = change_rule_effective_from(update.action())?;
+                if now < effective_from {
+                    return Ok(Some(ChangeRuleResolution::Pending {
+                        current: record_details.record,
+                        update: update.hashed.hash,
+                        effective_from,
+                    }));
+                }
+                change_rule_hash = update.hashed.hash;
+            }
+            _ => {
+                let mut updates: Vec<ActionHash> = updates
+                    .into_iter()
+                    .map(|update| update.hashed.hash)
+                    .collect();
+                updates.sort();
+                return Ok(Some(ChangeRuleResolution::Conflict {
+                    superseded: record_details.record,
+                    updates,
+                }));
+            }
+        }
+    }
+}
+/// The rules `resolve_change_rule` walks through, from `change_rule_hash` to the one it
+/// resolves to, oldest first. Fails if the chain has branched, as `get_change_rule` does.
+pub fn get_change_rule_history(change_rule_hash: ActionHash) -> ExternResult<Vec<Record>> {
+    let now = sys_time()?;
+    let mut change_rule_hash = change_rule_hash;
+    let mut history = Vec::new();
+    loop {
+        let record_details = get_change_rule_details(change_rule_hash)?;
+        let mut updates = get_unvetoed_updates(record_details.updates)?;
+        match updates.len() {
+            0 => {
+                history.push(record_details.record);
+                return Ok(history);
+            }
+            1 => {
+                history.push(record_details.record);
+                let update = updates.remove(0);
+                if now < change_rule_effective_from(update.action())? {
+                    return Ok(history);
+                }
+                change_rule_hash = update.hashed.hash;
+            }
+            _ => return Err(conflicting_updates_error(&record_details.record)),
+        }
+    }
+}
+fn conflicting_updates_error(superseded: &Record) -> WasmError {
+    wasm_error!(WasmErrorInner::Guest(format!(
+        "Conflicting updates of ChangeRule {}",
+        superseded.action_address()
+    )))
+}
+/// The ChangeRule created with a KeysetRoot, which always immediately follows it on the
+/// chain of the first deepkey agent.
+pub fn get_genesis_change_rule_hash(keyset_root: &Record) -> ExternResult<ActionHash> {
+    let action_seq = keyset_root.action().action_seq() + 1;
+    let activity = get_agent_activity(
+        keyset_root.action().author().clone(),
+        ChainQueryFilter::new().sequence_range(ChainQueryFilterRange::ActionSeqRange(
+            action_seq, action_seq,
+        )),
+        ActivityRequest::Full,
+    )?;
+    activity
+        .valid_activity
         .into_iter()
-        .max_by(|link_a, link_b| link_b.timestamp.cmp(&link_a.timestamp));
-    let latest_change_rule_hash = match latest_link {
-        Some(link) => ActionHash::from(link.target.clone()),
-        None => original_change_rule_hash.clone(),
-    };
-    get(latest_change_rule_hash, GetOptions::default())
+        .next()
+        .map(|(_, change_rule_hash)| change_rule_hash)
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the ChangeRule of the KeysetRoot"
+        ))))
+}
+/// The ChangeRule in force for the keyset, as `get_change_rule` resolves it.
+pub fn get_keyset_change_rule_hash(keyset_root: ActionHash) -> ExternResult<ActionHash> {
+    let keyset_root = get(keyset_root, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the KeysetRoot"))
+    ))?;
+    get_change_rule(get_genesis_change_rule_hash(&keyset_root)?)?
+        .map(|change_rule| change_rule.action_address().clone())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the ChangeRule of the KeysetRoot"
+        ))))
+}
+/// The ChangeRule in force on the update chain starting at `original_change_rule_hash`.
+/// Fails if the chain has branched; `resolve_change_rule` reports the competing updates.
+#[hdk_extern]
+pub fn get_change_rule(original_change_rule_hash: ActionHash) -> ExternResult<Option<Record>> {
+    match resolve_change_rule(original_change_rule_hash)? {
+        Some(ChangeRuleResolution::Current(record)) => Ok(Some(record)),
+        Some(ChangeRuleResolution::Pending { current, .. }) => Ok(Some(current)),
+        Some(ChangeRuleResolution::Conflict { superseded, .. }) => {
+            Err(conflicting_updates_error(&superseded))
+        }
+        None => Ok(None),
+    }
+}
+/// Refuses a ChangeRule that has been vetoed or already has an unvetoed update, even one
+/// still waiting to take effect, so that approvals are only ever collected for, and
+/// applied to, the latest rule.
+pub fn ensure_change_rule_is_current(change_rule_hash: ActionHash) -> ExternResult<ChangeRule> {
+    if is_change_rule_update_vetoed(change_rule_hash.clone())? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::ChangeRuleVetoed.to_string()
+        )));
+    }
+    let record_details = get_change_rule_details(change_rule_hash)?;
+    if !get_unvetoed_updates(record_details.updates)?.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::StaleChangeRule.to_string()
+        )));
+    }
+    ChangeRule::try_from(record_details.record)
+}
+/// Refuses a ChangeRule that does not govern the keyset right now: it has been vetoed, has
+/// not yet taken effect, or an update replacing it already has.
+pub fn ensure_change_rule_in_force(change_rule_hash: ActionHash) -> ExternResult<ChangeRule> {
+    if is_change_rule_update_vetoed(change_rule_hash.clone())? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::ChangeRuleVetoed.to_string()
+        )));
+    }
+    let now = sys_time()?;
+    let record_details = get_change_rule_details(change_rule_hash)?;
+    if change_rule_effective_from(record_details.record.action())? > now {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::ChangeRuleNotYetEffective.to_string()
+        )));
+    }
+    for update in get_unvetoed_updates(record_details.updates)? {
+        if change_rule_effective_from(update.action())? <= now {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                deepkey_integrity::Error::StaleChangeRule.to_string()
+            )));
+        }
+    }
+    ChangeRule::try_from(record_details.record)
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpecChangePayloadInput {
+    pub previous_change_rule_hash: ActionHash,
+    pub new_spec: AuthoritySpec,
+    #[serde(default)]
+    pub operation_specs: OperationSpecs,
+    #[serde(default)]
+    pub update_delay_secs: u64,
+}
+/// The payload the signers of the previous ChangeRule must sign to approve `new_spec` and
+/// `operation_specs`, and the delay before the rule after it can take effect.
+pub fn build_spec_change_payload(input: SpecChangePayloadInput) -> ExternResult<SigningPayload> {
+    let previous_change_rule =
+        ensure_change_rule_is_current(input.previous_change_rule_hash.clone())?;
+    Ok(SigningPayload::from(SpecChangePayload {
+        keyset_root: Some(previous_change_rule.keyset_root),
+        dna_hash: dna_info()?.hash,
+        prev_change_rule: Some(input.previous_change_rule_hash),
+        revision: previous_change_rule.revision + 1,
+        new_spec: input.new_spec,
+        operation_specs: input.operation_specs,
+        update_delay_secs: input.update_delay_secs,
+    }))
+}
+/// The bytes of `build_spec_change_payload`.
+#[hdk_extern]
+pub fn get_spec_change_payload(input: SpecChangePayloadInput) -> ExternResult<Vec<u8>> {
+    build_spec_change_payload(input)?.to_signable_bytes()
+}
+/// The first ChangeRule of the update chain that `change_rule_hash` belongs to.
+pub fn get_original_change_rule_hash(change_rule_hash: ActionHash) -> ExternResult<ActionHash> {
+    let mut change_rule_hash = change_rule_hash;
+    loop {
+        match must_get_action(change_rule_hash.clone())?.action() {
+            Action::Update(update) => change_rule_hash = update.original_action_address.clone(),
+            _ => return Ok(change_rule_hash),
+        }
+    }
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UpdateChangeRuleInput {
@@ -32,6 +298,9 @@ pub struct UpdateChangeRuleInput {
 }
 #[hdk_extern]
 pub fn update_change_rule(input: UpdateChangeRuleInput) -> ExternResult<Record> {
+    let previous_change_rule =
+        ensure_change_rule_is_current(input.previous_change_rule_hash.clone())?;
+    ensure_keyset_not_frozen(previous_change_rule.keyset_root)?;
     let updated_change_rule_hash = update_entry(
         input.previous_change_rule_hash.clone(),
         &input.updated_change_rule,
@@ -42,6 +311,7 @@ pub fn update_change_rule(input: UpdateChangeRuleInput) -> ExternResult<Record>
         LinkTypes::ChangeRuleUpdates,
         (),
     )?;
+    link_change_rule_specs(updated_change_rule_hash.clone(), &input.updated_change_rule)?;
     let record =
         get(updated_change_rule_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
             WasmErrorInner::Guest(String::from("Could not find the newly updated ChangeRule"))
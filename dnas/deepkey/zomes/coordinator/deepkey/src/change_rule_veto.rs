@@ -0,0 +1,75 @@
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::keyset_freeze::{ensure_keyset_not_frozen, get_keyset_freezes};
+/// Commits a veto of a ChangeRule update, citing the freezes of its keyset.
+#[hdk_extern]
+pub fn create_change_rule_veto(change_rule_veto: ChangeRuleVeto) -> ExternResult<Record> {
+    let record = get(
+        change_rule_veto.change_rule_update.clone(),
+        GetOptions::default(),
+    )?
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not find the ChangeRule update"
+    ))))?;
+    let keyset_root = ChangeRule::try_from(record)?.keyset_root;
+    ensure_keyset_not_frozen(keyset_root.clone())?;
+    let change_rule_veto = ChangeRuleVeto {
+        keyset_freezes: get_keyset_freezes(keyset_root)?,
+        ..change_rule_veto
+    };
+    let change_rule_veto_hash =
+        create_entry(&EntryTypes::ChangeRuleVeto(change_rule_veto.clone()))?;
+    create_link(
+        change_rule_veto.change_rule_update.clone(),
+        change_rule_veto_hash.clone(),
+        LinkTypes::ChangeRuleUpdateToVetoes,
+        (),
+    )?;
+    let record = get(change_rule_veto_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created ChangeRuleVeto"
+        ))
+    ))?;
+    Ok(record)
+}
+#[hdk_extern]
+pub fn get_change_rule_veto(change_rule_veto_hash: ActionHash) -> ExternResult<Option<Record>> {
+    get(change_rule_veto_hash, GetOptions::default())
+}
+#[hdk_extern]
+pub fn get_change_rule_vetoes_for_change_rule_update(
+    change_rule_update_hash: ActionHash,
+) -> ExternResult<Vec<Record>> {
+    let links = get_links(
+        change_rule_update_hash,
+        LinkTypes::ChangeRuleUpdateToVetoes,
+        None,
+    )?;
+    let get_input: Vec<GetInput> = links
+        .into_iter()
+        .map(|link| GetInput::new(ActionHash::from(link.target).into(), GetOptions::default()))
+        .collect();
+    let records: Vec<Record> = HDK
+        .with(|hdk| hdk.borrow().get(get_input))?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(records)
+}
+/// The bytes the signers of the rule being replaced must sign to veto
+/// `change_rule_update_hash`. A device of the keyset can veto without them.
+#[hdk_extern]
+pub fn get_change_rule_veto_payload(change_rule_update_hash: ActionHash) -> ExternResult<Vec<u8>> {
+    let record =
+        get(change_rule_update_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Could not find the ChangeRule update"))
+        ))?;
+    let change_rule = ChangeRule::try_from(record)?;
+    SigningPayload::from(ChangeRuleVetoPayload {
+        keyset_root: change_rule.keyset_root,
+        dna_hash: dna_info()?.hash,
+        change_rule_update: change_rule_update_hash,
+    })
+    .to_signable_bytes()
+}
@@ -1,6 +1,11 @@
 use deepkey_integrity::*;
 use hdk::prelude::*;
 
+use crate::change_rule::{
+    ensure_change_rule_in_force, get_change_rule_vetoes, get_keyset_change_rule_hash,
+};
+use crate::device_invite_acceptance::create_device_invite_acceptance;
+use crate::keyset_freeze::{ensure_keyset_not_frozen, get_keyset_freezes};
 use crate::source_of_authority::*;
 
 pub fn create_device_invite(device_invite: DeviceInvite) -> ExternResult<Record> {
@@ -40,7 +45,7 @@ pub fn get_device_invites_for_keyset_root(
     let records: Vec<Record> = HDK
         .with(|hdk| hdk.borrow().get(get_input))?
         .into_iter()
-        .filter_map(|r| r)
+        .flatten()
         .collect();
     Ok(records)
 }
@@ -54,7 +59,7 @@ pub fn get_device_invites_for_invitee(invitee: AgentPubKey) -> ExternResult<Vec<
     let records: Vec<Record> = HDK
         .with(|hdk| hdk.borrow().get(get_input))?
         .into_iter()
-        .filter_map(|r| r)
+        .flatten()
         .collect();
     Ok(records)
 }
@@ -64,22 +69,91 @@ pub fn get_device_invites_for_invitee(invitee: AgentPubKey) -> ExternResult<Vec<
 /// This function will create a new device invitation for the given agent and then return the
 /// acceptance for that invitation.
 ///
-/// This function will fail if the given agent is already a device of the current device.
+/// This function will fail if the given agent is already a device of the current device, or
+/// if the ChangeRule's spec for `DeviceInvite` needs more than this agent's approval.
 #[hdk_extern]
 pub fn invite_agent(agent_to_invite: AgentPubKey) -> ExternResult<DeviceInviteAcceptance> {
-    // let (keyset_root, parent) = local_keyset_parent()?;
+    let keyset_root = query_keyset_root_action_hash(())?;
+    commit_invite(
+        agent_to_invite,
+        get_keyset_change_rule_hash(keyset_root)?,
+        Vec::new(),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InviteAgentInput {
+    pub agent_to_invite: AgentPubKey,
+    pub change_rule: ActionHash,
+    pub authorization: Vec<Authorization>,
+}
+/// `invite_agent` under a ChangeRule whose signers for `DeviceInvite` approved the invite by
+/// signing the bytes `get_device_invite_payload` returns.
+#[hdk_extern]
+pub fn invite_agent_with_authorization(
+    input: InviteAgentInput,
+) -> ExternResult<DeviceInviteAcceptance> {
+    commit_invite(
+        input.agent_to_invite,
+        input.change_rule,
+        input.authorization,
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceInvitePayloadInput {
+    pub invitee: AgentPubKey,
+    pub change_rule: ActionHash,
+}
+/// The bytes this device and the signers of `change_rule` for `DeviceInvite` sign to invite
+/// `invitee` below this device.
+#[hdk_extern]
+pub fn get_device_invite_payload(input: DeviceInvitePayloadInput) -> ExternResult<Vec<u8>> {
+    build_device_invite_payload(input.invitee, input.change_rule)?.to_signable_bytes()
+}
+
+fn build_device_invite_payload(
+    invitee: AgentPubKey,
+    change_rule: ActionHash,
+) -> ExternResult<SigningPayload> {
+    ensure_change_rule_in_force(change_rule.clone())?;
+    Ok(SigningPayload::from(DeviceInvitePayload {
+        keyset_root: query_keyset_root_action_hash(())?,
+        dna_hash: dna_info()?.hash,
+        parent: query_keyset_authority_action_hash(())?,
+        invitee,
+        change_rule,
+    }))
+}
 
-    // This is the invitor; query this chain directly using HDI
-    // query(ChainQueryFilter::new()
-    //     .entry_type(EntryType::App(AppEntryType::from("keyset_root".into())))
-    //     .include_entries(true)
-    //     .limit(1)
-    // )?;
-    // get_source_of_authority_action_hash((agent_info()?.agent_latest_pubkey, )))?;
+fn commit_invite(
+    agent_to_invite: AgentPubKey,
+    change_rule: ActionHash,
+    authorization: Vec<Authorization>,
+) -> ExternResult<DeviceInviteAcceptance> {
     let keyset_root = query_keyset_root_action_hash(())?;
     let parent = query_keyset_authority_action_hash(())?;
+    ensure_keyset_not_frozen(keyset_root.clone())?;
+    let change_rule_entry = ensure_change_rule_in_force(change_rule.clone())?;
 
-    let invite = DeviceInvite::new(keyset_root.clone(), parent, agent_to_invite.clone());
+    let payload = build_device_invite_payload(agent_to_invite.clone(), change_rule.clone())?;
+    let inviter = agent_info()?.agent_latest_pubkey;
+    let inviter_signature = sign_raw(inviter.clone(), payload.to_signable_bytes()?)?;
+    let authorization = if authorization.is_empty() {
+        approve_own_invite(&change_rule_entry, &inviter, &inviter_signature)?
+    } else {
+        authorization
+    };
+    let mut invite = DeviceInvite::new(
+        keyset_root.clone(),
+        parent,
+        agent_to_invite.clone(),
+        inviter_signature,
+        change_rule.clone(),
+        authorization,
+    );
+    invite.vetoes = get_change_rule_vetoes(change_rule)?;
+    invite.keyset_freezes = get_keyset_freezes(keyset_root.clone())?;
     let invite_hash = create_entry(EntryTypes::DeviceInvite(invite.clone()))?;
 
     create_link(
@@ -95,8 +169,82 @@ pub fn invite_agent(agent_to_invite: AgentPubKey) -> ExternResult<DeviceInviteAc
         (),
     )?;
 
-    Ok(DeviceInviteAcceptance::new(
-        keyset_root.clone(),
-        invite_hash,
-    ))
+    let mut device_invite_acceptance = DeviceInviteAcceptance::new(keyset_root, invite_hash);
+    device_invite_acceptance.keyset_freezes = invite.keyset_freezes;
+    Ok(device_invite_acceptance)
+}
+
+/// The inviting device's approval of its own invite, where it alone satisfies the
+/// ChangeRule's spec for `DeviceInvite`.
+fn approve_own_invite(
+    change_rule: &ChangeRule,
+    inviter: &AgentPubKey,
+    inviter_signature: &Signature,
+) -> ExternResult<Vec<Authorization>> {
+    let spec = change_rule
+        .spec_change
+        .spec_for(AuthorityOperation::DeviceInvite);
+    match spec
+        .authorized_signers
+        .iter()
+        .position(|signer| signer == inviter)
+    {
+        Some(position) if spec.policy.is_none() && spec.sigs_required == 1 => {
+            Ok(vec![(position as u8, inviter_signature.clone())])
+        }
+        _ => Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "The ChangeRule's signers for DeviceInvite must approve the invite"
+        )))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeysetRecoveryPayloadInput {
+    pub change_rule_hash: ActionHash,
+    pub invitee: AgentPubKey,
+}
+/// The bytes the signers of the keyset's current ChangeRule must sign to admit `invitee`
+/// when no device of the keyset remains to invite it.
+#[hdk_extern]
+pub fn get_keyset_recovery_payload(input: KeysetRecoveryPayloadInput) -> ExternResult<Vec<u8>> {
+    let change_rule = ensure_change_rule_in_force(input.change_rule_hash.clone())?;
+    SigningPayload::from(KeysetRecoveryPayload {
+        keyset_root: change_rule.keyset_root,
+        dna_hash: dna_info()?.hash,
+        change_rule: input.change_rule_hash,
+        invitee: input.invitee,
+    })
+    .to_signable_bytes()
+}
+
+/// Brings this chain into the keyset of `recovery.change_rule`, by committing a recovery
+/// invite of this agent approved by that rule's signers, and accepting it.
+/// Only a chain without a source of authority can recover a keyset, so the agent must join
+/// with a `recovering` JoiningProof, or where the DNA properties disable `bootstrap_keyset`.
+#[hdk_extern]
+pub fn recover_keyset(recovery: RecoveryAuthorization) -> ExternResult<DeviceInviteAcceptance> {
+    if query_keyset_root_and_leaf().is_ok() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            Error::SourceOfAuthorityExists.to_string()
+        )));
+    }
+    let change_rule = ensure_change_rule_in_force(recovery.change_rule.clone())?;
+    ensure_keyset_not_frozen(change_rule.keyset_root.clone())?;
+    let keyset_freezes = get_keyset_freezes(change_rule.keyset_root.clone())?;
+    let vetoes = get_change_rule_vetoes(recovery.change_rule.clone())?;
+    let mut invite = DeviceInvite::new_recovery(
+        change_rule.keyset_root.clone(),
+        agent_info()?.agent_latest_pubkey,
+        recovery,
+    );
+    invite.vetoes = vetoes;
+    invite.keyset_freezes = keyset_freezes.clone();
+    let invite_record = create_device_invite(invite)?;
+    let mut device_invite_acceptance = DeviceInviteAcceptance::new(
+        change_rule.keyset_root,
+        invite_record.action_address().clone(),
+    );
+    device_invite_acceptance.keyset_freezes = keyset_freezes;
+    create_device_invite_acceptance(device_invite_acceptance.clone())?;
+    Ok(device_invite_acceptance)
 }
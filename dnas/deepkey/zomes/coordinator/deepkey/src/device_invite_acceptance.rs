@@ -1,5 +1,7 @@
 use deepkey_integrity::*;
 use hdk::prelude::*;
+
+use crate::keyset_freeze::{ensure_keyset_not_frozen, get_keyset_freezes};
 #[hdk_extern]
 pub fn create_device_invite_acceptance(
     device_invite_acceptance: DeviceInviteAcceptance,
@@ -42,19 +44,25 @@ pub fn get_device_invite_acceptances_for_device_invite(
     let records: Vec<Record> = HDK
         .with(|hdk| hdk.borrow().get(get_input))?
         .into_iter()
-        .filter_map(|r| r)
+        .flatten()
         .collect();
     Ok(records)
 }
 
 #[hdk_extern]
 pub fn accept_invite(invite_acceptance: DeviceInviteAcceptance) -> ExternResult<ActionHash> {
+    ensure_keyset_not_frozen(invite_acceptance.keyset_root_authority.clone())?;
+    let invite_acceptance = DeviceInviteAcceptance {
+        keyset_freezes: get_keyset_freezes(invite_acceptance.keyset_root_authority.clone())?,
+        ..invite_acceptance
+    };
     // let joining_proof = JoiningProof::new(
     //     KeysetProof::DeviceInviteAcceptance(invite_acceptance.clone()),
     //     MembraneProof::None,
     // );
     // let joining_proof_hash = create_entry(EntryTypes::JoiningProof(joining_proof))?;
-    let acceptance_hash = create_entry(EntryTypes::DeviceInviteAcceptance(invite_acceptance.clone()))?;
+    // Linked from its invite, like every acceptance, so the keyset can find its devices.
+    let record = create_device_invite_acceptance(invite_acceptance)?;
 
-    Ok(acceptance_hash)
+    Ok(record.action_address().clone())
 }
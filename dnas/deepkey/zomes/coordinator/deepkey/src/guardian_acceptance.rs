@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+fn commit_guardian_decision(
+    authority_spec: EntryHash,
+    decision: GuardianDecision,
+) -> ExternResult<Record> {
+    let guardian_acceptance = GuardianAcceptance {
+        authority_spec,
+        decision,
+    };
+    let guardian_acceptance_hash =
+        create_entry(&EntryTypes::GuardianAcceptance(guardian_acceptance.clone()))?;
+    create_link(
+        guardian_acceptance.authority_spec,
+        guardian_acceptance_hash.clone(),
+        LinkTypes::AuthoritySpecToGuardianAcceptances,
+        (),
+    )?;
+    let record = get(guardian_acceptance_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created GuardianAcceptance"
+        ))
+    ))?;
+    Ok(record)
+}
+/// Consents to guarding under the AuthoritySpec with entry hash `authority_spec`.
+#[hdk_extern]
+pub fn accept_authority_spec(authority_spec: EntryHash) -> ExternResult<Record> {
+    commit_guardian_decision(authority_spec, GuardianDecision::Accept)
+}
+/// Declines guarding under the AuthoritySpec with entry hash `authority_spec`, and removes
+/// the links that list it among this agent's specs.
+#[hdk_extern]
+pub fn decline_authority_spec(authority_spec: EntryHash) -> ExternResult<Record> {
+    let record = commit_guardian_decision(authority_spec.clone(), GuardianDecision::Decline)?;
+    let signer = agent_info()?.agent_latest_pubkey;
+    for link in get_links(signer, LinkTypes::SignerToAuthoritySpecs, None)? {
+        let spec_record = get(ActionHash::from(link.target), GetOptions::default())?;
+        let spec_entry_hash = spec_record
+            .as_ref()
+            .and_then(|spec_record| spec_record.action().entry_hash());
+        if spec_entry_hash == Some(&authority_spec) {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(record)
+}
+#[hdk_extern]
+pub fn get_guardian_acceptances_for_authority_spec(
+    authority_spec: EntryHash,
+) -> ExternResult<Vec<Record>> {
+    let links = get_links(
+        authority_spec,
+        LinkTypes::AuthoritySpecToGuardianAcceptances,
+        None,
+    )?;
+    let get_input: Vec<GetInput> = links
+        .into_iter()
+        .map(|link| GetInput::new(ActionHash::from(link.target).into(), GetOptions::default()))
+        .collect();
+    let records: Vec<Record> = HDK
+        .with(|hdk| hdk.borrow().get(get_input))?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(records)
+}
+/// The GuardianAcceptances to cite in a ChangeRule adopting `authority_spec`: the latest
+/// decision of each signer, where it is an acceptance.
+#[hdk_extern]
+pub fn get_consenting_guardian_acceptances(
+    authority_spec: EntryHash,
+) -> ExternResult<Vec<ActionHash>> {
+    let mut latest: HashMap<AgentPubKey, Record> = HashMap::new();
+    for record in get_guardian_acceptances_for_authority_spec(authority_spec)? {
+        let author = record.action().author().clone();
+        match latest.get(&author) {
+            Some(previous) if previous.action().action_seq() >= record.action().action_seq() => {}
+            _ => {
+                latest.insert(author, record);
+            }
+        }
+    }
+    let mut guardian_acceptances = Vec::new();
+    for record in latest.into_values() {
+        let guardian_acceptance = GuardianAcceptance::try_from(record.clone())?;
+        if guardian_acceptance.decision == GuardianDecision::Accept {
+            guardian_acceptances.push(record.action_address().clone());
+        }
+    }
+    Ok(guardian_acceptances)
+}
@@ -1,21 +1,27 @@
-use hdk::prelude::*;
 use deepkey_integrity::*;
+use hdk::prelude::*;
 #[hdk_extern]
 pub fn create_joining_proof(joining_proof: JoiningProof) -> ExternResult<Record> {
-    let joining_proof_hash = create_entry(
-        &EntryTypes::JoiningProof(joining_proof.clone()),
-    )?;
-    let record = get(joining_proof_hash.clone(), GetOptions::default())?
-        .ok_or(
-            wasm_error!(
-                WasmErrorInner::Guest(String::from("Could not find the newly created JoiningProof"))
-            ),
-        )?;
+    let joining_proof_hash = create_entry(&EntryTypes::JoiningProof(joining_proof.clone()))?;
+    let record = get(joining_proof_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created JoiningProof"
+        ))
+    ))?;
     Ok(record)
 }
 #[hdk_extern]
-pub fn get_joining_proof(
-    joining_proof_hash: ActionHash,
-) -> ExternResult<Option<Record>> {
+pub fn get_joining_proof(joining_proof_hash: ActionHash) -> ExternResult<Option<Record>> {
     get(joining_proof_hash, GetOptions::default())
 }
+/// The JoiningProof this chain committed in `init`, if any.
+#[hdk_extern]
+pub fn query_committed_joining_proof(_: ()) -> ExternResult<Option<Record>> {
+    Ok(query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::JoiningProof.try_into()?)
+            .include_entries(true),
+    )?
+    .into_iter()
+    .next())
+}
@@ -0,0 +1,158 @@
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum KeyState {
+    // The KeyAnchor action that registered the key.
+    Valid(SignedActionHashed),
+    // The update or delete that replaced or revoked the key.
+    Invalidated(SignedActionHashed),
+    // The update or delete that later revoked the key as compromised since before the
+    // timestamp asked about: signatures from then on are not to be trusted.
+    Compromised(SignedActionHashed),
+    NotFound,
+}
+
+/// Splits the actions on a KeyAnchor into the creations that are still live at `timestamp`
+/// and the updates/deletes that had invalidated a creation by then, both oldest first.
+fn key_anchor_actions_at(
+    entry_details: EntryDetails,
+    timestamp: Timestamp,
+) -> (Vec<SignedActionHashed>, Vec<SignedActionHashed>) {
+    let before = |action: &SignedActionHashed| action.action().timestamp() <= timestamp;
+    let mut invalidations: Vec<SignedActionHashed> = entry_details
+        .updates
+        .into_iter()
+        .chain(entry_details.deletes)
+        .filter(before)
+        .collect();
+    invalidations.sort_by_key(|action| action.action().timestamp());
+    let invalidated: HashSet<ActionHash> = invalidations
+        .iter()
+        .filter_map(|action| match action.action() {
+            Action::Update(update) => Some(update.original_action_address.clone()),
+            Action::Delete(delete) => Some(delete.deletes_address.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut live: Vec<SignedActionHashed> = entry_details
+        .actions
+        .into_iter()
+        .filter(before)
+        .filter(|action| !invalidated.contains(action.as_hash()))
+        .collect();
+    live.sort_by_key(|action| action.action().timestamp());
+    (live, invalidations)
+}
+
+/// Returns the action hash of the KeyRegistration that currently holds the key, if any.
+pub fn get_live_key_registration(key_anchor: KeyAnchor) -> ExternResult<Option<ActionHash>> {
+    match get_details(hash_entry(key_anchor)?, GetOptions::latest())? {
+        Some(Details::Entry(entry_details)) => {
+            let (live, _) = key_anchor_actions_at(entry_details, sys_time()?);
+            // The action preceding a KeyAnchor is always its KeyRegistration.
+            Ok(live
+                .first()
+                .and_then(|action| action.action().prev_action().cloned()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the action hash of the KeyAnchor that currently holds the key, if any.
+pub fn get_live_key_anchor(key_anchor: KeyAnchor) -> ExternResult<Option<ActionHash>> {
+    match get_details(hash_entry(key_anchor)?, GetOptions::latest())? {
+        Some(Details::Entry(entry_details)) => {
+            let (live, _) = key_anchor_actions_at(entry_details, sys_time()?);
+            Ok(live.first().map(|action| action.as_hash().clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the update or delete that last released the key from a registration, if it
+/// was ever registered: the prior state a KeyGeneration of it must cite.
+pub fn get_prior_key_anchor(key_anchor: KeyAnchor) -> ExternResult<Option<ActionHash>> {
+    match get_details(hash_entry(key_anchor)?, GetOptions::latest())? {
+        Some(Details::Entry(entry_details)) => {
+            let (_, invalidations) = key_anchor_actions_at(entry_details, sys_time()?);
+            Ok(invalidations.last().map(|action| action.as_hash().clone()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The KeyRevocation behind an update or delete of a KeyAnchor, recorded by the
+/// KeyRegistration update that always precedes it.
+pub fn get_key_revocation_of(
+    invalidation: &SignedActionHashed,
+) -> ExternResult<Option<KeyRevocation>> {
+    let key_registration_hash = match invalidation.action().prev_action() {
+        Some(key_registration_hash) => key_registration_hash.clone(),
+        None => return Ok(None),
+    };
+    let record = match get(key_registration_hash, GetOptions::default())? {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+    Ok(match KeyRegistration::try_from(record) {
+        Ok(
+            KeyRegistration::Update(key_revocation, _) | KeyRegistration::Delete(key_revocation),
+        ) => Some(key_revocation),
+        _ => None,
+    })
+}
+
+// The invalidation, made at any time up to now, of `key_anchor_action` by a revocation that
+// dates the compromise of the key to `timestamp` or earlier.
+fn get_compromise_of(
+    key_anchor_action: &SignedActionHashed,
+    invalidations_to_date: &[SignedActionHashed],
+    timestamp: Timestamp,
+) -> ExternResult<Option<SignedActionHashed>> {
+    for invalidation in invalidations_to_date {
+        let invalidated = match invalidation.action() {
+            Action::Update(update) => &update.original_action_address,
+            Action::Delete(delete) => &delete.deletes_address,
+            _ => continue,
+        };
+        if invalidated != key_anchor_action.as_hash() {
+            continue;
+        }
+        if let Some(key_revocation) = get_key_revocation_of(invalidation)? {
+            if matches!(key_revocation.compromised_since, Some(since) if since <= timestamp) {
+                return Ok(Some(invalidation.clone()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// This is not about the device or keyset root, this is about the registered and revoked keys.
+// Pass in now for the timestamp for the current state, or a past timestamp for the state of
+// the key at that time.
+// A key that was live at the timestamp is still reported compromised if it has since been
+// revoked as compromised from that time or earlier.
+#[hdk_extern]
+pub fn key_state((key_anchor, timestamp): (KeyAnchor, Timestamp)) -> ExternResult<KeyState> {
+    Ok(
+        match get_details(hash_entry(key_anchor)?, GetOptions::latest())? {
+            Some(Details::Entry(entry_details)) => {
+                let (_, invalidations_to_date) =
+                    key_anchor_actions_at(entry_details.clone(), sys_time()?);
+                let (live, invalidations) = key_anchor_actions_at(entry_details, timestamp);
+                if let Some(action) = live.into_iter().next() {
+                    match get_compromise_of(&action, &invalidations_to_date, timestamp)? {
+                        Some(invalidation) => KeyState::Compromised(invalidation),
+                        None => KeyState::Valid(action),
+                    }
+                } else if let Some(action) = invalidations.into_iter().next() {
+                    KeyState::Invalidated(action)
+                } else {
+                    KeyState::NotFound
+                }
+            }
+            _ => KeyState::NotFound,
+        },
+    )
+}
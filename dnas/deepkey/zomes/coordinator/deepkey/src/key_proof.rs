@@ -0,0 +1,64 @@
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::{
+    change_rule::{get_change_rule_history, get_genesis_change_rule_hash},
+    key_anchor::get_live_key_registration,
+    keyset_root::get_keyset_root,
+};
+
+fn get_record(action_hash: ActionHash, name: &str) -> ExternResult<Record> {
+    get(action_hash, GetOptions::default())?.ok_or(wasm_error!(WasmErrorInner::Guest(format!(
+        "Could not find the {}",
+        name
+    ))))
+}
+// The invites and acceptances leading from the KeysetRoot down to `keyset_leaf`.
+fn get_invite_path(
+    keyset_root: &ActionHash,
+    keyset_leaf: ActionHash,
+) -> ExternResult<Vec<InviteProof>> {
+    let mut invites = Vec::new();
+    let mut keyset_leaf = keyset_leaf;
+    while keyset_leaf != *keyset_root {
+        let device_invite_acceptance = get_record(keyset_leaf, "DeviceInviteAcceptance")?;
+        let invite = DeviceInviteAcceptance::try_from(device_invite_acceptance.clone())?.invite;
+        let device_invite = get_record(invite, "DeviceInvite")?;
+        keyset_leaf = DeviceInvite::try_from(device_invite.clone())?.parent;
+        invites.push(InviteProof {
+            device_invite,
+            device_invite_acceptance,
+        });
+    }
+    invites.reverse();
+    Ok(invites)
+}
+/// Bundles the records proving that `key` is registered in its keyset, for checking
+/// without network access. Fails while the keyset's ChangeRule has competing updates, as
+/// `get_change_rule_history` does.
+#[hdk_extern]
+pub fn export_key_proof(key: AgentPubKey) -> ExternResult<KeyProof> {
+    let key_registration_hash =
+        get_live_key_registration(KeyAnchor::from(&key))?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("The key is not registered"))
+        ))?;
+    let key_registration = get_record(key_registration_hash.clone(), "KeyRegistration")?;
+    let (keyset_root_hash, keyset_leaf) = get_keyset_root_and_leaf(
+        key_registration.action().author().clone(),
+        key_registration_hash,
+    )?;
+    let keyset_root = get_keyset_root(keyset_root_hash.clone())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the KeysetRoot"))
+    ))?;
+    let change_rules = get_change_rule_history(get_genesis_change_rule_hash(&keyset_root)?)?;
+    Ok(KeyProof {
+        version: KEY_PROOF_VERSION,
+        dna_hash: dna_info()?.hash,
+        key,
+        keyset_root,
+        change_rules,
+        invites: get_invite_path(&keyset_root_hash, keyset_leaf)?,
+        key_registration,
+        exported_at: sys_time()?,
+    })
+}
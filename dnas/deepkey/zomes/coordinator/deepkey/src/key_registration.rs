@@ -1,21 +1,166 @@
 use deepkey_integrity::*;
 use hdk::prelude::*;
 
+use crate::{
+    change_rule::ensure_change_rule_in_force,
+    key_anchor::{get_live_key_anchor, get_live_key_registration, get_prior_key_anchor},
+    keyset_freeze::{ensure_keyset_not_frozen, get_keyset_freezes},
+    source_of_authority::query_keyset_root_and_leaf,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeyGenerationPayloadInput {
+    pub new_key: AgentPubKey,
+    pub timestamp: Timestamp,
+}
+
+/// The bytes the new key must sign, in Lair, before calling `register_key`, and the prior
+/// state of its KeyAnchor they bind it to.
+#[hdk_extern]
+pub fn get_key_generation_payload(
+    input: KeyGenerationPayloadInput,
+) -> ExternResult<(Vec<u8>, Option<ActionHash>)> {
+    let (keyset_root, keyset_leaf) = query_keyset_root_and_leaf()?;
+    let prior_key_anchor = get_prior_key_anchor(KeyAnchor::from(&input.new_key))?;
+    let bytes = SigningPayload::from(KeyGenerationPayload {
+        keyset_root,
+        dna_hash: dna_info()?.hash,
+        keyset_leaf,
+        author: agent_info()?.agent_latest_pubkey,
+        timestamp: input.timestamp,
+        prior_key_anchor: prior_key_anchor.clone(),
+    })
+    .to_signable_bytes()?;
+    Ok((bytes, prior_key_anchor))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeyRevocationPayloadInput {
+    pub prior_key_registration: ActionHash,
+    pub change_rule: ActionHash,
+    pub new_key: Option<AgentPubKey>,
+    pub reason: RevocationReason,
+    pub compromised_since: Option<Timestamp>,
+}
+
+/// The payload the signers of the current ChangeRule must sign to revoke, and optionally
+/// replace, a key.
+pub fn build_key_revocation_payload(
+    input: KeyRevocationPayloadInput,
+) -> ExternResult<SigningPayload> {
+    ensure_change_rule_in_force(input.change_rule.clone())?;
+    let prior_record =
+        get(input.prior_key_registration.clone(), GetOptions::default())?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Could not find the KeyRegistration to revoke"))
+        ))?;
+    let (keyset_root, _) = get_keyset_root_and_leaf(
+        prior_record.action().author().clone(),
+        input.prior_key_registration.clone(),
+    )?;
+    Ok(SigningPayload::from(KeyRevocationPayload {
+        keyset_root,
+        dna_hash: dna_info()?.hash,
+        prior_key_registration: input.prior_key_registration,
+        change_rule: input.change_rule,
+        new_key: input.new_key,
+        reason: input.reason,
+        compromised_since: input.compromised_since,
+    }))
+}
+/// The bytes of `build_key_revocation_payload`.
 #[hdk_extern]
-pub fn register_key(new_key: AgentPubKey) -> ExternResult<()> {
-    let my_pubkey = agent_info()?.agent_latest_pubkey;
-    let author_signature = sign(my_pubkey, new_key.clone())?;
+pub fn get_key_revocation_payload(input: KeyRevocationPayloadInput) -> ExternResult<Vec<u8>> {
+    build_key_revocation_payload(input)?.to_signable_bytes()
+}
+
+/// Commits the revocation of a registered key, or with `key_generation` its replacement:
+/// the update of its KeyRegistration, followed by the deletion or update of its KeyAnchor.
+pub fn commit_key_revocation(
+    key_revocation: KeyRevocation,
+    key_generation: Option<KeyGeneration>,
+) -> ExternResult<ActionHash> {
+    let prior_record = get(
+        key_revocation.prior_key_registration.clone(),
+        GetOptions::default(),
+    )?
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not find the KeyRegistration to revoke"
+    ))))?;
+    let revoked_key = match KeyRegistration::try_from(prior_record)? {
+        KeyRegistration::Create(key_generation) | KeyRegistration::Update(_, key_generation) => {
+            key_generation.new_key
+        }
+        _ => {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                deepkey_integrity::Error::RegistrationWrongOp.to_string()
+            )))
+        }
+    };
+    let key_anchor_hash = get_live_key_anchor(KeyAnchor::from(&revoked_key))?.ok_or(
+        wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the KeyAnchor of the key to revoke"
+        ))),
+    )?;
+    let prior_key_registration = key_revocation.prior_key_registration.clone();
+    match key_generation {
+        Some(key_generation) => {
+            let key_anchor = KeyAnchor::from(&key_generation.new_key);
+            let key_registration_hash = update_entry(
+                prior_key_registration,
+                &KeyRegistration::Update(key_revocation, key_generation),
+            )?;
+            // The KeyAnchor update must immediately follow its KeyRegistration.
+            update_entry(key_anchor_hash, &key_anchor)?;
+            Ok(key_registration_hash)
+        }
+        None => {
+            let key_registration_hash = update_entry(
+                prior_key_registration,
+                &KeyRegistration::Delete(key_revocation),
+            )?;
+            delete_entry(key_anchor_hash)?;
+            Ok(key_registration_hash)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RegisterKeyInput {
+    pub new_key: AgentPubKey,
+    // The new key's signature of `get_key_generation_payload`.
+    pub new_key_signing_of_author: Signature,
+    pub timestamp: Timestamp,
+    // As returned with the payload.
+    pub prior_key_anchor: Option<ActionHash>,
+}
+
+#[hdk_extern]
+pub fn register_key(input: RegisterKeyInput) -> ExternResult<()> {
+    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+    ensure_keyset_not_frozen(keyset_root.clone())?;
+
+    // Refuse a key that another registration already holds, on any chain.
+    let key_anchor = KeyAnchor::from(&input.new_key);
+    if let Some(key_registration_hash) = get_live_key_registration(key_anchor.clone())? {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::KeyAlreadyRegistered(key_registration_hash).to_string()
+        )));
+    }
+
     let key_generation = KeyGeneration {
-        new_key: new_key.clone(),
-        new_key_signing_of_author: author_signature,
+        new_key: input.new_key,
+        new_key_signing_of_author: input.new_key_signing_of_author,
+        timestamp: input.timestamp,
+        prior_key_anchor: input.prior_key_anchor,
+        keyset_freezes: get_keyset_freezes(keyset_root)?,
     };
 
     let key_registration = KeyRegistration::Create(key_generation);
 
     // write the key registration to the chain
     create_entry(EntryTypes::KeyRegistration(key_registration))?;
-    // now write the key anchor
-    // create_entry(EntryTypes::KeyAnchor(KeyAnchor::new(new_key)))?;
+    // now write the key anchor, which must immediately follow its registration
+    create_entry(EntryTypes::KeyAnchor(key_anchor))?;
     Ok(())
 }
 // #[hdk_extern]
@@ -30,7 +30,7 @@ fn get_latest_key_revocation(
         }
         Details::Record(record_details) => Ok(record_details),
     }?;
-    if record_details.deletes.len() > 0 {
+    if !record_details.deletes.is_empty() {
         return Ok(None);
     }
     match record_details.updates.last() {
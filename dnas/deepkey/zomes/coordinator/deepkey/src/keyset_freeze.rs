@@ -0,0 +1,125 @@
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::{
+    change_rule::{ensure_change_rule_in_force, get_change_rule_vetoes},
+    source_of_authority::query_keyset_root_and_leaf,
+};
+
+/// Freezes this agent's keyset until the ChangeRule signers lift it.
+#[hdk_extern]
+pub fn freeze_keyset(_: ()) -> ExternResult<Record> {
+    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+    let keyset_freeze = KeysetFreeze { keyset_root };
+    let keyset_freeze_hash = create_entry(&EntryTypes::KeysetFreeze(keyset_freeze.clone()))?;
+    create_link(
+        keyset_freeze.keyset_root,
+        keyset_freeze_hash.clone(),
+        LinkTypes::KeysetRootToKeysetFreezes,
+        (),
+    )?;
+    let record = get(keyset_freeze_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created KeysetFreeze"
+        ))
+    ))?;
+    Ok(record)
+}
+#[hdk_extern]
+pub fn get_keyset_freeze(keyset_freeze_hash: ActionHash) -> ExternResult<Option<Record>> {
+    get(keyset_freeze_hash, GetOptions::default())
+}
+/// The freezes of `keyset_root`, from any device, that no KeysetUnfreeze has lifted yet.
+#[hdk_extern]
+pub fn get_unlifted_keyset_freezes(keyset_root: ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let mut freezes = Vec::new();
+    for link in get_links(keyset_root, LinkTypes::KeysetRootToKeysetFreezes, None)? {
+        let keyset_freeze_hash = ActionHash::from(link.target);
+        let unfreezes = get_links(
+            keyset_freeze_hash.clone(),
+            LinkTypes::KeysetFreezeToKeysetUnfreezes,
+            None,
+        )?;
+        if unfreezes.is_empty() {
+            freezes.push(keyset_freeze_hash);
+        }
+    }
+    Ok(freezes)
+}
+/// The freezes of `keyset_root`, from any device, and the KeysetUnfreezes lifting them, for
+/// the entries acting on the keyset to cite so that validators see freezes committed on
+/// other chains.
+pub fn get_keyset_freezes(keyset_root: ActionHash) -> ExternResult<Vec<ActionHash>> {
+    let mut keyset_freezes = Vec::new();
+    for link in get_links(keyset_root, LinkTypes::KeysetRootToKeysetFreezes, None)? {
+        let keyset_freeze_hash = ActionHash::from(link.target);
+        for link in get_links(
+            keyset_freeze_hash.clone(),
+            LinkTypes::KeysetFreezeToKeysetUnfreezes,
+            None,
+        )? {
+            keyset_freezes.push(ActionHash::from(link.target));
+        }
+        keyset_freezes.push(keyset_freeze_hash);
+    }
+    Ok(keyset_freezes)
+}
+/// Refuses to commit anything for a keyset that any of its devices has frozen.
+pub fn ensure_keyset_not_frozen(keyset_root: ActionHash) -> ExternResult<()> {
+    if !get_unlifted_keyset_freezes(keyset_root)?.is_empty() {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::KeysetFrozen.to_string()
+        )));
+    }
+    Ok(())
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeysetUnfreezePayloadInput {
+    pub keyset_freeze: ActionHash,
+    pub change_rule: ActionHash,
+}
+/// The bytes the signers of the keyset's current ChangeRule must sign to lift a freeze.
+#[hdk_extern]
+pub fn get_keyset_unfreeze_payload(input: KeysetUnfreezePayloadInput) -> ExternResult<Vec<u8>> {
+    let change_rule = ensure_change_rule_in_force(input.change_rule.clone())?;
+    SigningPayload::from(KeysetUnfreezePayload {
+        keyset_root: change_rule.keyset_root,
+        dna_hash: dna_info()?.hash,
+        keyset_freeze: input.keyset_freeze,
+        change_rule: input.change_rule,
+    })
+    .to_signable_bytes()
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UnfreezeKeysetInput {
+    pub keyset_freeze: ActionHash,
+    pub change_rule: ActionHash,
+    // Signatures of `get_keyset_unfreeze_payload` by the ChangeRule's signers.
+    pub authorization: Vec<Authorization>,
+}
+/// Lifts a freeze of this agent's keyset. Devices that committed the freeze themselves
+/// must also lift it on their own chain, which they can do with the same authorization.
+#[hdk_extern]
+pub fn unfreeze_keyset(input: UnfreezeKeysetInput) -> ExternResult<Record> {
+    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+    let keyset_unfreeze = KeysetUnfreeze {
+        keyset_root,
+        keyset_freeze: input.keyset_freeze,
+        vetoes: get_change_rule_vetoes(input.change_rule.clone())?,
+        change_rule: input.change_rule,
+        authorization: input.authorization,
+    };
+    let keyset_unfreeze_hash = create_entry(&EntryTypes::KeysetUnfreeze(keyset_unfreeze.clone()))?;
+    create_link(
+        keyset_unfreeze.keyset_freeze,
+        keyset_unfreeze_hash.clone(),
+        LinkTypes::KeysetFreezeToKeysetUnfreezes,
+        (),
+    )?;
+    let record = get(keyset_unfreeze_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created KeysetUnfreeze"
+        ))
+    ))?;
+    Ok(record)
+}
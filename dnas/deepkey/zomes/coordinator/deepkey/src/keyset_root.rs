@@ -1,19 +1,46 @@
 use deepkey_integrity::*;
 use hdk::prelude::*;
 
+use crate::change_rule::link_change_rule_specs;
+use crate::device_invite::get_device_invites_for_keyset_root;
+use crate::device_invite_acceptance::get_device_invite_acceptances_for_device_invite;
+use crate::joining_proof::create_joining_proof;
+
 #[hdk_extern]
 pub fn create_keyset_root(_: ()) -> ExternResult<(ActionHash, ActionHash)> {
+    commit_keyset_root(None)
+}
+
+/// Creates a new KeysetRoot and its first ChangeRule. Given a membrane proof, the KeysetRoot
+/// is preceded by a JoiningProof naming it, which must then be the chain's first entry.
+pub fn commit_keyset_root(
+    membrane_proof: Option<deepkey_integrity::MembraneProof>,
+) -> ExternResult<(ActionHash, ActionHash)> {
     let first_deepkey_agent: AgentPubKey = agent_info()?.agent_latest_pubkey;
 
     // There is only one authorized signer: the first deepkey agent (fda)
     let new_authority_spec = AuthoritySpec::new(1, vec![first_deepkey_agent.clone()]);
 
-    let fda_bytes = SerializedBytes::try_from(first_deepkey_agent.clone())
-        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.into())))?;
-    let new_authority_spec_bytes = SerializedBytes::try_from(new_authority_spec.clone())
-        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.into())))?;
+    let dna_hash = dna_info()?.hash;
+    let fda_payload = SigningPayload::from(KeysetRootPayload {
+        dna_hash: dna_hash.clone(),
+        first_deepkey_agent: first_deepkey_agent.clone(),
+    });
+    // The genesis spec is signed before the KeysetRoot exists, so it is not bound to it.
+    let authority_spec_payload = SigningPayload::from(SpecChangePayload {
+        keyset_root: None,
+        dna_hash,
+        prev_change_rule: None,
+        revision: 0,
+        new_spec: new_authority_spec.clone(),
+        operation_specs: OperationSpecs::default(),
+        update_delay_secs: 0,
+    });
 
-    let sigs = sign_ephemeral::<SerializedBytes>(vec![fda_bytes, new_authority_spec_bytes])?;
+    let sigs = sign_ephemeral_raw(vec![
+        fda_payload.to_signable_bytes()?,
+        authority_spec_payload.to_signable_bytes()?,
+    ])?;
     let root_pub_key = sigs.key;
     let mut sig_iter = sigs.signatures.into_iter();
     let sig_error_closure = || {
@@ -26,15 +53,24 @@ pub fn create_keyset_root(_: ()) -> ExternResult<(ActionHash, ActionHash)> {
     let auth_spec_signature = sig_iter.next().ok_or_else(sig_error_closure)?;
 
     let keyset_root = KeysetRoot::new(first_deepkey_agent.clone(), root_pub_key, fda_signature);
+    if let Some(membrane_proof) = membrane_proof {
+        create_joining_proof(JoiningProof::new(
+            SourceOfAuthority::KeysetRoot(keyset_root.clone()),
+            membrane_proof,
+        ))?;
+    }
     let keyset_root_hash = create_entry(EntryTypes::KeysetRoot(keyset_root))?;
 
     let spec_change = AuthorizedSpecChange::new(new_authority_spec, vec![(0, auth_spec_signature)]);
     // TODO: Should the keyset_leaf here be a SourceOfAuthority::KeysetRoot hash?
-    let change_rule_hash = create_entry(EntryTypes::ChangeRule(ChangeRule::new(
+    let change_rule = ChangeRule::new(
         keyset_root_hash.clone(),
         keyset_root_hash.clone(),
+        0,
         spec_change,
-    )))?;
+    );
+    let change_rule_hash = create_entry(EntryTypes::ChangeRule(change_rule.clone()))?;
+    link_change_rule_specs(change_rule_hash.clone(), &change_rule)?;
 
     // let keyset_root_record =
     //     get(keyset_root_hash.clone(), GetOptions::default())?.ok_or(wasm_error!(
@@ -57,3 +93,23 @@ pub fn create_keyset_root(_: ()) -> ExternResult<(ActionHash, ActionHash)> {
 pub fn get_keyset_root(keyset_root_hash: ActionHash) -> ExternResult<Option<Record>> {
     get(keyset_root_hash, GetOptions::default())
 }
+/// Every agent that has been a device of the keyset: the author of its KeysetRoot and of
+/// each acceptance of an invite into it, whether or not they have since left.
+#[hdk_extern]
+pub fn get_keyset_devices(keyset_root_hash: ActionHash) -> ExternResult<Vec<AgentPubKey>> {
+    let keyset_root = get_keyset_root(keyset_root_hash.clone())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the KeysetRoot"))
+    ))?;
+    let mut devices = vec![keyset_root.action().author().clone()];
+    for device_invite in get_device_invites_for_keyset_root(keyset_root_hash)? {
+        for acceptance in
+            get_device_invite_acceptances_for_device_invite(device_invite.action_address().clone())?
+        {
+            let device = acceptance.action().author().clone();
+            if !devices.contains(&device) {
+                devices.push(device);
+            }
+        }
+    }
+    Ok(devices)
+}
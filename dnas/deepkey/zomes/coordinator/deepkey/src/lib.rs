@@ -1,20 +1,70 @@
 pub mod authority_spec;
 pub mod authorized_spec_change;
 pub mod change_rule;
+pub mod change_rule_veto;
 pub mod device_invite;
 pub mod device_invite_acceptance;
+pub mod guardian_acceptance;
+pub mod joining_proof;
+pub mod key_anchor;
 pub mod key_generation;
+pub mod key_proof;
 pub mod key_registration;
 pub mod key_revocation;
+pub mod keyset_freeze;
 pub mod keyset_root;
+pub mod proposal;
+pub mod recovery_share;
+pub mod revocation_list;
+mod shamir;
+pub mod signer_dashboard;
 pub mod source_of_authority;
 use deepkey_integrity::*;
 use hdk::prelude::*;
-#[allow(unused_imports)]
-use keyset_root::create_keyset_root;
+use device_invite_acceptance::create_device_invite_acceptance;
+use joining_proof::create_joining_proof;
+use keyset_root::commit_keyset_root;
+use source_of_authority::query_joining_proof;
+/// Gives the chain its source of authority: the acceptance a device was admitted with, or
+/// else a new KeysetRoot and ChangeRule unless the DNA properties disable it or the agent
+/// joined to recover a keyset.
+/// Either is preceded by a JoiningProof recording how the agent joined.
+/// Also grants anyone the right to signal this agent about proposals it must sign.
 #[hdk_extern]
 pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
-    // create_keyset_root(())?;
+    match query_joining_proof()? {
+        Some(JoiningProof { recovering: true, .. }) => {}
+        Some(JoiningProof {
+            source_of_authority:
+                Some(SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance)),
+            membrane_proof,
+            ..
+        }) => {
+            create_joining_proof(JoiningProof::new(
+                SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance.clone()),
+                membrane_proof,
+            ))?;
+            create_device_invite_acceptance(device_invite_acceptance)?;
+        }
+        joining_proof => {
+            if DeepkeyProperties::get()?.bootstrap_keyset {
+                commit_keyset_root(Some(
+                    joining_proof
+                        .map(|joining_proof| joining_proof.membrane_proof)
+                        .unwrap_or(deepkey_integrity::MembraneProof::None),
+                ))?;
+            }
+        }
+    }
+    // Let the signers of a proposal be signalled about it. Granted last, as the JoiningProof
+    // must be the first entry after genesis.
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_info()?.name, "recv_remote_signal".into()));
+    create_cap_grant(CapGrantEntry {
+        tag: "".into(),
+        access: ().into(),
+        functions: GrantedFunctions::Listed(functions),
+    })?;
     Ok(InitCallbackResult::Pass)
 }
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,7 +77,8 @@ pub enum Signal {
     EntryUpdated {
         action: SignedActionHashed,
         app_entry: EntryTypes,
-        original_app_entry: EntryTypes,
+        // Boxed, as two ChangeRules with their operation specs would make every Signal large.
+        original_app_entry: Box<EntryTypes>,
     },
     EntryDeleted {
         action: SignedActionHashed,
@@ -41,6 +92,19 @@ pub enum Signal {
         action: SignedActionHashed,
         link_type: LinkTypes,
     },
+    // Sent to the signers a Proposal asks to sign.
+    ProposalReceived {
+        proposal_hash: ActionHash,
+        proposal: Proposal,
+    },
+}
+/// Passes on the proposals other agents signal to this one.
+#[hdk_extern]
+pub fn recv_remote_signal(signal: ExternIO) -> ExternResult<()> {
+    match signal.decode::<Signal>() {
+        Ok(signal @ Signal::ProposalReceived { .. }) => emit_signal(signal),
+        _ => Ok(()),
+    }
 }
 #[hdk_extern(infallible)]
 pub fn post_commit(committed_actions: Vec<SignedActionHashed>) {
@@ -66,7 +130,7 @@ fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
                     emit_signal(Signal::EntryUpdated {
                         action,
                         app_entry,
-                        original_app_entry,
+                        original_app_entry: Box::new(original_app_entry),
                     })?;
                 }
             }
@@ -105,9 +169,9 @@ fn signal_action(action: SignedActionHashed) -> ExternResult<()> {
                     Ok(())
                 }
                 _ => {
-                    return Err(wasm_error!(WasmErrorInner::Guest(
+                    Err(wasm_error!(WasmErrorInner::Guest(
                         "Create Link should exist".to_string()
-                    )));
+                    )))
                 }
             }
         }
@@ -137,9 +201,9 @@ fn get_entry_for_action(action_hash: &ActionHash) -> ExternResult<Option<EntryTy
             return Ok(None);
         }
     };
-    Ok(EntryTypes::deserialize_from_type(
-        zome_index.clone(),
-        entry_index.clone(),
+    EntryTypes::deserialize_from_type(
+        *zome_index,
+        *entry_index,
         entry,
-    )?)
+    )
 }
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::{
+    authority_spec::publish_authority_spec,
+    change_rule::{
+        build_spec_change_payload, get_change_rule_vetoes, get_original_change_rule_hash,
+        update_change_rule, SpecChangePayloadInput, UpdateChangeRuleInput,
+    },
+    guardian_acceptance::get_consenting_guardian_acceptances,
+    key_registration::{
+        build_key_revocation_payload, commit_key_revocation, KeyRevocationPayloadInput,
+    },
+    keyset_freeze::{ensure_keyset_not_frozen, get_keyset_freezes},
+    source_of_authority::query_keyset_root_and_leaf,
+    Signal,
+};
+
+fn signers_of(proposal: &Proposal) -> ExternResult<Vec<AgentPubKey>> {
+    let operation = proposal
+        .operation()
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::UnsupportedProposal.to_string()
+        )))?;
+    let record = get(proposal.change_rule.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the ChangeRule"))
+    ))?;
+    let change_rule = ChangeRule::try_from(record)?;
+    Ok(change_rule
+        .spec_change
+        .spec_for(operation)
+        .authorized_signers
+        .clone())
+}
+/// Commits a proposal, links it from each signer asked to sign it, and signals those who
+/// are online. Signers who miss the signal find it through `get_proposals_for_signer`.
+fn commit_proposal(proposal: Proposal) -> ExternResult<Record> {
+    let proposal_hash = create_entry(&EntryTypes::Proposal(proposal.clone()))?;
+    let signers = signers_of(&proposal)?;
+    for signer in signers.iter() {
+        create_link(
+            signer.clone(),
+            proposal_hash.clone(),
+            LinkTypes::SignerToProposals,
+            (),
+        )?;
+    }
+    remote_signal(
+        Signal::ProposalReceived {
+            proposal_hash: proposal_hash.clone(),
+            proposal,
+        },
+        signers,
+    )?;
+    let record = get(proposal_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the newly created Proposal"))
+    ))?;
+    Ok(record)
+}
+/// Asks the signers of the previous ChangeRule to approve a spec change, and publishes the
+/// new specs so that their signers can find them and answer them with a GuardianAcceptance.
+#[hdk_extern]
+pub fn propose_spec_change(input: SpecChangePayloadInput) -> ExternResult<Record> {
+    for authority_spec in std::iter::once(&input.new_spec).chain(input.operation_specs.iter()) {
+        publish_authority_spec(authority_spec)?;
+    }
+    let change_rule = input.previous_change_rule_hash.clone();
+    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+    let payload = build_spec_change_payload(input)?;
+    commit_proposal(Proposal {
+        keyset_root,
+        change_rule,
+        payload,
+    })
+}
+/// Asks the signers of the current ChangeRule to approve revoking, or replacing, a key.
+#[hdk_extern]
+pub fn propose_revocation(input: KeyRevocationPayloadInput) -> ExternResult<Record> {
+    let change_rule = input.change_rule.clone();
+    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+    let payload = build_key_revocation_payload(input)?;
+    commit_proposal(Proposal {
+        keyset_root,
+        change_rule,
+        payload,
+    })
+}
+#[hdk_extern]
+pub fn get_proposal(proposal_hash: ActionHash) -> ExternResult<Option<Record>> {
+    get(proposal_hash, GetOptions::default())
+}
+/// The proposals `signer` has been asked to sign.
+#[hdk_extern]
+pub fn get_proposals_for_signer(signer: AgentPubKey) -> ExternResult<Vec<Record>> {
+    let links = get_links(signer, LinkTypes::SignerToProposals, None)?;
+    let get_input: Vec<GetInput> = links
+        .into_iter()
+        .map(|link| GetInput::new(ActionHash::from(link.target).into(), GetOptions::default()))
+        .collect();
+    let records: Vec<Record> = HDK
+        .with(|hdk| hdk.borrow().get(get_input))?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(records)
+}
+#[hdk_extern]
+pub fn get_proposal_signatures(proposal_hash: ActionHash) -> ExternResult<Vec<Record>> {
+    let links = get_links(proposal_hash, LinkTypes::ProposalToSignatures, None)?;
+    let get_input: Vec<GetInput> = links
+        .into_iter()
+        .map(|link| GetInput::new(ActionHash::from(link.target).into(), GetOptions::default()))
+        .collect();
+    let records: Vec<Record> = HDK
+        .with(|hdk| hdk.borrow().get(get_input))?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(records)
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignProposalInput {
+    pub proposal: ActionHash,
+    pub position: u8,
+    // A signature made elsewhere, e.g. by a hardware key. When absent, this agent's key
+    // signs the payload.
+    pub signature: Option<Signature>,
+}
+/// Contributes a signature of a proposal's payload.
+#[hdk_extern]
+pub fn sign_proposal(input: SignProposalInput) -> ExternResult<Record> {
+    let record = get(input.proposal.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the Proposal"))
+    ))?;
+    let proposal = Proposal::try_from(record)?;
+    let signature = match input.signature {
+        Some(signature) => signature,
+        None => sign_raw(
+            agent_info()?.agent_latest_pubkey,
+            proposal.payload.to_signable_bytes()?,
+        )?,
+    };
+    let proposal_signature = ProposalSignature {
+        proposal: input.proposal,
+        authorization: (input.position, signature),
+    };
+    let proposal_signature_hash =
+        create_entry(&EntryTypes::ProposalSignature(proposal_signature.clone()))?;
+    create_link(
+        proposal_signature.proposal,
+        proposal_signature_hash.clone(),
+        LinkTypes::ProposalToSignatures,
+        (),
+    )?;
+    let record = get(proposal_signature_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created ProposalSignature"
+        ))
+    ))?;
+    Ok(record)
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FinalizeProposalInput {
+    pub proposal: ActionHash,
+    // For a key replacement, the new key's proof of possession, made for this agent.
+    pub key_generation: Option<KeyGeneration>,
+}
+/// Commits the ChangeRule update or key revocation a proposal asked for, once the
+/// signatures collected for it satisfy `authorize_change_rule`.
+#[hdk_extern]
+pub fn finalize_proposal(input: FinalizeProposalInput) -> ExternResult<ActionHash> {
+    let record = get(input.proposal.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the Proposal"))
+    ))?;
+    let proposal = Proposal::try_from(record)?;
+    let operation = proposal
+        .operation()
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::UnsupportedProposal.to_string()
+        )))?;
+    let record = get(proposal.change_rule.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the ChangeRule"))
+    ))?;
+    let change_rule = ChangeRule::try_from(record)?;
+
+    // One signature per position. A spec without a policy needs exactly `sigs_required`.
+    let mut signatures: BTreeMap<u8, Signature> = BTreeMap::new();
+    for record in get_proposal_signatures(input.proposal)? {
+        let (position, signature) = ProposalSignature::try_from(record)?.authorization;
+        signatures.entry(position).or_insert(signature);
+    }
+    let mut authorization: Vec<Authorization> = signatures.into_iter().collect();
+    let spec = change_rule.spec_change.spec_for(operation);
+    if spec.policy.is_none() {
+        authorization.truncate(spec.sigs_required as usize);
+    }
+    authorize_change_rule(&change_rule, operation, &authorization, &proposal.payload)
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))?;
+
+    match proposal.payload {
+        SigningPayload::SpecChange(payload) => {
+            let (_, keyset_leaf) = query_keyset_root_and_leaf()?;
+            let spec_change = AuthorizedSpecChange::new_with_operation_specs(
+                payload.new_spec,
+                payload.operation_specs,
+                authorization,
+            )
+            .with_update_delay_secs(payload.update_delay_secs);
+            let mut guardian_acceptances = Vec::new();
+            for authority_spec in
+                std::iter::once(&spec_change.new_spec).chain(spec_change.operation_specs.iter())
+            {
+                if authority_spec.requires_consent {
+                    guardian_acceptances.extend(get_consenting_guardian_acceptances(hash_entry(
+                        authority_spec,
+                    )?)?);
+                }
+            }
+            guardian_acceptances.sort();
+            guardian_acceptances.dedup();
+            let mut updated_change_rule = ChangeRule::new(
+                proposal.keyset_root.clone(),
+                keyset_leaf,
+                payload.revision,
+                spec_change,
+            );
+            updated_change_rule.guardian_acceptances = guardian_acceptances;
+            updated_change_rule.vetoes = get_change_rule_vetoes(proposal.change_rule.clone())?;
+            updated_change_rule.keyset_freezes = get_keyset_freezes(proposal.keyset_root)?;
+            let record = update_change_rule(UpdateChangeRuleInput {
+                original_change_rule_hash: get_original_change_rule_hash(
+                    proposal.change_rule.clone(),
+                )?,
+                previous_change_rule_hash: proposal.change_rule,
+                updated_change_rule,
+            })?;
+            Ok(record.action_address().clone())
+        }
+        SigningPayload::KeyRevocation(payload) => {
+            ensure_keyset_not_frozen(payload.keyset_root.clone())?;
+            let key_generation = match input.key_generation {
+                Some(mut key_generation) => {
+                    let (keyset_root, _) = query_keyset_root_and_leaf()?;
+                    key_generation.keyset_freezes = get_keyset_freezes(keyset_root)?;
+                    Some(key_generation)
+                }
+                None => None,
+            };
+            commit_key_revocation(
+                KeyRevocation {
+                    prior_key_registration: payload.prior_key_registration,
+                    vetoes: get_change_rule_vetoes(payload.change_rule.clone())?,
+                    change_rule: payload.change_rule,
+                    revocation_authorization: authorization,
+                    reason: payload.reason,
+                    compromised_since: payload.compromised_since,
+                    keyset_freezes: get_keyset_freezes(payload.keyset_root)?,
+                },
+                key_generation,
+            )
+        }
+        _ => Err(wasm_error!(WasmErrorInner::Guest(
+            deepkey_integrity::Error::UnsupportedProposal.to_string()
+        ))),
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeSet;
+
+use deepkey_integrity::*;
+use hdk::prelude::holochain_zome_types::x_salsa20_poly1305::nonce::XSalsa20Poly1305Nonce;
+use hdk::prelude::*;
+
+use crate::shamir;
+
+fn to_array<const N: usize>(bytes: &[u8]) -> ExternResult<[u8; N]> {
+    bytes.try_into().map_err(|_| {
+        wasm_error!(WasmErrorInner::Guest(format!(
+            "Expected {} bytes, got {}",
+            N,
+            bytes.len()
+        )))
+    })
+}
+fn get_encryption_key(recovery_encryption_key_hash: ActionHash) -> ExternResult<X25519PubKey> {
+    let record = get(recovery_encryption_key_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the RecoveryEncryptionKey"))
+    ))?;
+    let recovery_encryption_key = RecoveryEncryptionKey::try_from(record)?;
+    Ok(X25519PubKey::from(recovery_encryption_key.encryption_key))
+}
+/// Creates an X25519 keypair in this agent's keystore and publishes its public key for
+/// recovery shares to be encrypted to.
+#[hdk_extern]
+pub fn publish_recovery_encryption_key(_: ()) -> ExternResult<Record> {
+    let encryption_key = create_x25519_keypair()?;
+    let recovery_encryption_key = RecoveryEncryptionKey {
+        encryption_key: to_array(encryption_key.as_ref())?,
+    };
+    let recovery_encryption_key_hash =
+        create_entry(&EntryTypes::RecoveryEncryptionKey(recovery_encryption_key))?;
+    create_link(
+        agent_info()?.agent_latest_pubkey,
+        recovery_encryption_key_hash.clone(),
+        LinkTypes::AgentToRecoveryEncryptionKeys,
+        (),
+    )?;
+    let record = get(recovery_encryption_key_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from(
+            "Could not find the newly created RecoveryEncryptionKey"
+        ))
+    ))?;
+    Ok(record)
+}
+/// The RecoveryEncryptionKey `agent` published last, if any.
+#[hdk_extern]
+pub fn get_recovery_encryption_key(agent: AgentPubKey) -> ExternResult<Option<Record>> {
+    let latest = get_links(agent, LinkTypes::AgentToRecoveryEncryptionKeys, None)?
+        .into_iter()
+        .max_by_key(|link| link.timestamp);
+    match latest {
+        Some(link) => get(ActionHash::from(link.target), GetOptions::default()),
+        None => Ok(None),
+    }
+}
+// This agent's latest RecoveryEncryptionKey, read from its own chain so that a key
+// published moments ago is found; one is published if there is none yet.
+fn get_own_recovery_encryption_key() -> ExternResult<(ActionHash, X25519PubKey)> {
+    let record = match query(
+        ChainQueryFilter::new()
+            .entry_type(UnitEntryTypes::RecoveryEncryptionKey.try_into()?)
+            .include_entries(true),
+    )?
+    .into_iter()
+    .last()
+    {
+        Some(record) => record,
+        None => publish_recovery_encryption_key(())?,
+    };
+    let recovery_encryption_key_hash = record.action_address().clone();
+    let recovery_encryption_key = RecoveryEncryptionKey::try_from(record)?;
+    Ok((
+        recovery_encryption_key_hash,
+        X25519PubKey::from(recovery_encryption_key.encryption_key),
+    ))
+}
+fn commit_recovery_share(
+    recipient: AgentPubKey,
+    share: Vec<u8>,
+    sender_encryption_key: (ActionHash, X25519PubKey),
+    released_from: Option<ActionHash>,
+) -> ExternResult<ActionHash> {
+    let recipient_encryption_key_hash = get_recovery_encryption_key(recipient.clone())?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(format!(
+            "{} has not published a RecoveryEncryptionKey",
+            recipient
+        ))))?
+        .action_address()
+        .clone();
+    let recipient_key = get_encryption_key(recipient_encryption_key_hash.clone())?;
+    let encrypted_data = x_25519_x_salsa20_poly1305_encrypt(
+        sender_encryption_key.1,
+        recipient_key,
+        XSalsa20Poly1305Data::from(share),
+    )?;
+    let recovery_share = RecoveryShare {
+        recipient: recipient.clone(),
+        recipient_encryption_key: recipient_encryption_key_hash,
+        sender_encryption_key: sender_encryption_key.0,
+        nonce: to_array(encrypted_data.as_nonce_ref().as_ref())?,
+        encrypted_share: encrypted_data.as_encrypted_data_ref().to_vec(),
+        released_from,
+    };
+    let recovery_share_hash = create_entry(&EntryTypes::RecoveryShare(recovery_share))?;
+    create_link(
+        recipient,
+        recovery_share_hash.clone(),
+        LinkTypes::RecipientToRecoveryShares,
+        (),
+    )?;
+    Ok(recovery_share_hash)
+}
+// Decrypts a share addressed to this agent.
+fn open_recovery_share(recovery_share_hash: ActionHash) -> ExternResult<Vec<u8>> {
+    let record = get(recovery_share_hash, GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the RecoveryShare"))
+    ))?;
+    let recovery_share = RecoveryShare::try_from(record)?;
+    if recovery_share.recipient != agent_info()?.agent_latest_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "The RecoveryShare is addressed to another agent"
+        ))));
+    }
+    let encrypted_data = XSalsa20Poly1305EncryptedData::new(
+        XSalsa20Poly1305Nonce::from(recovery_share.nonce),
+        recovery_share.encrypted_share,
+    );
+    let share = x_25519_x_salsa20_poly1305_decrypt(
+        get_encryption_key(recovery_share.recipient_encryption_key)?,
+        get_encryption_key(recovery_share.sender_encryption_key)?,
+        encrypted_data,
+    )?
+    .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+        "Could not decrypt the RecoveryShare"
+    ))))?;
+    Ok(share.as_ref().to_vec())
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SplitRevocationSeedInput {
+    // The seed of the revocation key; it never leaves this call unencrypted.
+    pub seed: Vec<u8>,
+    // How many guardians must release their shares to rebuild the seed.
+    pub threshold: u8,
+    pub guardians: Vec<AgentPubKey>,
+}
+/// Splits a revocation seed so that any `threshold` of `guardians` can rebuild it, and
+/// hands each guardian a share encrypted to their RecoveryEncryptionKey.
+/// Returns the RecoveryShares in the order of `guardians`.
+#[hdk_extern]
+pub fn split_revocation_seed(input: SplitRevocationSeedInput) -> ExternResult<Vec<ActionHash>> {
+    let distinct_guardians: BTreeSet<&AgentPubKey> = input.guardians.iter().collect();
+    if distinct_guardians.len() != input.guardians.len() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Each guardian can only hold one share"
+        ))));
+    }
+    let randomness_len = input.seed.len() * (input.threshold.max(1) as usize - 1);
+    let randomness = random_bytes(randomness_len as u32)?;
+    let shares = shamir::split(
+        &input.seed,
+        input.threshold,
+        input.guardians.len(),
+        &randomness,
+    )?;
+    let sender_encryption_key = get_own_recovery_encryption_key()?;
+    input
+        .guardians
+        .into_iter()
+        .zip(shares)
+        .map(|(guardian, share)| {
+            commit_recovery_share(guardian, share, sender_encryption_key.clone(), None)
+        })
+        .collect()
+}
+#[hdk_extern]
+pub fn get_recovery_shares_for_recipient(recipient: AgentPubKey) -> ExternResult<Vec<Record>> {
+    let links = get_links(recipient, LinkTypes::RecipientToRecoveryShares, None)?;
+    let get_input: Vec<GetInput> = links
+        .into_iter()
+        .map(|link| GetInput::new(ActionHash::from(link.target).into(), GetOptions::default()))
+        .collect();
+    let records: Vec<Record> = HDK
+        .with(|hdk| hdk.borrow().get(get_input))?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(records)
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReleaseRecoveryShareInput {
+    pub recovery_share: ActionHash,
+    // The recovering device; confirm out of band that it belongs to the seed's owner.
+    pub recipient: AgentPubKey,
+}
+/// Re-encrypts a share this guardian holds to `recipient`'s RecoveryEncryptionKey.
+#[hdk_extern]
+pub fn release_recovery_share(input: ReleaseRecoveryShareInput) -> ExternResult<ActionHash> {
+    let share = open_recovery_share(input.recovery_share.clone())?;
+    commit_recovery_share(
+        input.recipient,
+        share,
+        get_own_recovery_encryption_key()?,
+        Some(input.recovery_share),
+    )
+}
+/// Rebuilds the revocation seed from shares released to this agent.
+#[hdk_extern]
+pub fn reassemble_revocation_seed(recovery_shares: Vec<ActionHash>) -> ExternResult<Vec<u8>> {
+    let shares = recovery_shares
+        .into_iter()
+        .map(open_recovery_share)
+        .collect::<ExternResult<Vec<Vec<u8>>>>()?;
+    shamir::combine(&shares)
+}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportRecoveryManifestInput {
+    // The shares `split_revocation_seed` returned, and the threshold it was called with.
+    pub recovery_shares: Vec<ActionHash>,
+    pub threshold: u8,
+}
+/// Lists the shares of a split of this agent's revocation seed and the guardians holding
+/// them, for keeping with the owner's recovery instructions.
+#[hdk_extern]
+pub fn export_recovery_manifest(
+    input: ExportRecoveryManifestInput,
+) -> ExternResult<RecoveryManifest> {
+    if input.threshold == 0 || input.threshold as usize > input.recovery_shares.len() {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "The threshold must be between 1 and the number of shares"
+        ))));
+    }
+    let owner = agent_info()?.agent_latest_pubkey;
+    let mut recovery_shares = Vec::new();
+    for recovery_share_hash in input.recovery_shares {
+        let record = get(recovery_share_hash, GetOptions::default())?.ok_or(wasm_error!(
+            WasmErrorInner::Guest(String::from("Could not find the RecoveryShare"))
+        ))?;
+        let recovery_share = RecoveryShare::try_from(record.clone())?;
+        if *record.action().author() != owner || recovery_share.released_from.is_some() {
+            return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+                "Only shares this agent split its own seed into can be listed"
+            ))));
+        }
+        recovery_shares.push(record);
+    }
+    Ok(RecoveryManifest {
+        version: RECOVERY_MANIFEST_VERSION,
+        dna_hash: dna_info()?.hash,
+        owner,
+        threshold: input.threshold,
+        recovery_shares,
+        exported_at: sys_time()?,
+    })
+}
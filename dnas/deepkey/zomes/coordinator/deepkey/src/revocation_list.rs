@@ -0,0 +1,123 @@
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::{
+    device_invite::get_device_invites_for_keyset_root,
+    device_invite_acceptance::get_device_invite_acceptances_for_device_invite,
+    keyset_root::get_keyset_root, source_of_authority::query_keyset_root_and_leaf,
+};
+
+// The KeysetRoot and every DeviceInviteAcceptance ever made under it, including those of
+// devices that have since left or rejoined the keyset.
+fn get_keyset_leaves(keyset_root: ActionHash) -> ExternResult<Vec<Record>> {
+    let mut keyset_leaves =
+        vec![
+            get_keyset_root(keyset_root.clone())?.ok_or(wasm_error!(WasmErrorInner::Guest(
+                String::from("Could not find the KeysetRoot")
+            )))?,
+        ];
+    for device_invite in get_device_invites_for_keyset_root(keyset_root)? {
+        keyset_leaves.extend(get_device_invite_acceptances_for_device_invite(
+            device_invite.action_address().clone(),
+        )?);
+    }
+    Ok(keyset_leaves)
+}
+// The KeyRegistrations committed on a device's chain while `keyset_leaf` was its source
+// of authority.
+fn get_key_registrations_under_leaf(keyset_leaf: &Record) -> ExternResult<Vec<Record>> {
+    let device = keyset_leaf.action().author().clone();
+    let activity = get_agent_activity(
+        device.clone(),
+        ChainQueryFilter::new().entry_type(UnitEntryTypes::KeyRegistration.try_into()?),
+        ActivityRequest::Full,
+    )?;
+    let mut key_registrations = Vec::new();
+    for (action_seq, key_registration_hash) in activity.valid_activity {
+        if action_seq <= keyset_leaf.action().action_seq() {
+            continue;
+        }
+        let (_, registration_keyset_leaf) =
+            get_keyset_root_and_leaf(device.clone(), key_registration_hash.clone())?;
+        if registration_keyset_leaf != *keyset_leaf.action_address() {
+            continue;
+        }
+        if let Some(record) = get(key_registration_hash, GetOptions::default())? {
+            key_registrations.push(record);
+        }
+    }
+    Ok(key_registrations)
+}
+// The revocations of the key a KeyRegistration introduced, wherever they were committed.
+fn get_revoked_keys(key_registration: Record) -> ExternResult<Vec<RevokedKey>> {
+    let key = match KeyRegistration::try_from(key_registration.clone())? {
+        KeyRegistration::Create(key_generation) | KeyRegistration::Update(_, key_generation) => {
+            key_generation.new_key
+        }
+        _ => return Ok(vec![]),
+    };
+    let updates = match get_details(
+        key_registration.action_address().clone(),
+        GetOptions::default(),
+    )? {
+        Some(Details::Record(record_details)) => record_details.updates,
+        _ => return Ok(vec![]),
+    };
+    let mut revoked_keys = Vec::new();
+    for update in updates {
+        let record = match get(update.as_hash().clone(), GetOptions::default())? {
+            Some(record) => record,
+            None => continue,
+        };
+        let (key_revocation, replaced_by) = match KeyRegistration::try_from(record)? {
+            KeyRegistration::Update(key_revocation, key_generation) => {
+                (key_revocation, Some(key_generation.new_key))
+            }
+            KeyRegistration::Delete(key_revocation) => (key_revocation, None),
+            _ => continue,
+        };
+        revoked_keys.push(RevokedKey {
+            key: key.clone(),
+            key_revocation: update.as_hash().clone(),
+            revoked_at: update.action().timestamp(),
+            reason: key_revocation.reason,
+            compromised_since: key_revocation.compromised_since,
+            replaced_by,
+        });
+    }
+    Ok(revoked_keys)
+}
+/// Lists every key registered in the keyset that has been revoked or replaced, signed by
+/// this agent, which must be a current device of the keyset.
+#[hdk_extern]
+pub fn export_revocation_list(keyset_root: ActionHash) -> ExternResult<SignedRevocationList> {
+    if query_keyset_root_and_leaf()?.0 != keyset_root {
+        return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Only a current device of the keyset can export its revocation list"
+        ))));
+    }
+    let mut revoked_keys = Vec::new();
+    for keyset_leaf in get_keyset_leaves(keyset_root.clone())? {
+        for key_registration in get_key_registrations_under_leaf(&keyset_leaf)? {
+            revoked_keys.extend(get_revoked_keys(key_registration)?);
+        }
+    }
+    revoked_keys.sort_by_key(|revoked_key| revoked_key.revoked_at);
+    let revocation_list = RevocationList {
+        version: REVOCATION_LIST_VERSION,
+        keyset_root,
+        dna_hash: dna_info()?.hash,
+        issued_at: sys_time()?,
+        revoked_keys,
+    };
+    let signer = agent_info()?.agent_latest_pubkey;
+    let signature = sign_raw(
+        signer.clone(),
+        SigningPayload::from(revocation_list.clone()).to_signable_bytes()?,
+    )?;
+    Ok(SignedRevocationList {
+        revocation_list,
+        signer,
+        signature,
+    })
+}
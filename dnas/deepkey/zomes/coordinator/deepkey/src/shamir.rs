@@ -0,0 +1,203 @@
+//! Shamir secret sharing over GF(2^8), byte by byte.
+//!
+//! A share is encoded as `[threshold, x, y_0, y_1, ..]`: the number of shares needed to
+//! rebuild the secret, the share's non-zero evaluation point, and the value of each byte's
+//! polynomial at that point.
+use hdk::prelude::*;
+
+fn guest_error(message: &str) -> WasmError {
+    wasm_error!(WasmErrorInner::Guest(String::from(message)))
+}
+
+// Multiplication in GF(2^8) reduced by the AES polynomial x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+// a^254, the multiplicative inverse of a non-zero a.
+fn gf_inv(a: u8) -> u8 {
+    let mut inverse = 1;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            inverse = gf_mul(inverse, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    inverse
+}
+
+/// Splits `secret` into `share_count` shares, any `threshold` of which rebuild it.
+/// `randomness` supplies the polynomial coefficients and must hold
+/// `secret.len() * (threshold - 1)` random bytes.
+pub fn split(
+    secret: &[u8],
+    threshold: u8,
+    share_count: usize,
+    randomness: &[u8],
+) -> ExternResult<Vec<Vec<u8>>> {
+    if secret.is_empty() {
+        return Err(guest_error("Cannot split an empty secret"));
+    }
+    if threshold == 0 || threshold as usize > share_count || share_count > u8::MAX as usize {
+        return Err(guest_error(
+            "The threshold must be between 1 and the number of shares, which is at most 255",
+        ));
+    }
+    let degree = threshold as usize - 1;
+    if randomness.len() != secret.len() * degree {
+        return Err(guest_error(
+            "Wrong amount of randomness for the secret and threshold",
+        ));
+    }
+    let shares = (1..=share_count as u8)
+        .map(|x| {
+            let mut share = vec![threshold, x];
+            for (index, byte) in secret.iter().enumerate() {
+                let coefficients = &randomness[index * degree..(index + 1) * degree];
+                // Horner's rule, from the highest coefficient down to the secret byte.
+                let y = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |y, coefficient| gf_mul(y, x) ^ coefficient);
+                share.push(gf_mul(y, x) ^ byte);
+            }
+            share
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Rebuilds the secret from at least `threshold` shares of the same split.
+pub fn combine(shares: &[Vec<u8>]) -> ExternResult<Vec<u8>> {
+    let first = shares.first().ok_or(guest_error("No shares to combine"))?;
+    if first.len() < 3 {
+        return Err(guest_error("Malformed share"));
+    }
+    let threshold = first[0] as usize;
+    if threshold == 0 || shares.len() < threshold {
+        return Err(guest_error("Not enough shares to rebuild the secret"));
+    }
+    let shares = &shares[..threshold];
+    let mut xs = Vec::with_capacity(threshold);
+    for share in shares {
+        if share.len() != first.len() || share[0] != first[0] {
+            return Err(guest_error("The shares are not from the same split"));
+        }
+        if share[1] == 0 || xs.contains(&share[1]) {
+            return Err(guest_error("The shares must have distinct non-zero points"));
+        }
+        xs.push(share[1]);
+    }
+    // Lagrange interpolation at zero; subtraction in GF(2^8) is xor.
+    let basis: Vec<u8> = xs
+        .iter()
+        .enumerate()
+        .map(|(i, x_i)| {
+            xs.iter()
+                .enumerate()
+                .filter(|(m, _)| *m != i)
+                .fold(1, |basis, (_, x_m)| {
+                    gf_mul(basis, gf_mul(*x_m, gf_inv(x_m ^ x_i)))
+                })
+        })
+        .collect();
+    let secret = (2..first.len())
+        .map(|index| {
+            shares
+                .iter()
+                .zip(basis.iter())
+                .fold(0, |byte, (share, basis)| {
+                    byte ^ gf_mul(share[index], *basis)
+                })
+        })
+        .collect();
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"revocation seed";
+
+    fn randomness(threshold: u8) -> Vec<u8> {
+        (0..SECRET.len() * (threshold as usize - 1))
+            .map(|index| (index * 37 + 11) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_with_exactly_threshold_shares() {
+        let shares = split(SECRET, 3, 5, &randomness(3)).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert_eq!(combine(&shares[..3]).unwrap(), SECRET);
+        assert_eq!(combine(&shares[2..]).unwrap(), SECRET);
+        let mixed = vec![shares[4].clone(), shares[0].clone(), shares[2].clone()];
+        assert_eq!(combine(&mixed).unwrap(), SECRET);
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_do_not_rebuild() {
+        let shares = split(SECRET, 3, 5, &randomness(3)).unwrap();
+        assert!(combine(&shares[..2]).is_err());
+        assert!(combine(&[]).is_err());
+        // Lowering the threshold a share claims does not rebuild the secret either.
+        let forged: Vec<Vec<u8>> = shares[..2]
+            .iter()
+            .map(|share| {
+                let mut share = share.clone();
+                share[0] = 2;
+                share
+            })
+            .collect();
+        assert_ne!(combine(&forged).unwrap(), SECRET);
+    }
+
+    #[test]
+    fn rejects_duplicate_points() {
+        let shares = split(SECRET, 2, 3, &randomness(2)).unwrap();
+        assert!(combine(&[shares[1].clone(), shares[1].clone()]).is_err());
+        let mut zero = shares[0].clone();
+        zero[1] = 0;
+        assert!(combine(&[zero, shares[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn rejects_threshold_zero() {
+        assert!(split(SECRET, 0, 3, &[]).is_err());
+        let mut share = split(SECRET, 1, 1, &[]).unwrap().remove(0);
+        share[0] = 0;
+        assert!(combine(&[share]).is_err());
+    }
+
+    #[test]
+    fn threshold_one_shares_are_the_secret() {
+        let shares = split(SECRET, 1, 3, &[]).unwrap();
+        for share in shares.iter() {
+            assert_eq!(&share[2..], SECRET);
+            assert_eq!(combine(std::slice::from_ref(share)).unwrap(), SECRET);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(split(SECRET, 4, 3, &randomness(4)).is_err());
+        assert!(split(SECRET, 2, 3, &[]).is_err());
+        assert!(split(&[], 1, 3, &[]).is_err());
+    }
+}
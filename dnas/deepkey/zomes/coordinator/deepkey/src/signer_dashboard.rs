@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use deepkey_integrity::*;
+use hdk::prelude::*;
+
+use crate::{
+    authority_spec::get_authority_specs_for_signer,
+    change_rule::{
+        ensure_change_rule_in_force, ensure_change_rule_is_current, resolve_change_rule,
+        ChangeRuleResolution,
+    },
+    proposal::{get_proposal_signatures, get_proposals_for_signer},
+};
+
+/// A keyset that a signer guards, as of its ChangeRule in force.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GuardedKeyset {
+    pub keyset_root: ActionHash,
+    // The ChangeRule in force, with any update still waiting to take effect, or the
+    // competing updates if the rule's update chain has branched.
+    pub change_rule: ChangeRuleResolution,
+    // The operations whose spec in that rule lists the signer.
+    pub operations: Vec<AuthorityOperation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignerDashboard {
+    pub keysets: Vec<GuardedKeyset>,
+    // Proposals to the ChangeRules in force that the signer has not signed yet.
+    pub open_proposals: Vec<Record>,
+}
+
+fn change_rule_in_force(resolution: &ChangeRuleResolution) -> &Record {
+    match resolution {
+        ChangeRuleResolution::Current(record) => record,
+        ChangeRuleResolution::Pending { current, .. } => current,
+        ChangeRuleResolution::Conflict { superseded, .. } => superseded,
+    }
+}
+
+fn operations_for_signer(
+    change_rule: &ChangeRule,
+    signer: &AgentPubKey,
+) -> Vec<AuthorityOperation> {
+    AuthorityOperation::ALL
+        .into_iter()
+        .filter(|operation| {
+            change_rule
+                .spec_change
+                .spec_for(*operation)
+                .authorized_signers
+                .contains(signer)
+        })
+        .collect()
+}
+
+/// The keysets whose ChangeRule in force lists `signer`, found from the specs linked from
+/// the signer and the ChangeRules carrying them.
+fn get_guarded_keysets(signer: &AgentPubKey) -> ExternResult<Vec<GuardedKeyset>> {
+    let mut keysets: BTreeMap<ActionHash, GuardedKeyset> = BTreeMap::new();
+    for authority_spec_record in get_authority_specs_for_signer(signer.clone())? {
+        let authority_spec_hash = match authority_spec_record.action().entry_hash() {
+            Some(entry_hash) => entry_hash.clone(),
+            None => continue,
+        };
+        let links = get_links(
+            authority_spec_hash,
+            LinkTypes::AuthoritySpecToChangeRules,
+            None,
+        )?;
+        for link in links {
+            let resolution = match resolve_change_rule(ActionHash::from(link.target))? {
+                Some(resolution) => resolution,
+                None => continue,
+            };
+            let change_rule = ChangeRule::try_from(change_rule_in_force(&resolution).clone())?;
+            if keysets.contains_key(&change_rule.keyset_root) {
+                continue;
+            }
+            let operations = operations_for_signer(&change_rule, signer);
+            if operations.is_empty() {
+                // Superseded by a rule that no longer lists the signer.
+                continue;
+            }
+            keysets.insert(
+                change_rule.keyset_root.clone(),
+                GuardedKeyset {
+                    keyset_root: change_rule.keyset_root,
+                    change_rule: resolution,
+                    operations,
+                },
+            );
+        }
+    }
+    Ok(keysets.into_values().collect())
+}
+
+/// Whether a proposal can still be finalized: its ChangeRule has not been replaced and, for
+/// a revocation, the key has not been revoked since.
+fn is_proposal_open(proposal: &Proposal) -> ExternResult<bool> {
+    match &proposal.payload {
+        SigningPayload::SpecChange(_) => {
+            Ok(ensure_change_rule_is_current(proposal.change_rule.clone()).is_ok())
+        }
+        SigningPayload::KeyRevocation(payload) => {
+            if ensure_change_rule_in_force(proposal.change_rule.clone()).is_err() {
+                return Ok(false);
+            }
+            match get_details(
+                payload.prior_key_registration.clone(),
+                GetOptions::default(),
+            )? {
+                Some(Details::Record(record_details)) => Ok(record_details.updates.is_empty()),
+                _ => Ok(false),
+            }
+        }
+        _ => Ok(false),
+    }
+}
+
+fn has_signed(
+    proposal_hash: ActionHash,
+    proposal: &Proposal,
+    signer: &AgentPubKey,
+) -> ExternResult<bool> {
+    let operation = match proposal.operation() {
+        Some(operation) => operation,
+        None => return Ok(false),
+    };
+    let record = get(proposal.change_rule.clone(), GetOptions::default())?.ok_or(wasm_error!(
+        WasmErrorInner::Guest(String::from("Could not find the ChangeRule"))
+    ))?;
+    let change_rule = ChangeRule::try_from(record)?;
+    let authorized_signers = &change_rule
+        .spec_change
+        .spec_for(operation)
+        .authorized_signers;
+    for record in get_proposal_signatures(proposal_hash)? {
+        let (position, _) = ProposalSignature::try_from(record)?.authorization;
+        if authorized_signers.get(position as usize) == Some(signer) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// What needs `signer`'s attention: the keysets they guard under the ChangeRules in force,
+/// and the open proposals awaiting their signature.
+#[hdk_extern]
+pub fn get_signer_dashboard(signer: AgentPubKey) -> ExternResult<SignerDashboard> {
+    let keysets = get_guarded_keysets(&signer)?;
+    let mut open_proposals = Vec::new();
+    for record in get_proposals_for_signer(signer.clone())? {
+        let proposal_hash = record.action_address().clone();
+        let proposal = Proposal::try_from(record.clone())?;
+        if is_proposal_open(&proposal)? && !has_signed(proposal_hash, &proposal, &signer)? {
+            open_proposals.push(record);
+        }
+    }
+    Ok(SignerDashboard {
+        keysets,
+        open_proposals,
+    })
+}
@@ -49,3 +49,44 @@ pub fn query_keyset_root_action_hash(_: ()) -> ExternResult<ActionHash> {
         )))
     }
 }
+// This function queries for the keyset root and the keyset leaf this chain is under:
+// the latest device invite acceptance if one was committed, otherwise the keyset root.
+pub fn query_keyset_root_and_leaf() -> ExternResult<(ActionHash, ActionHash)> {
+    let keyset_roots =
+        query(ChainQueryFilter::new().entry_type(UnitEntryTypes::KeysetRoot.try_into()?))?;
+    let device_invite_acceptances = query(
+        ChainQueryFilter::new().entry_type(UnitEntryTypes::DeviceInviteAcceptance.try_into()?),
+    )?;
+    let keyset_leaf = keyset_roots
+        .into_iter()
+        .chain(device_invite_acceptances)
+        .max_by_key(|record| record.action().action_seq())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(
+            "No KeysetFound on chain".into()
+        )))?;
+    match DeviceInviteAcceptance::try_from(keyset_leaf.clone()) {
+        Ok(device_invite_acceptance) => Ok((
+            device_invite_acceptance.keyset_root_authority,
+            keyset_leaf.action_address().to_owned(),
+        )),
+        Err(_) => Ok((
+            keyset_leaf.action_address().to_owned(),
+            keyset_leaf.action_address().to_owned(),
+        )),
+    }
+}
+// This function reads the JoiningProof this agent presented as its membrane proof, if any,
+// from the AgentValidationPkg at the start of the chain.
+pub fn query_joining_proof() -> ExternResult<Option<JoiningProof>> {
+    let membrane_proof =
+        query(ChainQueryFilter::new().action_type(ActionType::AgentValidationPkg))?
+            .into_iter()
+            .find_map(|record| match record.action() {
+                Action::AgentValidationPkg(AgentValidationPkg { membrane_proof, .. }) => {
+                    membrane_proof.clone()
+                }
+                _ => None,
+            });
+    decode_joining_proof(&membrane_proof)
+        .map_err(|e| wasm_error!(WasmErrorInner::Guest(e.to_string())))
+}
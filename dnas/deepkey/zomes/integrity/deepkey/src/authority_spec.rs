@@ -1,31 +1,91 @@
 use hdi::prelude::*;
-// Represents an M:N multisignature spec.
-// The trivial case 1:1 represents a single agent to sign.
-// We need an entry to define the rules of authority
-// (for authorizing or revoking) keys in the space under a KeysetRoot.
-// This is only committed by the first Deepkey agent.
-#[hdk_entry_helper]
-#[derive(Clone)]
-pub struct AuthoritySpec {
-    // set to 1 for a single signer scenario
-    pub sigs_required: u8,
-    // These signers may not exist on the DHT.
-    // E.g. a revocation key used to create the first change rule.
-    pub authorized_signers: Vec<AgentPubKey>,
-}
-impl AuthoritySpec {
-    pub fn new(sigs_required: u8, authorized_signers: Vec<AgentPubKey>) -> Self {
-        Self {
-            sigs_required,
-            authorized_signers,
+
+use crate::{error::Error, AuthorityPolicy, AuthoritySpec, MAX_AUTHORIZED_SIGNERS};
+
+/// How deeply an AuthorityPolicy may nest, bounding the work of evaluating it.
+pub const MAX_AUTHORITY_POLICY_DEPTH: usize = 8;
+
+/// Refuses policies that refer to missing signers, nest too deeply, can never be met,
+/// or have a node met without any signature.
+pub fn validate_authority_policy(
+    policy: &AuthorityPolicy,
+    signer_count: usize,
+    depth: usize,
+) -> Result<(), Error> {
+    if depth > MAX_AUTHORITY_POLICY_DEPTH {
+        return Err(Error::AuthorityPolicyTooDeep(MAX_AUTHORITY_POLICY_DEPTH));
+    }
+    let sub_policies: Vec<&AuthorityPolicy> = match policy {
+        AuthorityPolicy::Signer(position) => {
+            if *position as usize >= signer_count {
+                return Err(Error::AuthorizedPositionOutOfBounds);
+            }
+            vec![]
+        }
+        AuthorityPolicy::All(policies) => {
+            if policies.is_empty() {
+                return Err(Error::NotEnoughSignatures);
+            }
+            policies.iter().collect()
+        }
+        AuthorityPolicy::Any(policies) => {
+            if policies.is_empty() {
+                return Err(Error::UnsatisfiableAuthorityPolicy);
+            }
+            policies.iter().collect()
         }
+        AuthorityPolicy::Threshold { required, policies } => {
+            if *required == 0 {
+                return Err(Error::NotEnoughSignatures);
+            }
+            if policies.len() < *required as usize {
+                return Err(Error::UnsatisfiableAuthorityPolicy);
+            }
+            policies.iter().collect()
+        }
+        AuthorityPolicy::WeightedThreshold { required, policies } => {
+            if *required == 0 {
+                return Err(Error::NotEnoughSignatures);
+            }
+            let total_weight: u64 = policies.iter().map(|(weight, _)| *weight as u64).sum();
+            if total_weight < *required as u64 {
+                return Err(Error::UnsatisfiableAuthorityPolicy);
+            }
+            policies.iter().map(|(_, policy)| policy).collect()
+        }
+    };
+    for policy in sub_policies {
+        validate_authority_policy(policy, signer_count, depth + 1)?;
+    }
+    Ok(())
+}
+/// Refuses specs with more signers than positions can address, and specs whose policy or
+/// `sigs_required` can never be met.
+pub fn validate_authority_spec(
+    authority_spec: &AuthoritySpec,
+) -> ExternResult<ValidateCallbackResult> {
+    if authority_spec.authorized_signers.len() > MAX_AUTHORIZED_SIGNERS {
+        return Error::TooManySigners(MAX_AUTHORIZED_SIGNERS).into();
+    }
+    if let Some(policy) = &authority_spec.policy {
+        return match validate_authority_policy(policy, authority_spec.authorized_signers.len(), 0) {
+            Ok(()) => Ok(ValidateCallbackResult::Valid),
+            Err(e) => e.into(),
+        };
+    }
+    if authority_spec.sigs_required == 0 {
+        return Error::NotEnoughSignatures.into();
+    }
+    if authority_spec.authorized_signers.len() < authority_spec.sigs_required as usize {
+        return Error::NotEnoughSigners.into();
     }
+    Ok(ValidateCallbackResult::Valid)
 }
 pub fn validate_create_authority_spec(
     _action: EntryCreationAction,
-    _authority_spec: AuthoritySpec,
+    authority_spec: AuthoritySpec,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    validate_authority_spec(&authority_spec)
 }
 pub fn validate_update_authority_spec(
     _action: Update,
@@ -63,14 +123,160 @@ pub fn validate_create_link_signer_to_authority_specs(
         ))))?;
     Ok(ValidateCallbackResult::Valid)
 }
+/// Only the signer a link was made for can remove it, to hide a spec they declined.
 pub fn validate_delete_link_signer_to_authority_specs(
-    _action: DeleteLink,
+    action: DeleteLink,
     _original_action: CreateLink,
-    _base: AnyLinkableHash,
+    base: AnyLinkableHash,
     _target: AnyLinkableHash,
     _tag: LinkTag,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Invalid(String::from(
-        "SignerToAuthoritySpecs links cannot be deleted",
-    )))
+    if AnyLinkableHash::from(action.author) != base {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "SignerToAuthoritySpecs links can only be deleted by their signer",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signers(count: usize) -> Vec<AgentPubKey> {
+        (0..count)
+            .map(|i| AgentPubKey::from_raw_36(vec![i as u8; 36]))
+            .collect()
+    }
+
+    fn signer_threshold(required: u8, positions: &[u8]) -> AuthorityPolicy {
+        AuthorityPolicy::Threshold {
+            required,
+            policies: positions
+                .iter()
+                .map(|position| AuthorityPolicy::Signer(*position))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn nested_policy_within_the_signers_is_valid() {
+        let policy = AuthorityPolicy::Any(vec![
+            AuthorityPolicy::Signer(0),
+            AuthorityPolicy::All(vec![
+                signer_threshold(2, &[1, 2, 3]),
+                AuthorityPolicy::Signer(4),
+            ]),
+        ]);
+        assert_eq!(validate_authority_policy(&policy, 5, 0), Ok(()));
+    }
+
+    #[test]
+    fn nested_position_out_of_range_is_refused() {
+        let policy = AuthorityPolicy::Any(vec![
+            AuthorityPolicy::Signer(0),
+            AuthorityPolicy::All(vec![signer_threshold(1, &[1, 5])]),
+        ]);
+        assert_eq!(
+            validate_authority_policy(&policy, 5, 0),
+            Err(Error::AuthorizedPositionOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn policy_nesting_is_bounded() {
+        let mut policy = AuthorityPolicy::Signer(0);
+        for _ in 0..=MAX_AUTHORITY_POLICY_DEPTH {
+            policy = AuthorityPolicy::All(vec![policy]);
+        }
+        assert_eq!(
+            validate_authority_policy(&policy, 1, 0),
+            Err(Error::AuthorityPolicyTooDeep(MAX_AUTHORITY_POLICY_DEPTH))
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_policies_are_refused() {
+        assert_eq!(
+            validate_authority_policy(&AuthorityPolicy::Any(vec![]), 1, 0),
+            Err(Error::UnsatisfiableAuthorityPolicy)
+        );
+        assert_eq!(
+            validate_authority_policy(&signer_threshold(3, &[0, 1]), 2, 0),
+            Err(Error::UnsatisfiableAuthorityPolicy)
+        );
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: 6,
+            policies: vec![
+                (3, AuthorityPolicy::Signer(0)),
+                (2, AuthorityPolicy::Signer(1)),
+            ],
+        };
+        assert_eq!(
+            validate_authority_policy(&policy, 2, 0),
+            Err(Error::UnsatisfiableAuthorityPolicy)
+        );
+    }
+
+    #[test]
+    fn policies_met_without_signatures_are_refused() {
+        assert_eq!(
+            validate_authority_policy(&AuthorityPolicy::All(vec![]), 1, 0),
+            Err(Error::NotEnoughSignatures)
+        );
+        assert_eq!(
+            validate_authority_policy(&signer_threshold(0, &[0]), 1, 0),
+            Err(Error::NotEnoughSignatures)
+        );
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: 0,
+            policies: vec![(1, AuthorityPolicy::Signer(0))],
+        };
+        assert_eq!(
+            validate_authority_policy(&policy, 1, 0),
+            Err(Error::NotEnoughSignatures)
+        );
+    }
+
+    #[test]
+    fn weighted_threshold_within_its_total_weight_is_valid() {
+        let policy = AuthorityPolicy::WeightedThreshold {
+            required: u32::MAX,
+            policies: vec![
+                (u32::MAX, AuthorityPolicy::Signer(0)),
+                (1, AuthorityPolicy::Signer(1)),
+            ],
+        };
+        assert_eq!(validate_authority_policy(&policy, 2, 0), Ok(()));
+    }
+
+    #[test]
+    fn flat_spec_needs_enough_signers() {
+        assert_eq!(
+            validate_authority_spec(&AuthoritySpec::new(2, signers(2))),
+            Ok(ValidateCallbackResult::Valid)
+        );
+        assert!(matches!(
+            validate_authority_spec(&AuthoritySpec::new(3, signers(2))),
+            Ok(ValidateCallbackResult::Invalid(_))
+        ));
+        assert!(matches!(
+            validate_authority_spec(&AuthoritySpec::new(0, signers(2))),
+            Ok(ValidateCallbackResult::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn spec_with_more_signers_than_positions_is_refused() {
+        assert_eq!(
+            validate_authority_spec(&AuthoritySpec::new(1, signers(MAX_AUTHORIZED_SIGNERS))),
+            Ok(ValidateCallbackResult::Valid)
+        );
+        assert_eq!(
+            validate_authority_spec(&AuthoritySpec::new(1, signers(MAX_AUTHORIZED_SIGNERS + 1))),
+            Ok(ValidateCallbackResult::Invalid(
+                Error::TooManySigners(MAX_AUTHORIZED_SIGNERS).to_string()
+            ))
+        );
+    }
 }
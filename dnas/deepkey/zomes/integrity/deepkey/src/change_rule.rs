@@ -1,71 +1,312 @@
+use std::collections::BTreeSet;
+
 use hdi::prelude::*;
 
-use crate::{error::Error, Authorization, AuthorizedSpecChange};
+use crate::{
+    error::Error, validate_authority_spec, validate_guardian_consent, validate_keyset_not_frozen,
+    AuthorityOperation, Authorization, AuthorizedSpecChange, ChangeRule, ChangeRuleVeto,
+    DeepkeyProperties, KeysetRoot, SigningPayload, SpecChangePayload, UnitEntryTypes,
+};
+
+/// Checks that the signers of the ChangeRule's spec for `operation` signed `payload` as its
+/// policy requires.
+/// A spec without an explicit policy keeps requiring exactly `sigs_required` signatures.
+pub fn authorize_change_rule(
+    change_rule: &ChangeRule,
+    operation: AuthorityOperation,
+    authorization: &[Authorization],
+    payload: &SigningPayload,
+) -> Result<(), Error> {
+    let spec = change_rule.spec_change.spec_for(operation);
+    if spec.policy.is_none() && authorization.len() != spec.sigs_required as usize {
+        return Err(Error::WrongNumberOfSignatures);
+    }
+    let data = payload.to_signable_bytes()?;
+    let mut signed = BTreeSet::new();
+    for (position, signature) in authorization.iter() {
+        match spec.authorized_signers.get(*position as usize) {
+            Some(agent) => {
+                if !signed.insert(*position) {
+                    return Err(Error::DuplicateAuthorization(*position));
+                }
+                if !verify_signature_raw(agent.to_owned(), signature.to_owned(), data.clone())? {
+                    // Short circuit any failed sig.
+                    return Err(Error::BadUpdateSignature);
+                }
+            }
+            None => return Err(Error::AuthorizedPositionOutOfBounds),
+        }
+    }
+    if !spec.policy().is_satisfied_by(&signed) {
+        return Err(Error::AuthorityPolicyNotMet);
+    }
+    Ok(())
+}
+
+/// How long an update replacing `change_rule` waits before it takes effect: the longer of
+/// the network's delay and the one the rule asks for.
+pub fn change_rule_update_delay(change_rule: &ChangeRule) -> ExternResult<std::time::Duration> {
+    let network_delay_secs = DeepkeyProperties::get()?.change_rule_update_delay_secs;
+    Ok(std::time::Duration::from_secs(
+        network_delay_secs.max(change_rule.spec_change.update_delay_secs),
+    ))
+}
 
-// The author needs to be linked from the KeysetRoot
-#[hdk_entry_helper]
-#[derive(Clone)]
-pub struct ChangeRule {
-    pub keyset_root: ActionHash,
-    pub keyset_leaf: ActionHash,
-    pub spec_change: AuthorizedSpecChange,
+/// When the ChangeRule committed by `action` takes effect.
+/// The first rule of a keyset is in force at once; an update only once the delay of the
+/// rule it replaces has passed.
+pub fn change_rule_effective_from(action: &Action) -> ExternResult<Timestamp> {
+    match action {
+        Action::Create(create) => Ok(create.timestamp),
+        Action::Update(update) => {
+            let replaced = ChangeRule::try_from(must_get_valid_record(
+                update.original_action_address.clone(),
+            )?)?;
+            Ok(update
+                .timestamp
+                .saturating_add(&change_rule_update_delay(&replaced)?))
+        }
+        _ => Err(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Action does not commit a ChangeRule"
+        )))),
+    }
 }
 
-impl ChangeRule {
-    pub fn new(
-        keyset_root: ActionHash,
-        keyset_leaf: ActionHash,
-        spec_change: AuthorizedSpecChange,
-    ) -> Self {
-        Self {
-            keyset_root,
-            keyset_leaf,
-            spec_change,
+/// The ChangeRule updates vetoed by the ChangeRuleVetoes an acting entry cites, and by
+/// those committed on its author's chain as of `chain_top`.
+pub fn get_vetoed_change_rule_updates(
+    agent: AgentPubKey,
+    chain_top: ActionHash,
+    cited_vetoes: &[ActionHash],
+) -> ExternResult<Vec<ActionHash>> {
+    let change_rule_veto_def = AppEntryDef::try_from(UnitEntryTypes::ChangeRuleVeto)?;
+    let mut vetoed = Vec::new();
+    for veto_hash in cited_vetoes {
+        let veto = ChangeRuleVeto::try_from(must_get_valid_record(veto_hash.clone())?)?;
+        vetoed.push(veto.change_rule_update);
+    }
+    for activity in must_get_agent_activity(agent, ChainFilter::new(chain_top))? {
+        if let Some((entry_hash, EntryType::App(app_entry_def))) =
+            activity.action.action().entry_data()
+        {
+            if *app_entry_def == change_rule_veto_def {
+                let veto = ChangeRuleVeto::try_from(must_get_entry(entry_hash.clone())?)?;
+                vetoed.push(veto.change_rule_update);
+            }
         }
     }
+    Ok(vetoed)
+}
 
-    pub fn authorize(&self, authorization: &[Authorization], data: &[u8]) -> Result<(), Error> {
-        if authorization.len() != self.spec_change.new_spec.sigs_required as usize {
-            Err(Error::WrongNumberOfSignatures)
-        } else {
-            for (position, signature) in authorization.iter() {
-                match self
-                    .spec_change
-                    .new_spec
-                    .authorized_signers
-                    .get(*position as usize)
+/// The highest revision of the keyset's ChangeRule committed on an agent's chain as of
+/// `chain_top` and in force at `at`, if any. Updates in `vetoed` never take effect.
+pub fn get_latest_change_rule_revision_on_chain(
+    agent: AgentPubKey,
+    chain_top: ActionHash,
+    keyset_root: &ActionHash,
+    at: Timestamp,
+    vetoed: &[ActionHash],
+) -> ExternResult<Option<u32>> {
+    let change_rule_def = AppEntryDef::try_from(UnitEntryTypes::ChangeRule)?;
+    let mut latest_revision = None;
+    for activity in must_get_agent_activity(agent, ChainFilter::new(chain_top))? {
+        if let Some((entry_hash, EntryType::App(app_entry_def))) =
+            activity.action.action().entry_data()
+        {
+            if *app_entry_def == change_rule_def && !vetoed.contains(activity.action.as_hash()) {
+                let change_rule = ChangeRule::try_from(must_get_entry(entry_hash.clone())?)?;
+                if change_rule.keyset_root == *keyset_root
+                    && latest_revision < Some(change_rule.revision)
+                    && change_rule_effective_from(activity.action.action())? <= at
                 {
-                    Some(agent) => {
-                        if !verify_signature_raw(
-                            agent.to_owned(),
-                            signature.to_owned(),
-                            data.to_vec(),
-                        )? {
-                            // Short circuit any failed sig.
-                            return Err(Error::BadUpdateSignature);
-                        }
-                    }
-                    None => return Err(Error::AuthorizedPositionOutOfBounds),
+                    latest_revision = Some(change_rule.revision);
                 }
             }
-            Ok(())
         }
     }
+    Ok(latest_revision)
 }
 
+/// Refuses authority from a ChangeRule that the author's own chain had already moved on
+/// from at `at`.
+///
+/// Only the author's chain can be walked deterministically; `update_change_rule` and the
+/// payload calls also refuse rules that already have an update anywhere on the DHT.
+pub fn validate_change_rule_not_stale(
+    author: AgentPubKey,
+    chain_top: ActionHash,
+    at: Timestamp,
+    change_rule: &ChangeRule,
+    vetoed: &[ActionHash],
+) -> ExternResult<ValidateCallbackResult> {
+    match get_latest_change_rule_revision_on_chain(
+        author,
+        chain_top,
+        &change_rule.keyset_root,
+        at,
+        vetoed,
+    )? {
+        Some(latest_revision) if latest_revision > change_rule.revision => {
+            Error::StaleChangeRule.into()
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// Checks that the ChangeRule committed at `change_rule_hash` governed the keyset at `at`,
+/// the timestamp of the action it authorizes: it had taken effect, no veto cited by the
+/// acting entry or committed on the author's chain stopped it, and their chain had not
+/// moved on to a later rule in force.
+///
+/// Vetoes committed elsewhere can only be seen deterministically if the acting entry cites
+/// them in `vetoes`; the coordinator cites every veto it finds of the rule and its updates.
+/// An entry that leaves a veto out is not refused for it, so such vetoes are advisory.
+pub fn validate_change_rule_in_force(
+    author: AgentPubKey,
+    chain_top: ActionHash,
+    at: Timestamp,
+    change_rule_hash: &ActionHash,
+    change_rule: &ChangeRule,
+    vetoes: &[ActionHash],
+) -> ExternResult<ValidateCallbackResult> {
+    let change_rule_action = must_get_action(change_rule_hash.clone())?;
+    if change_rule_effective_from(change_rule_action.action())? > at {
+        return Error::ChangeRuleNotYetEffective.into();
+    }
+    let vetoed = get_vetoed_change_rule_updates(author.clone(), chain_top.clone(), vetoes)?;
+    if vetoed.contains(change_rule_hash) {
+        return Error::ChangeRuleVetoed.into();
+    }
+    validate_change_rule_not_stale(author, chain_top, at, change_rule, &vetoed)
+}
+
+fn validate_authority_specs(
+    spec_change: &AuthorizedSpecChange,
+) -> ExternResult<ValidateCallbackResult> {
+    for authority_spec in
+        std::iter::once(&spec_change.new_spec).chain(spec_change.operation_specs.iter())
+    {
+        let result = validate_authority_spec(authority_spec)?;
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The first ChangeRule immediately follows its KeysetRoot on the first deepkey agent's
+/// chain, and its spec is signed by the throwaway root key.
 pub fn validate_create_change_rule(
-    _action: EntryCreationAction,
-    _change_rule: ChangeRule,
+    action: EntryCreationAction,
+    change_rule: ChangeRule,
 ) -> ExternResult<ValidateCallbackResult> {
+    let keyset_root_record = must_get_valid_record(change_rule.keyset_root.clone())?;
+    let keyset_root = KeysetRoot::try_from(keyset_root_record)?;
+    if *action.author() != keyset_root.first_deepkey_agent {
+        return Error::AuthorNotFda.into();
+    }
+    if *action.prev_action() != change_rule.keyset_root {
+        return Error::CreateNotAfterKeysetRoot.into();
+    }
+    if change_rule.revision != 0 {
+        return Error::WrongChangeRuleRevision.into();
+    }
+    let result = validate_authority_specs(&change_rule.spec_change)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    let signature = match change_rule.spec_change.authorization_of_new_spec.as_slice() {
+        [(_, signature)] => signature.clone(),
+        [] => return Error::NoCreateSignature.into(),
+        _ => return Error::MultipleCreateSignatures.into(),
+    };
+    let payload = SigningPayload::from(SpecChangePayload {
+        keyset_root: None,
+        dna_hash: dna_info()?.hash,
+        prev_change_rule: None,
+        revision: 0,
+        new_spec: change_rule.spec_change.new_spec,
+        operation_specs: change_rule.spec_change.operation_specs,
+        update_delay_secs: change_rule.spec_change.update_delay_secs,
+    });
+    if !verify_signature_raw(
+        keyset_root.root_pub_key().clone(),
+        signature,
+        payload.to_signable_bytes()?,
+    )? {
+        return Error::BadCreateSignature.into();
+    }
     Ok(ValidateCallbackResult::Valid)
 }
+/// An update must be authorized by the rule it replaces, over the new spec, and must
+/// supersede that rule with the next revision while it is in force. New specs that
+/// require consent must have been accepted by enough of their signers.
+/// Binding the signatures to the superseded rule and revision means approvals collected
+/// for one revision cannot be replayed against another.
 pub fn validate_update_change_rule(
-    _action: Update,
-    _change_rule: ChangeRule,
+    action: Update,
+    change_rule: ChangeRule,
     _original_action: EntryCreationAction,
-    _original_change_rule: ChangeRule,
+    original_change_rule: ChangeRule,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    if change_rule.keyset_root != original_change_rule.keyset_root {
+        return Error::KeysetRootMismatch.into();
+    }
+    if Some(change_rule.revision) != original_change_rule.revision.checked_add(1) {
+        return Error::WrongChangeRuleRevision.into();
+    }
+    let result = validate_authority_specs(&change_rule.spec_change)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    for authority_spec in std::iter::once(&change_rule.spec_change.new_spec)
+        .chain(change_rule.spec_change.operation_specs.iter())
+    {
+        let result = validate_guardian_consent(authority_spec, &change_rule.guardian_acceptances)?;
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+    }
+    let result = validate_change_rule_in_force(
+        action.author.clone(),
+        action.prev_action.clone(),
+        action.timestamp,
+        &action.original_action_address,
+        &original_change_rule,
+        &change_rule.vetoes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let result = validate_keyset_not_frozen(
+        action.author.clone(),
+        action.prev_action.clone(),
+        &change_rule.keyset_root,
+        &change_rule.keyset_freezes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    let payload = SigningPayload::from(SpecChangePayload {
+        keyset_root: Some(change_rule.keyset_root),
+        dna_hash: dna_info()?.hash,
+        prev_change_rule: Some(action.original_action_address),
+        revision: change_rule.revision,
+        new_spec: change_rule.spec_change.new_spec,
+        operation_specs: change_rule.spec_change.operation_specs,
+        update_delay_secs: change_rule.spec_change.update_delay_secs,
+    });
+    match authorize_change_rule(
+        &original_change_rule,
+        AuthorityOperation::ChangeRuleUpdate,
+        &change_rule.spec_change.authorization_of_new_spec,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(e) => e.into(),
+    }
 }
 pub fn validate_delete_change_rule(
     _action: Delete,
@@ -113,3 +354,40 @@ pub fn validate_delete_link_change_rule_updates(
         "ChangeRuleUpdates links cannot be deleted",
     )))
 }
+pub fn validate_create_link_authority_spec_to_change_rules(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let change_rule: crate::ChangeRule = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    for authority_spec in std::iter::once(&change_rule.spec_change.new_spec)
+        .chain(change_rule.spec_change.operation_specs.iter())
+    {
+        if AnyLinkableHash::from(hash_entry(authority_spec)?) == base_address {
+            return Ok(ValidateCallbackResult::Valid);
+        }
+    }
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "A ChangeRule can only be linked from the AuthoritySpecs it carries",
+    )))
+}
+pub fn validate_delete_link_authority_spec_to_change_rules(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "AuthoritySpecToChangeRules links cannot be deleted",
+    )))
+}
@@ -0,0 +1,134 @@
+use hdi::prelude::*;
+
+use crate::{
+    authorize_change_rule, change_rule_update_delay, error::Error, get_keyset_root_and_leaf,
+    validate_keyset_not_frozen, AuthorityOperation, Authorization, ChangeRule,
+    ChangeRuleVetoPayload, SigningPayload,
+};
+
+// Stops a ChangeRule update from taking effect while its delay runs.
+// Advisory beyond the vetoing chain: validation only enforces a veto on chains that commit
+// it or entries that cite it, and an entry acting under the vetoed update that leaves it
+// out is still valid.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct ChangeRuleVeto {
+    // The update of the keyset's ChangeRule being vetoed.
+    pub change_rule_update: ActionHash,
+    // Signatures of a ChangeRuleVetoPayload by the signers of the rule being replaced.
+    // Empty when a device of the keyset vetoes on its own authority.
+    pub authorization: Vec<Authorization>,
+    // KeysetFreezes of the keyset and the KeysetUnfreezes lifting them, from any device.
+    #[serde(default)]
+    pub keyset_freezes: Vec<ActionHash>,
+}
+
+/// A veto must be committed before the update takes effect and while the keyset is not
+/// frozen, either by a device of the keyset or with the approval of the signers of the rule
+/// the update replaces.
+pub fn validate_create_change_rule_veto(
+    action: EntryCreationAction,
+    change_rule_veto: ChangeRuleVeto,
+) -> ExternResult<ValidateCallbackResult> {
+    let update_record = must_get_valid_record(change_rule_veto.change_rule_update.clone())?;
+    let update = match update_record.action() {
+        Action::Update(update) => update.clone(),
+        _ => return Error::VetoNotOfChangeRuleUpdate.into(),
+    };
+    let change_rule = ChangeRule::try_from(update_record)?;
+    let replaced_change_rule = ChangeRule::try_from(must_get_valid_record(
+        update.original_action_address.clone(),
+    )?)?;
+    let effective_from = update
+        .timestamp
+        .saturating_add(&change_rule_update_delay(&replaced_change_rule)?);
+    if *action.timestamp() >= effective_from {
+        return Error::VetoWindowClosed.into();
+    }
+    let result = validate_keyset_not_frozen(
+        action.author().clone(),
+        action.prev_action().clone(),
+        &change_rule.keyset_root,
+        &change_rule_veto.keyset_freezes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    if change_rule_veto.authorization.is_empty() {
+        let (keyset_root, _) =
+            get_keyset_root_and_leaf(action.author().clone(), action.prev_action().clone())?;
+        if keyset_root != change_rule.keyset_root {
+            return Error::VetoerNotInKeyset.into();
+        }
+        return Ok(ValidateCallbackResult::Valid);
+    }
+
+    let payload = SigningPayload::from(ChangeRuleVetoPayload {
+        keyset_root: change_rule.keyset_root,
+        dna_hash: dna_info()?.hash,
+        change_rule_update: change_rule_veto.change_rule_update,
+    });
+    match authorize_change_rule(
+        &replaced_change_rule,
+        AuthorityOperation::ChangeRuleUpdate,
+        &change_rule_veto.authorization,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(Error::BadUpdateSignature) => Error::BadVetoSignature.into(),
+        Err(e) => e.into(),
+    }
+}
+pub fn validate_update_change_rule_veto(
+    _action: Update,
+    _change_rule_veto: ChangeRuleVeto,
+    _original_action: EntryCreationAction,
+    _original_change_rule_veto: ChangeRuleVeto,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Change Rule Vetoes cannot be updated",
+    )))
+}
+pub fn validate_delete_change_rule_veto(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_change_rule_veto: ChangeRuleVeto,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Change Rule Vetoes cannot be deleted",
+    )))
+}
+pub fn validate_create_link_change_rule_update_to_vetoes(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let change_rule_veto: crate::ChangeRuleVeto = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(change_rule_veto.change_rule_update) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A ChangeRuleVeto must be linked from the update it vetoes",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_change_rule_update_to_vetoes(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "ChangeRuleUpdateToVetoes links cannot be deleted",
+    )))
+}
@@ -1,28 +1,162 @@
 use hdi::prelude::*;
 
-use crate::*;
-
-#[hdk_entry_helper]
-#[derive(Clone)]
-pub struct DeviceInvite {
-    pub keyset_root: ActionHash,
-    // Either the KeysetRoot or the DeviceInviteAcceptance
-    pub parent: ActionHash,
-    pub invitee: AgentPubKey,
+use crate::{error::Error, *};
+
+/// A recovery invite is committed by the invitee itself, as no device of the keyset remains
+/// to do it, on a chain that has no source of authority yet. It must sit straight under the
+/// KeysetRoot and satisfy `authorize_change_rule` for a ChangeRule of that keyset in force
+/// when the invite is committed.
+pub fn validate_recovery_invite(
+    action: &EntryCreationAction,
+    device_invite: &DeviceInvite,
+    recovery: &RecoveryAuthorization,
+) -> ExternResult<ValidateCallbackResult> {
+    if *action.author() != device_invite.invitee {
+        return Error::RecoveryInviteNotByInvitee.into();
+    }
+    if has_source_of_authority(action.author(), action.prev_action())? {
+        return Error::SourceOfAuthorityExists.into();
+    }
+    if device_invite.parent != device_invite.keyset_root {
+        return Error::RecoveryInviteNotUnderKeysetRoot.into();
+    }
+    let change_rule = ChangeRule::try_from(must_get_valid_record(recovery.change_rule.clone())?)?;
+    if change_rule.keyset_root != device_invite.keyset_root {
+        return Error::RecoveryKeysetRootMismatch.into();
+    }
+    let result = validate_change_rule_in_force(
+        action.author().clone(),
+        action.prev_action().clone(),
+        *action.timestamp(),
+        &recovery.change_rule,
+        &change_rule,
+        &device_invite.vetoes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    let payload = SigningPayload::from(KeysetRecoveryPayload {
+        keyset_root: device_invite.keyset_root.clone(),
+        dna_hash: dna_info()?.hash,
+        change_rule: recovery.change_rule.clone(),
+        invitee: device_invite.invitee.clone(),
+    });
+    match authorize_change_rule(
+        &change_rule,
+        AuthorityOperation::DeviceInvite,
+        &recovery.authorization,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(Error::BadUpdateSignature) => Error::BadRecoverySignature.into(),
+        Err(e) => e.into(),
+    }
 }
 
-impl DeviceInvite {
-    pub fn new(keyset_root: ActionHash, parent: ActionHash, invitee: AgentPubKey) -> Self {
-        Self {
-            keyset_root,
-            parent,
-            invitee,
-        }
+/// Any other invite is committed by a device of the keyset, below itself: the keyset leaf
+/// its chain is under when the invite is committed must be the invite's parent, and that
+/// leaf must be the keyset's KeysetRoot or a valid acceptance of an invite into it.
+pub fn validate_inviter_is_parent_device(
+    action: &EntryCreationAction,
+    device_invite: &DeviceInvite,
+) -> ExternResult<ValidateCallbackResult> {
+    if *action.author() == device_invite.invitee {
+        return Error::InviteeIsInviter.into();
+    }
+    if !has_source_of_authority(action.author(), action.prev_action())? {
+        return Error::InviteNotByParentDevice.into();
+    }
+    let (keyset_root, keyset_leaf) =
+        get_keyset_root_and_leaf(action.author().clone(), action.prev_action().clone())?;
+    if keyset_leaf != device_invite.parent {
+        return Error::InviteNotByParentDevice.into();
+    }
+    if keyset_root != device_invite.keyset_root {
+        return Error::InviteOutsideKeyset.into();
+    }
+    // An acceptance is only a leaf of the keyset if its own invite was valid.
+    must_get_valid_record(keyset_leaf)?;
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The inviting device signs a DeviceInvitePayload of the invite as its approval.
+pub fn validate_invite_signature(
+    action: &EntryCreationAction,
+    device_invite: &DeviceInvite,
+) -> ExternResult<ValidateCallbackResult> {
+    let (inviter_signature, change_rule) =
+        match (&device_invite.inviter_signature, &device_invite.change_rule) {
+            (Some(inviter_signature), Some(change_rule)) => {
+                (inviter_signature.clone(), change_rule.clone())
+            }
+            (None, _) => return Error::InviteNotSigned.into(),
+            (_, None) => return Error::InviteChangeRuleMissing.into(),
+        };
+    let payload = device_invite_payload(device_invite, change_rule)?;
+    if !verify_signature_raw(
+        action.author().clone(),
+        inviter_signature,
+        payload.to_signable_bytes()?,
+    )? {
+        return Error::BadInviteSignature.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The invite's ChangeRule must be one of its keyset in force when the invite is committed,
+/// and the signers of its spec for `DeviceInvite` must approve the invite, as for any other
+/// operation it authorizes.
+pub fn validate_invite_authorization(
+    action: &EntryCreationAction,
+    device_invite: &DeviceInvite,
+) -> ExternResult<ValidateCallbackResult> {
+    let change_rule_hash = match &device_invite.change_rule {
+        Some(change_rule_hash) => change_rule_hash.clone(),
+        None => return Error::InviteChangeRuleMissing.into(),
+    };
+    let change_rule = ChangeRule::try_from(must_get_valid_record(change_rule_hash.clone())?)?;
+    if change_rule.keyset_root != device_invite.keyset_root {
+        return Error::InviteChangeRuleKeysetMismatch.into();
+    }
+    let result = validate_change_rule_in_force(
+        action.author().clone(),
+        action.prev_action().clone(),
+        *action.timestamp(),
+        &change_rule_hash,
+        &change_rule,
+        &device_invite.vetoes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let payload = device_invite_payload(device_invite, change_rule_hash)?;
+    match authorize_change_rule(
+        &change_rule,
+        AuthorityOperation::DeviceInvite,
+        &device_invite.authorization,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(e) => e.into(),
     }
 }
 
+fn device_invite_payload(
+    device_invite: &DeviceInvite,
+    change_rule: ActionHash,
+) -> ExternResult<SigningPayload> {
+    Ok(SigningPayload::from(DeviceInvitePayload {
+        keyset_root: device_invite.keyset_root.clone(),
+        dna_hash: dna_info()?.hash,
+        parent: device_invite.parent.clone(),
+        invitee: device_invite.invitee.clone(),
+        change_rule,
+    }))
+}
+
 pub fn validate_create_device_invite(
-    _action: EntryCreationAction,
+    action: EntryCreationAction,
     device_invite: DeviceInvite,
 ) -> ExternResult<ValidateCallbackResult> {
     let record = must_get_valid_record(device_invite.keyset_root.clone())?;
@@ -33,7 +167,29 @@ pub fn validate_create_device_invite(
         .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
             "Dependant action must be accompanied by an entry"
         ))))?;
-    Ok(ValidateCallbackResult::Valid)
+    let result = match &device_invite.recovery {
+        Some(recovery) => validate_recovery_invite(&action, &device_invite, recovery)?,
+        None => {
+            let result = validate_inviter_is_parent_device(&action, &device_invite)?;
+            if result != ValidateCallbackResult::Valid {
+                return Ok(result);
+            }
+            let result = validate_invite_signature(&action, &device_invite)?;
+            if result != ValidateCallbackResult::Valid {
+                return Ok(result);
+            }
+            validate_invite_authorization(&action, &device_invite)?
+        }
+    };
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    validate_keyset_not_frozen(
+        action.author().clone(),
+        action.prev_action().clone(),
+        &device_invite.keyset_root,
+        &device_invite.keyset_freezes,
+    )
 }
 pub fn validate_update_device_invite(
     _action: Update,
@@ -119,134 +275,166 @@ pub fn validate_delete_link_invitee_to_device_invites(
         "InviteeToDeviceInvites links cannot be deleted",
     )))
 }
-/// TODO: Run this as a validation
-pub fn validate_device_invite_original(
-    invite: DeviceInvite,
-    invite_create_action: Create,
-) -> ExternResult<ValidateCallbackResult> {
-    // // A DeviceInvite must deserialize cleanly from the validating record.
-    // let invite_action_hash = invite_acceptance.invite;
-    // let invite_record = must_get_valid_record(invite_action_hash.clone())?;
-
-    // let invite_option = invite_record
-    //     .entry()
-    //     .to_app_option::<DeviceInvite>()
-    //     .ok()
-    //     .flatten();
-    // if let None = invite_option {
-    //     return Ok(ValidateCallbackResult::Invalid(
-    //         "DeviceInviteAcceptance contains an invalid DeviceInvite".into(),
-    //     ));
-    // }
-    // let invite = invite_option.unwrap();
-
-    // The KSR must be fetched and deserialized into a KeysetRoot.
-    // let ksr_action_hash = invite_acceptance.keyset_root_authority;
-    let ksr_action_hash = invite.keyset_root;
-    let ksr_record = must_get_valid_record(ksr_action_hash.clone())?;
-    let ksr_option = KeysetRoot::try_from(ksr_record.clone()).ok();
-    if let None = ksr_option {
-        return Ok(ValidateCallbackResult::Invalid(
-            "DeviceInviteAcceptance contains a malformed KeysetRoot".into(),
-        ));
-    }
-    let ksr = ksr_option.unwrap();
-
-    let parent_record = must_get_valid_record(invite.parent.clone())?;
-
-    let invitor = parent_record.action().author().clone();
-
-    // An invitee must have a different agent pubkey than the invitor
-    if invite.invitee == invitor {
-        return Ok(ValidateCallbackResult::Invalid(
-            "An invitee must have a different agent pubkey than the invitor".into(),
-        ));
-    }
-
-    // If the author of the invitation is the FDA in the invitation's KSR
-    if invitor == ksr.first_deepkey_agent {
-        if *ksr_record.action().author() != invitor {
-            return Ok(ValidateCallbackResult::Invalid(
-                "Invitor claims to be Keyset Root Authority, but the Keyset Root is written on another chain.".into(),
-            ));
-        }
-        let invite_action_hash = hash_action(invite_create_action.into())?;
-
-        // Do a hash-bounded query from the invite hash back to the KSR in the invitor's source chain.
-        let filter = ChainFilter::new(invite_action_hash).until(ksr_action_hash.clone());
-        let activities = must_get_agent_activity(invitor, filter)?;
-
-        // Check that that range contains no invite acceptances (have abandoned the Keyset they are inviting a new device into).
-        let dia_def = AppEntryDef::try_from(UnitEntryTypes::DeviceInviteAcceptance).unwrap();
-        for activity in activities.into_iter() {
-            if let Some(EntryType::App(app_entry_def)) = activity.action.action().entry_type() {
-                if *app_entry_def == dia_def {
-                    return Ok(ValidateCallbackResult::Invalid(
-                        "The invitor has abandoned the Keyset they are inviting a new device into"
-                            .into(),
-                    ));
-                }
-            };
-        }
-    } else {
-        // Search from invite backwards & find the first `DeviceInviteAcceptance` in their chain.
-        let filter = ChainFilter::new(invite.parent);
-        let activities = must_get_agent_activity(invitor, filter)?;
-        let invitor_dias = activities
-            .into_iter()
-            .filter_map(|activity| {
-                if let Some(EntryType::App(app_entry_def)) = activity.action.action().entry_type() {
-                    if *app_entry_def
-                        == AppEntryDef::try_from(UnitEntryTypes::DeviceInviteAcceptance).unwrap()
-                    {
-                        return Some(activity.clone());
-                    }
-                };
-                None
-            })
-            .collect::<Vec<_>>();
-        if invitor_dias.is_empty() {
-            return Ok(ValidateCallbackResult::Invalid(
-                "The invitor is inviting into a Keyset they do not have authority over".into(),
-            ));
-        }
-        // The invite in that `DeviceInviteAcceptance` must fetch and deserialize to a `DeviceInvite`.
-        let invitor_dia_action = invitor_dias.first().unwrap().action.action();
-        let invitor_dia_entry = must_get_entry(invitor_dia_action.entry_hash().unwrap().clone())?;
-        let invitor_dia = DeviceInviteAcceptance::try_from(invitor_dia_entry).unwrap();
-        let invitors_original_invite_action_hash = invitor_dia.invite;
-        let invitors_original_invite_record =
-            must_get_valid_record(invitors_original_invite_action_hash.clone())?;
-        let invite_option = invitors_original_invite_record
-            .entry()
-            .to_app_option::<DeviceInvite>()
-            .ok()
-            .flatten();
-        if let None = invite_option {
-            return Ok(ValidateCallbackResult::Invalid(
-                "Invitor's DeviceInviteAcceptance contains an invalid DeviceInvite".into(),
-            ));
-        }
-        // That deserialized `DeviceInvite` must have the same KSR authority as the new `DeviceInvite` currently being validated.
-        let invite = invite_option.unwrap();
-        if invite.keyset_root != ksr_action_hash {
-            return Ok(ValidateCallbackResult::Invalid(
-                "Invitor is part of a different KeysetRoot Authority than the one it is inviting into".into(),
-            ));
-        }
-        // Also in that `DeviceInvite`, the invitee must be the author of the new `DeviceInvite`.
-        if invite.invitee != *parent_record.action().author() {
-            return Ok(ValidateCallbackResult::Invalid(
-                "Inviting Agent is different from the invitation it is using as proof of authority"
-                    .into(),
-            ));
-        }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{TestKey, TestNetwork};
+
+    #[test]
+    fn first_deepkey_agent_invites_below_keyset_root() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &TestKey::new(1),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            ValidateCallbackResult::Valid
+        );
     }
 
-    // The invite in that acceptance must fetch and deserialize to a DeviceInvite.
-    // That deserialized DeviceInvite must have the same KSR as the new DeviceInvite currently being validated.
-    // Also in that DeviceInvite, the invitee must be the author of the new DeviceInvite.
-    Ok(ValidateCallbackResult::Valid)
-}
+    #[test]
+    fn invite_needs_approval_of_its_change_rule() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let invitee = TestKey::new(1);
+        let keyset_root_hash = keyset.keyset_root.action_address().clone();
+        let change_rule_hash = keyset.change_rule.action_address().clone();
+        let inviter_signature = keyset.fda.sign_payload(DeviceInvitePayload {
+            keyset_root: keyset_root_hash.clone(),
+            dna_hash: network.dna_hash(),
+            parent: keyset_root_hash.clone(),
+            invitee: invitee.agent(),
+            change_rule: change_rule_hash.clone(),
+        });
+        let device_invite = network.create(
+            &keyset.fda,
+            EntryTypes::DeviceInvite(DeviceInvite::new(
+                keyset_root_hash.clone(),
+                keyset_root_hash,
+                invitee.agent(),
+                inviter_signature,
+                change_rule_hash,
+                Vec::new(),
+            )),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::WrongNumberOfSignatures.into()
+        );
+    }
 
+    #[test]
+    fn invited_device_invites_below_its_acceptance() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let device = TestKey::new(1);
+        network.genesis(&device, None);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &device,
+        );
+        let device_invite_acceptance = network.accept(&device, &device_invite);
+        let device_invite = network.invite(
+            &device,
+            &keyset,
+            device_invite_acceptance.action_address(),
+            &TestKey::new(2),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn outsider_cannot_invite_into_a_keyset() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let outsider = network.keyset(20);
+        let device_invite = network.invite(
+            &outsider.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &TestKey::new(1),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::InviteNotByParentDevice.into()
+        );
+        let device_invite = network.invite(
+            &outsider.fda,
+            &keyset,
+            outsider.keyset_root.action_address(),
+            &TestKey::new(1),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::InviteOutsideKeyset.into()
+        );
+    }
 
+    #[test]
+    fn chain_without_source_of_authority_cannot_invite() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let outsider = TestKey::new(1);
+        network.genesis(&outsider, None);
+        let device_invite = network.invite(
+            &outsider,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &TestKey::new(2),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::InviteNotByParentDevice.into()
+        );
+    }
+
+    #[test]
+    fn device_that_left_the_keyset_cannot_invite_into_it() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let other = network.keyset(20);
+        let device_invite = network.invite(
+            &other.fda,
+            &other,
+            other.keyset_root.action_address(),
+            &keyset.fda,
+        );
+        network.accept(&keyset.fda, &device_invite);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &TestKey::new(1),
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::InviteNotByParentDevice.into()
+        );
+    }
+
+    #[test]
+    fn device_cannot_invite_itself() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &keyset.fda,
+        );
+        assert_eq!(
+            network.validate(&device_invite).unwrap(),
+            Error::InviteeIsInviter.into()
+        );
+    }
+}
@@ -1,38 +1,65 @@
 use hdi::prelude::*;
 
-#[hdk_entry_helper]
-#[derive(Clone)]
-pub struct DeviceInviteAcceptance {
-    /// The KSRA for the invite being accepted.
-    /// Not strictly required for validation as this is on the DeviceInvite.
-    /// This is here as it may save network hops other than during.
-    pub keyset_root_authority: ActionHash,
-    pub invite: ActionHash,
-}
+use crate::{
+    error::Error, has_source_of_authority, validate_follows_joining_proof,
+    validate_keyset_not_frozen, DeviceInviteAcceptance, SourceOfAuthority,
+};
 
-impl DeviceInviteAcceptance {
-    pub fn new(keyset_root_authority: ActionHash, invite: ActionHash) -> Self {
-        Self {
-            keyset_root_authority,
-            invite,
-        }
-    }
-}
-
-pub fn validate_create_device_invite_acceptance(
-    _action: EntryCreationAction,
-    device_invite_acceptance: DeviceInviteAcceptance,
+/// The invite must be valid on the DHT, addressed to `agent`, and into the keyset the
+/// acceptance names.
+pub fn validate_device_invite_acceptance(
+    agent: &AgentPubKey,
+    device_invite_acceptance: &DeviceInviteAcceptance,
 ) -> ExternResult<ValidateCallbackResult> {
     let record = must_get_valid_record(device_invite_acceptance.invite.clone())?;
-    let _device_invite: crate::DeviceInvite = record
+    let device_invite: crate::DeviceInvite = record
         .entry()
         .to_app_option()
         .map_err(|e| wasm_error!(e))?
         .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
             "Dependant action must be accompanied by an entry"
         ))))?;
+    if device_invite.invitee != *agent {
+        return Error::InviteForAnotherAgent.into();
+    }
+    if device_invite.keyset_root != device_invite_acceptance.keyset_root_authority {
+        return Error::InviteKeysetRootMismatch.into();
+    }
     Ok(ValidateCallbackResult::Valid)
 }
+/// A recovery invite gives a chain with no source of authority its first one, so its
+/// acceptance is refused on a chain that already has one. No invite can be accepted into a
+/// frozen keyset.
+pub fn validate_create_device_invite_acceptance(
+    action: EntryCreationAction,
+    device_invite_acceptance: DeviceInviteAcceptance,
+) -> ExternResult<ValidateCallbackResult> {
+    let device_invite = crate::DeviceInvite::try_from(must_get_valid_record(
+        device_invite_acceptance.invite.clone(),
+    )?)?;
+    if device_invite.recovery.is_some()
+        && has_source_of_authority(action.author(), action.prev_action())?
+    {
+        return Error::SourceOfAuthorityExists.into();
+    }
+    let result = validate_device_invite_acceptance(action.author(), &device_invite_acceptance)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let result = validate_keyset_not_frozen(
+        action.author().clone(),
+        action.prev_action().clone(),
+        &device_invite_acceptance.keyset_root_authority,
+        &device_invite_acceptance.keyset_freezes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    validate_follows_joining_proof(
+        &action,
+        &SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance),
+    )
+}
 pub fn validate_update_device_invite_acceptance(
     _action: Update,
     _device_invite_acceptance: DeviceInviteAcceptance,
@@ -92,3 +119,82 @@ pub fn validate_delete_link_device_invite_to_device_invite_acceptances(
         "DeviceInviteToDeviceInviteAcceptances links cannot be deleted",
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{membrane_proof, TestKey, TestNetwork};
+    use crate::{
+        validate_agent_joining, DeepkeyProperties, DeviceInvite, JoiningProof, MembraneMode,
+        MembraneProof,
+    };
+
+    fn invite_only() -> DeepkeyProperties {
+        DeepkeyProperties {
+            membrane_mode: MembraneMode::Gated,
+            accepted_membrane_proofs: Vec::new(),
+            accept_device_invites: true,
+            ..Default::default()
+        }
+    }
+
+    /// The membrane proof of an agent joining with an acceptance of `device_invite`.
+    fn joining_with(device_invite: &Record) -> Option<hdi::prelude::MembraneProof> {
+        let device_invite_acceptance = DeviceInviteAcceptance::new(
+            DeviceInvite::try_from(device_invite.clone())
+                .unwrap()
+                .keyset_root,
+            device_invite.action_address().clone(),
+        );
+        Some(membrane_proof(JoiningProof::new(
+            SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance),
+            MembraneProof::None,
+        )))
+    }
+
+    #[test]
+    fn invited_device_joins_an_invite_only_network() {
+        let network = TestNetwork::install(&invite_only());
+        let keyset = network.keyset(10);
+        let invitee = TestKey::new(1);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &invitee,
+        );
+        assert_eq!(
+            validate_agent_joining(
+                invitee.agent(),
+                Timestamp::from_micros(0),
+                &joining_with(&device_invite)
+            )
+            .unwrap(),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn outsider_cannot_invite_itself_into_a_keyset() {
+        let network = TestNetwork::install(&invite_only());
+        let keyset = network.keyset(10);
+        let outsider = network.keyset(20);
+        // The outsider's second device, invited by the outsider into another keyset.
+        let invitee = TestKey::new(1);
+        let device_invite = network.invite(
+            &outsider.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &invitee,
+        );
+        let error = validate_agent_joining(
+            invitee.agent(),
+            Timestamp::from_micros(0),
+            &joining_with(&device_invite),
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&Error::InviteNotByParentDevice.to_string()));
+    }
+}
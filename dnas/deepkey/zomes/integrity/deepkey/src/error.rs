@@ -53,6 +53,21 @@ pub enum Error {
     #[error("The new ChangeRule requires zero signatures")]
     NotEnoughSignatures,
 
+    #[error("The AuthoritySpec lists more than {0} signers")]
+    TooManySigners(usize),
+
+    #[error("The AuthorityPolicy can never be met")]
+    UnsatisfiableAuthorityPolicy,
+
+    #[error("The AuthorityPolicy nests deeper than {0} levels")]
+    AuthorityPolicyTooDeep(usize),
+
+    #[error("The authorization does not meet the ChangeRule's AuthorityPolicy")]
+    AuthorityPolicyNotMet,
+
+    #[error("The authorization has more than one signature from position {0}")]
+    DuplicateAuthorization(u8),
+
     #[error("The new ChangeRule update does not reference the root ChangeRule")]
     BranchingUpdates,
 
@@ -62,6 +77,210 @@ pub enum Error {
     #[error("The ChangeRule element has the wrong header")]
     WrongHeader,
 
+    #[error("Wrong KeyRegistration op for this action")]
+    RegistrationWrongOp,
+
+    #[error("Wrong key on KeyRegistration for this KeyAnchor")]
+    RegistrationWrongKey,
+
+    #[error("No KeyRegistration prior to KeyAnchor")]
+    RegistrationNone,
+
+    #[error("A KeyRegistration must be followed by the KeyAnchor change it stands for")]
+    KeyRegistrationNotFollowedByKeyAnchor,
+
+    #[error("The KeyRevocation does not revoke the KeyRegistration being updated")]
+    RevocationWrongRegistration,
+
+    #[error("The key is already registered and not revoked by KeyRegistration {0}")]
+    KeyAlreadyRegistered(ActionHash),
+
+    #[error("The prior KeyAnchor of the KeyGeneration is not a release of the new key")]
+    PriorKeyAnchorNotReleased,
+
+    #[error("The KeyGeneration was signed by the new key after it was committed")]
+    KeyGenerationFromFuture,
+
+    #[error("The KeyGeneration signature by the new key is too old")]
+    KeyGenerationExpired,
+
+    #[error("The KeyGeneration has an invalid signature by the new key")]
+    BadKeyGenerationSignature,
+
+    #[error("The KeysetRoot author is not its first deepkey agent")]
+    KeysetRootAuthorNotFda,
+
+    #[error("The KeysetRoot has an invalid signature by its root key")]
+    BadKeysetRootSignature,
+
+    #[error("The chain already has a KeysetRoot or DeviceInviteAcceptance")]
+    SourceOfAuthorityExists,
+
+    #[error("The KeyRevocation has an invalid signature")]
+    BadRevocationSignature,
+
+    #[error("The KeyRevocation is authorized by a ChangeRule of another keyset")]
+    RevocationKeysetRootMismatch,
+
+    #[error("The ChangeRule revision does not follow the revision it supersedes")]
+    WrongChangeRuleRevision,
+
+    #[error("The authorizing ChangeRule has already been superseded")]
+    StaleChangeRule,
+
+    #[error("This network is gated and requires a membrane proof")]
+    MembraneProofMissing,
+
+    #[error("The membrane proof is not a JoiningProof")]
+    MembraneProofMalformed,
+
+    #[error("This network does not accept {0} membrane proofs")]
+    MembraneProofNotAccepted(String),
+
+    #[error("{0} membrane proofs are not supported yet")]
+    MembraneProofUnsupported(String),
+
+    #[error("The ProofOfWork hash has fewer than {0} leading zero bits")]
+    InsufficientProofOfWork(u32),
+
+    #[error("A proof_of_work_difficulty of {0} is more than the bits of a ProofOfWork hash")]
+    ProofOfWorkDifficultyOutOfRange(u32),
+
+    #[error("The ProofOfExternalAuthority is signed by an authority this network does not trust")]
+    UntrustedExternalAuthority,
+
+    #[error("The ProofOfExternalAuthority was issued to another agent")]
+    ExternalAuthorityWrongAgent,
+
+    #[error("The ProofOfExternalAuthority expired before the agent joined")]
+    ExternalAuthorityExpired,
+
+    #[error("The ProofOfExternalAuthority has an invalid signature by its authority")]
+    BadExternalAuthoritySignature,
+
+    #[error("The DeviceInvite was issued to another agent")]
+    InviteForAnotherAgent,
+
+    #[error("The DeviceInviteAcceptance names a different KeysetRoot than its DeviceInvite")]
+    InviteKeysetRootMismatch,
+
+    #[error("A JoiningProof must be action {0} of the chain")]
+    JoiningProofWrongPosition(u32),
+
+    #[error("The source of authority differs from the one named by the preceding JoiningProof")]
+    JoiningProofSourceOfAuthorityMismatch,
+
+    #[error("The JoiningProof differs from the membrane proof the agent joined with")]
+    JoiningProofMembraneProofMismatch,
+
+    #[error("A JoiningProof must be followed by the source of authority it names")]
+    JoiningProofNotFollowedBySourceOfAuthority,
+
+    #[error("A committed JoiningProof must name the source of authority that follows it")]
+    JoiningProofWithoutSourceOfAuthority,
+
+    #[error("A membrane proof can only name a DeviceInviteAcceptance as its source of authority")]
+    MembraneProofNamesKeysetRoot,
+
+    #[error("A recovery DeviceInvite must be committed by its invitee")]
+    RecoveryInviteNotByInvitee,
+
+    #[error("A recovery DeviceInvite must have the KeysetRoot as its parent")]
+    RecoveryInviteNotUnderKeysetRoot,
+
+    #[error("The recovery DeviceInvite is authorized by a ChangeRule of another keyset")]
+    RecoveryKeysetRootMismatch,
+
+    #[error("The recovery DeviceInvite has an invalid signature")]
+    BadRecoverySignature,
+
+    #[error("The DeviceInvite was not issued by the device it was issued below")]
+    InviteNotByParentDevice,
+
+    #[error("The DeviceInvite is into a different keyset than its inviter's")]
+    InviteOutsideKeyset,
+
+    #[error("A DeviceInvite cannot be issued to its own inviter")]
+    InviteeIsInviter,
+
+    #[error("The DeviceInvite is not signed by its inviter")]
+    InviteNotSigned,
+
+    #[error("The DeviceInvite does not name the ChangeRule it was issued under")]
+    InviteChangeRuleMissing,
+
+    #[error("The DeviceInvite names a ChangeRule of another keyset")]
+    InviteChangeRuleKeysetMismatch,
+
+    #[error("The DeviceInvite has an invalid signature")]
+    BadInviteSignature,
+
+    #[error("The authorizing ChangeRule had not yet taken effect")]
+    ChangeRuleNotYetEffective,
+
+    #[error("The authorizing ChangeRule has been vetoed")]
+    ChangeRuleVetoed,
+
+    #[error("A ChangeRuleVeto must reference a ChangeRule update")]
+    VetoNotOfChangeRuleUpdate,
+
+    #[error("The ChangeRule update has already taken effect and can no longer be vetoed")]
+    VetoWindowClosed,
+
+    #[error("The ChangeRuleVeto author is not a device of the keyset")]
+    VetoerNotInKeyset,
+
+    #[error("The ChangeRuleVeto has an invalid signature")]
+    BadVetoSignature,
+
+    #[error("The keyset is frozen")]
+    KeysetFrozen,
+
+    #[error("The author is not a device of the keyset")]
+    FreezeAuthorNotInKeyset,
+
+    #[error("The KeysetUnfreeze references a freeze or ChangeRule of another keyset")]
+    UnfreezeKeysetRootMismatch,
+
+    #[error("The KeysetUnfreeze has an invalid signature")]
+    BadUnfreezeSignature,
+
+    #[error("Not enough signers of the new spec have accepted it")]
+    GuardianConsentMissing,
+
+    #[error("The GuardianAcceptance author is not a signer of the AuthoritySpec")]
+    GuardianNotListed,
+
+    #[error("Only spec changes and key revocations can be proposed")]
+    UnsupportedProposal,
+
+    #[error("The Proposal author is not a device of the keyset")]
+    ProposerNotInKeyset,
+
+    #[error("The Proposal payload is not bound to its keyset, network and ChangeRule")]
+    ProposalPayloadMismatch,
+
+    #[error("The ProposalSignature has an invalid signature")]
+    BadProposalSignature,
+
+    #[error("The sender encryption key was not published by the RecoveryShare author")]
+    RecoveryShareSenderKeyMismatch,
+
+    #[error("The recipient encryption key was not published by the RecoveryShare recipient")]
+    RecoveryShareRecipientKeyMismatch,
+
+    #[error("A RecoveryShare can only be released by the guardian it was entrusted to")]
+    RecoveryShareNotEntrusted,
+
+    #[error("Only a compromised key can have a compromise time")]
+    CompromiseTimeWithoutCompromise,
+
+    #[error("The compromise time is later than the revocation")]
+    CompromiseAfterRevocation,
+
+    #[error("A key can only be superseded by a replacement key")]
+    SupersededWithoutReplacement,
+
     #[error("Wasm error {0}")]
     Wasm(WasmError),
 }
@@ -0,0 +1,432 @@
+//! An in-memory network standing in for the host in tests: every agent's chain, served by
+//! the `must_get_*` calls, with a record's validity decided by running `validate` on it
+//! when it is asked for, as an authority would.
+
+use std::sync::{Arc, Mutex};
+
+use ed25519_dalek::{Signer, SigningKey};
+use hdi::hdi::{set_hdi, HdiT};
+
+use crate::*;
+
+/// `joining_proof` as the membrane proof an agent presents.
+pub fn membrane_proof(joining_proof: JoiningProof) -> hdi::prelude::MembraneProof {
+    Arc::new(SerializedBytes::try_from(joining_proof).unwrap())
+}
+
+pub struct TestKey(SigningKey);
+
+impl TestKey {
+    pub fn new(seed: u8) -> Self {
+        Self(SigningKey::from_bytes(&[seed; 32]))
+    }
+
+    pub fn agent(&self) -> AgentPubKey {
+        AgentPubKey::from_raw_32(self.0.verifying_key().to_bytes().to_vec())
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        Signature(self.0.sign(data).to_bytes())
+    }
+
+    pub fn sign_payload(&self, payload: impl Into<SigningPayload>) -> Signature {
+        self.sign(&payload.into().signable_bytes().unwrap())
+    }
+}
+
+fn guest_error(message: String) -> WasmError {
+    wasm_error!(WasmErrorInner::Guest(message))
+}
+
+fn zome_info() -> ZomeInfo {
+    let entry_types = <EntryTypes as EntryDefRegistration>::ENTRY_DEFS.len() as u8;
+    let link_types = LinkTypes::iter().count() as u8;
+    ZomeInfo::new(
+        "deepkey".into(),
+        ZomeIndex(0),
+        SerializedBytes::default(),
+        EntryDefs(Vec::new()),
+        Vec::new(),
+        ScopedZomeTypesSet {
+            entries: ScopedZomeTypes(vec![(
+                ZomeIndex(0),
+                (0..entry_types).map(EntryDefIndex).collect(),
+            )]),
+            links: ScopedZomeTypes(vec![(
+                ZomeIndex(0),
+                (0..link_types).map(LinkType).collect(),
+            )]),
+        },
+    )
+}
+
+#[derive(Clone)]
+struct TestHdi {
+    records: Arc<Mutex<Vec<Record>>>,
+    dna_hash: DnaHash,
+    properties: SerializedBytes,
+}
+
+impl TestHdi {
+    fn record(&self, action_hash: &ActionHash) -> ExternResult<Record> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|record| record.action_address() == action_hash)
+            .cloned()
+            .ok_or_else(|| guest_error(format!("No record at {}", action_hash)))
+    }
+}
+
+impl HdiT for TestHdi {
+    fn verify_signature(&self, verify_signature: VerifySignature) -> ExternResult<bool> {
+        Ok(verify_ed25519(
+            &verify_signature.key,
+            &verify_signature.signature,
+            &verify_signature.data,
+        ))
+    }
+
+    fn hash(&self, hash_input: HashInput) -> ExternResult<HashOutput> {
+        match hash_input {
+            HashInput::Entry(entry) => Ok(HashOutput::Entry(EntryHash::with_data_sync(&entry))),
+            HashInput::Action(action) => {
+                Ok(HashOutput::Action(ActionHash::with_data_sync(&action)))
+            }
+            _ => Err(guest_error(String::from("Unsupported hash"))),
+        }
+    }
+
+    fn must_get_entry(&self, input: MustGetEntryInput) -> ExternResult<EntryHashed> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|record| record.entry().as_option().cloned())
+            .map(EntryHashed::from_content_sync)
+            .find(|entry| *entry.as_hash() == input.0)
+            .ok_or_else(|| guest_error(format!("No entry at {}", input.0)))
+    }
+
+    fn must_get_action(&self, input: MustGetActionInput) -> ExternResult<SignedActionHashed> {
+        Ok(self.record(&input.0)?.signed_action)
+    }
+
+    fn must_get_valid_record(&self, input: MustGetValidRecordInput) -> ExternResult<Record> {
+        let record = self.record(&input.0)?;
+        match validate(Op::StoreRecord(StoreRecord {
+            record: record.clone(),
+        }))? {
+            ValidateCallbackResult::Valid => Ok(record),
+            result => Err(guest_error(format!(
+                "Record {} is not valid: {:?}",
+                input.0, result
+            ))),
+        }
+    }
+
+    fn must_get_agent_activity(
+        &self,
+        input: MustGetAgentActivityInput,
+    ) -> ExternResult<Vec<RegisterAgentActivity>> {
+        let chain_top = self.record(&input.chain_filter.chain_top)?;
+        let (take, until) = match input.chain_filter.filters {
+            ChainFilters::ToGenesis => (None, Default::default()),
+            ChainFilters::Take(take) => (Some(take), Default::default()),
+            ChainFilters::Until(until) => (None, until),
+            ChainFilters::Both(take, until) => (Some(take), until),
+        };
+        let mut activities = Vec::new();
+        for record in self.records.lock().unwrap().iter().rev() {
+            if *record.action().author() != input.author
+                || record.action().action_seq() > chain_top.action().action_seq()
+            {
+                continue;
+            }
+            activities.push(RegisterAgentActivity {
+                action: record.signed_action.clone(),
+                cached_entry: None,
+            });
+            if take == Some(activities.len() as u32) || until.contains(record.action_address()) {
+                break;
+            }
+        }
+        Ok(activities)
+    }
+
+    fn dna_info(&self, _: ()) -> ExternResult<DnaInfo> {
+        Ok(DnaInfo {
+            name: String::from("deepkey"),
+            hash: self.dna_hash.clone(),
+            properties: self.properties.clone(),
+            zome_names: vec!["deepkey".into()],
+        })
+    }
+
+    fn zome_info(&self, _: ()) -> ExternResult<ZomeInfo> {
+        Ok(zome_info())
+    }
+
+    fn trace(&self, _: TraceMsg) -> ExternResult<()> {
+        Ok(())
+    }
+
+    fn x_salsa20_poly1305_decrypt(
+        &self,
+        _: XSalsa20Poly1305Decrypt,
+    ) -> ExternResult<Option<XSalsa20Poly1305Data>> {
+        Err(guest_error(String::from("Unsupported decryption")))
+    }
+
+    fn x_25519_x_salsa20_poly1305_decrypt(
+        &self,
+        _: X25519XSalsa20Poly1305Decrypt,
+    ) -> ExternResult<Option<XSalsa20Poly1305Data>> {
+        Err(guest_error(String::from("Unsupported decryption")))
+    }
+}
+
+/// A keyset created on its first deepkey agent's chain.
+pub struct TestKeyset {
+    pub fda: TestKey,
+    pub guardian: TestKey,
+    pub keyset_root: Record,
+    pub change_rule: Record,
+}
+
+/// The network the calling test's thread validates against.
+pub struct TestNetwork(TestHdi);
+
+impl TestNetwork {
+    /// Installs a network with `properties` as the host of the calling thread.
+    pub fn install(properties: &DeepkeyProperties) -> Self {
+        let hdi = TestHdi {
+            records: Arc::new(Mutex::new(Vec::new())),
+            dna_hash: DnaHash::from_raw_32(vec![0xdb; 32]),
+            properties: SerializedBytes::from(UnsafeBytes::from(encode(properties).unwrap())),
+        };
+        set_hdi(hdi.clone());
+        Self(hdi)
+    }
+
+    pub fn dna_hash(&self) -> DnaHash {
+        self.0.dna_hash.clone()
+    }
+
+    fn chain_head(&self, author: &AgentPubKey) -> Option<(u32, ActionHash)> {
+        self.0
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|record| record.action().author() == author)
+            .map(|record| {
+                (
+                    record.action().action_seq(),
+                    record.action_address().clone(),
+                )
+            })
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from_micros(self.0.records.lock().unwrap().len() as i64)
+    }
+
+    // Signs and publishes the action `build` makes at the head of `author`'s chain.
+    fn commit(
+        &self,
+        author: &TestKey,
+        entry: Option<Entry>,
+        build: impl FnOnce(Timestamp, u32, ActionHash) -> Action,
+    ) -> Record {
+        let (action_seq, prev_action) = self
+            .chain_head(&author.agent())
+            .expect("The chain has no genesis");
+        let action = build(self.timestamp(), action_seq + 1, prev_action);
+        let signature = author.sign(&encode(&action).unwrap());
+        let record = Record::new(
+            SignedActionHashed::with_presigned(ActionHashed::from_content_sync(action), signature),
+            entry,
+        );
+        self.0.records.lock().unwrap().push(record.clone());
+        record
+    }
+
+    /// The genesis actions of `agent`'s chain, joining with `membrane_proof`.
+    pub fn genesis(
+        &self,
+        agent: &TestKey,
+        membrane_proof: Option<hdi::prelude::MembraneProof>,
+    ) -> Record {
+        let dna = Action::Dna(Dna {
+            author: agent.agent(),
+            timestamp: self.timestamp(),
+            hash: self.dna_hash(),
+        });
+        let signature = agent.sign(&encode(&dna).unwrap());
+        self.0.records.lock().unwrap().push(Record::new(
+            SignedActionHashed::with_presigned(ActionHashed::from_content_sync(dna), signature),
+            None,
+        ));
+        self.commit(agent, None, |timestamp, action_seq, prev_action| {
+            Action::AgentValidationPkg(AgentValidationPkg {
+                author: agent.agent(),
+                timestamp,
+                action_seq,
+                prev_action,
+                membrane_proof,
+            })
+        });
+        let entry = Entry::Agent(agent.agent());
+        self.commit(
+            agent,
+            Some(entry.clone()),
+            |timestamp, action_seq, prev_action| {
+                Action::Create(Create {
+                    author: agent.agent(),
+                    timestamp,
+                    action_seq,
+                    prev_action,
+                    entry_type: EntryType::AgentPubKey,
+                    entry_hash: EntryHash::with_data_sync(&entry),
+                    weight: EntryRateWeight::default(),
+                })
+            },
+        )
+    }
+
+    /// `author` committing `entry`, published whether or not it is valid.
+    pub fn create(&self, author: &TestKey, entry: EntryTypes) -> Record {
+        let scoped_entry_def_index = ScopedEntryDefIndex::try_from(&entry).unwrap();
+        let visibility = EntryVisibility::from(&entry);
+        let entry = Entry::try_from(entry).unwrap();
+        self.commit(
+            author,
+            Some(entry.clone()),
+            |timestamp, action_seq, prev_action| {
+                Action::Create(Create {
+                    author: author.agent(),
+                    timestamp,
+                    action_seq,
+                    prev_action,
+                    entry_type: EntryType::App(AppEntryDef::new(
+                        scoped_entry_def_index.zome_type,
+                        scoped_entry_def_index.zome_index,
+                        visibility,
+                    )),
+                    entry_hash: EntryHash::with_data_sync(&entry),
+                    weight: EntryRateWeight::default(),
+                })
+            },
+        )
+    }
+
+    /// Validates `record` as its authorities would.
+    pub fn validate(&self, record: &Record) -> ExternResult<ValidateCallbackResult> {
+        validate(Op::StoreRecord(StoreRecord {
+            record: record.clone(),
+        }))
+    }
+
+    /// A new keyset on `fda`'s chain, with a first ChangeRule whose spec is the
+    /// `guardians`, signed by `root`. Returns the KeysetRoot and ChangeRule records.
+    pub fn create_keyset(
+        &self,
+        fda: &TestKey,
+        root: &TestKey,
+        guardians: &[&TestKey],
+    ) -> (Record, Record) {
+        let keyset_root = KeysetRoot::new(
+            fda.agent(),
+            root.agent(),
+            root.sign_payload(KeysetRootPayload {
+                dna_hash: self.dna_hash(),
+                first_deepkey_agent: fda.agent(),
+            }),
+        );
+        let keyset_root = self.create(fda, EntryTypes::KeysetRoot(keyset_root));
+        let keyset_root_hash = keyset_root.action_address().clone();
+        let new_spec = AuthoritySpec::new(
+            1,
+            guardians.iter().map(|guardian| guardian.agent()).collect(),
+        );
+        let signature = root.sign_payload(SpecChangePayload {
+            keyset_root: None,
+            dna_hash: self.dna_hash(),
+            prev_change_rule: None,
+            revision: 0,
+            new_spec: new_spec.clone(),
+            operation_specs: OperationSpecs::default(),
+            update_delay_secs: 0,
+        });
+        let change_rule = ChangeRule::new(
+            keyset_root_hash.clone(),
+            keyset_root_hash,
+            0,
+            AuthorizedSpecChange::new(new_spec, vec![(0, signature)]),
+        );
+        let change_rule = self.create(fda, EntryTypes::ChangeRule(change_rule));
+        (keyset_root, change_rule)
+    }
+
+    /// `inviter` inviting `invitee` below `parent` into `keyset`, under its first ChangeRule
+    /// and approved by its guardian, published whether or not the inviter is a device of
+    /// the keyset.
+    pub fn invite(
+        &self,
+        inviter: &TestKey,
+        keyset: &TestKeyset,
+        parent: &ActionHash,
+        invitee: &TestKey,
+    ) -> Record {
+        let keyset_root_hash = keyset.keyset_root.action_address().clone();
+        let payload = DeviceInvitePayload {
+            keyset_root: keyset_root_hash.clone(),
+            dna_hash: self.dna_hash(),
+            parent: parent.clone(),
+            invitee: invitee.agent(),
+            change_rule: keyset.change_rule.action_address().clone(),
+        };
+        let device_invite = DeviceInvite::new(
+            keyset_root_hash,
+            parent.clone(),
+            invitee.agent(),
+            inviter.sign_payload(payload.clone()),
+            keyset.change_rule.action_address().clone(),
+            vec![(0, keyset.guardian.sign_payload(payload))],
+        );
+        self.create(inviter, EntryTypes::DeviceInvite(device_invite))
+    }
+
+    /// A new agent, seeded by `seed`, with a keyset whose root key and guardian are seeded
+    /// by the next two seeds.
+    pub fn keyset(&self, seed: u8) -> TestKeyset {
+        let fda = TestKey::new(seed);
+        let guardian = TestKey::new(seed + 2);
+        self.genesis(&fda, None);
+        let (keyset_root, change_rule) =
+            self.create_keyset(&fda, &TestKey::new(seed + 1), &[&guardian]);
+        TestKeyset {
+            fda,
+            guardian,
+            keyset_root,
+            change_rule,
+        }
+    }
+
+    /// `invitee` accepting `device_invite`.
+    pub fn accept(&self, invitee: &TestKey, device_invite: &Record) -> Record {
+        let device_invite_acceptance = DeviceInviteAcceptance::new(
+            DeviceInvite::try_from(device_invite.clone())
+                .unwrap()
+                .keyset_root,
+            device_invite.action_address().clone(),
+        );
+        self.create(
+            invitee,
+            EntryTypes::DeviceInviteAcceptance(device_invite_acceptance),
+        )
+    }
+}
@@ -0,0 +1,134 @@
+use hdi::prelude::*;
+
+use crate::{error::Error, AuthoritySpec};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardianDecision {
+    Accept,
+    Decline,
+}
+
+// A signer listed in an AuthoritySpec consenting to, or declining, guarding under it.
+// The spec is named by its entry hash, so the same consent covers that spec wherever a
+// ChangeRule carries it.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct GuardianAcceptance {
+    pub authority_spec: EntryHash,
+    pub decision: GuardianDecision,
+}
+
+/// The positions in `authority_spec` whose signers accepted it through the cited
+/// GuardianAcceptances. Only the latest cited decision of each signer counts, so an
+/// acceptance is withdrawn by citing a later decline of the same spec.
+pub fn get_consenting_positions(
+    authority_spec: &AuthoritySpec,
+    guardian_acceptances: &[ActionHash],
+) -> ExternResult<std::collections::BTreeSet<u8>> {
+    let authority_spec_hash = hash_entry(authority_spec)?;
+    let mut latest: std::collections::BTreeMap<AgentPubKey, (u32, GuardianDecision)> =
+        std::collections::BTreeMap::new();
+    for guardian_acceptance_hash in guardian_acceptances {
+        let record = must_get_valid_record(guardian_acceptance_hash.clone())?;
+        let author = record.action().author().clone();
+        let action_seq = record.action().action_seq();
+        let guardian_acceptance = GuardianAcceptance::try_from(record)?;
+        if guardian_acceptance.authority_spec != authority_spec_hash {
+            continue;
+        }
+        match latest.get(&author) {
+            Some((latest_seq, _)) if *latest_seq >= action_seq => {}
+            _ => {
+                latest.insert(author, (action_seq, guardian_acceptance.decision));
+            }
+        }
+    }
+    let mut positions = std::collections::BTreeSet::new();
+    for (position, signer) in authority_spec.authorized_signers.iter().enumerate() {
+        if let Some((_, GuardianDecision::Accept)) = latest.get(signer) {
+            positions.insert(position as u8);
+        }
+    }
+    Ok(positions)
+}
+
+/// A spec that requires consent is only usable once the signers who accepted it could
+/// satisfy its policy on their own.
+pub fn validate_guardian_consent(
+    authority_spec: &AuthoritySpec,
+    guardian_acceptances: &[ActionHash],
+) -> ExternResult<ValidateCallbackResult> {
+    if !authority_spec.requires_consent {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    let positions = get_consenting_positions(authority_spec, guardian_acceptances)?;
+    if !authority_spec.policy().is_satisfied_by(&positions) {
+        return Error::GuardianConsentMissing.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Only a signer listed in the spec can accept or decline it.
+pub fn validate_create_guardian_acceptance(
+    action: EntryCreationAction,
+    guardian_acceptance: GuardianAcceptance,
+) -> ExternResult<ValidateCallbackResult> {
+    let authority_spec =
+        AuthoritySpec::try_from(must_get_entry(guardian_acceptance.authority_spec)?)?;
+    if !authority_spec.authorized_signers.contains(action.author()) {
+        return Error::GuardianNotListed.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_guardian_acceptance(
+    _action: Update,
+    _guardian_acceptance: GuardianAcceptance,
+    _original_action: EntryCreationAction,
+    _original_guardian_acceptance: GuardianAcceptance,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Guardian Acceptances cannot be updated",
+    )))
+}
+pub fn validate_delete_guardian_acceptance(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_guardian_acceptance: GuardianAcceptance,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Guardian Acceptances cannot be deleted",
+    )))
+}
+pub fn validate_create_link_authority_spec_to_guardian_acceptances(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let guardian_acceptance: crate::GuardianAcceptance = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(guardian_acceptance.authority_spec) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A GuardianAcceptance must be linked from the AuthoritySpec it answers",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_authority_spec_to_guardian_acceptances(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "AuthoritySpecToGuardianAcceptances links cannot be deleted",
+    )))
+}
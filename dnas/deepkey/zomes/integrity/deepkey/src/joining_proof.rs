@@ -1,24 +1,100 @@
 use hdi::prelude::*;
 
-use crate::SourceOfAuthority;
+use crate::{
+    error::Error, validate_device_invite_acceptance, DeepkeyProperties, ExternalAuthorityPayload,
+    MembraneMode, SigningPayload, SourceOfAuthority, UnitEntryTypes,
+};
 
-// @todo - e.g. configurable difficulty over hashing the DNA - https://docs.rs/pow/0.2.0/pow/
+pub const JOINING_PROOF_INDEX: u32 = POST_GENESIS_SEQ_THRESHOLD;
+
+pub const PROOF_OF_WORK_HASH_BYTES: usize = 32;
+
+/// No proof can have more leading zero bits than its hash has bits.
+pub const MAX_PROOF_OF_WORK_DIFFICULTY: u32 = PROOF_OF_WORK_HASH_BYTES as u32 * 8;
+
+// A nonce such that the blake2b hash of the agent key, the DNA hash and the nonce
+// has at least the number of leading zero bits required by the DNA properties.
+// Binding the agent and DNA means a proof cannot be reused by another agent or in
+// another network.
 #[hdk_entry_helper]
-#[derive(Clone)]
-pub struct ProofOfWork([u8; 32]);
+#[derive(Clone, PartialEq)]
+pub struct ProofOfWork {
+    pub nonce: u64,
+}
+
+impl ProofOfWork {
+    /// blake2b(agent key ‖ DNA hash ‖ big endian nonce)
+    pub fn hash(&self, agent: &AgentPubKey, dna_hash: &DnaHash) -> [u8; PROOF_OF_WORK_HASH_BYTES] {
+        let mut state = blake2b_simd::Params::new()
+            .hash_length(PROOF_OF_WORK_HASH_BYTES)
+            .to_state();
+        state.update(agent.get_raw_39());
+        state.update(dna_hash.get_raw_39());
+        state.update(&self.nonce.to_be_bytes());
+        let mut hash = [0; PROOF_OF_WORK_HASH_BYTES];
+        hash.copy_from_slice(state.finalize().as_bytes());
+        hash
+    }
+
+    /// The number of leading zero bits of the proof's hash.
+    pub fn difficulty(&self, agent: &AgentPubKey, dna_hash: &DnaHash) -> u32 {
+        let mut bits = 0;
+        for byte in self.hash(agent, dna_hash) {
+            bits += byte.leading_zeros();
+            if byte != 0 {
+                break;
+            }
+        }
+        bits
+    }
+
+    /// Searches the first `max_attempts` nonces for a proof meeting `difficulty`, for
+    /// installers to call before joining. Runs natively; it needs nothing from the conductor.
+    /// Returns None if none of them meets it, or if no proof can.
+    pub fn generate(
+        agent: &AgentPubKey,
+        dna_hash: &DnaHash,
+        difficulty: u32,
+        max_attempts: u64,
+    ) -> Option<Self> {
+        if difficulty > MAX_PROOF_OF_WORK_DIFFICULTY {
+            return None;
+        }
+        (0..max_attempts)
+            .map(|nonce| Self { nonce })
+            .find(|proof| proof.difficulty(agent, dna_hash) >= difficulty)
+    }
+}
 
 // @todo
 #[hdk_entry_helper]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct ProofOfStake([u8; 32]);
 
-// @todo
+// A certificate from an authority the network trusts, admitting one agent until it expires.
+// The authority signs an ExternalAuthorityPayload of the DNA hash, the agent and the expiry.
 #[hdk_entry_helper]
-#[derive(Clone)]
-pub struct ProofOfExternalAuthority([u8; 32]);
+#[derive(Clone, PartialEq)]
+pub struct ProofOfExternalAuthority {
+    pub authority: AgentPubKey,
+    pub agent: AgentPubKey,
+    pub expires_at: Timestamp,
+    pub signature: Signature,
+}
+
+impl ProofOfExternalAuthority {
+    /// What the authority signs to issue a certificate for `agent` in the network `dna_hash`.
+    pub fn payload(dna_hash: DnaHash, agent: AgentPubKey, expires_at: Timestamp) -> SigningPayload {
+        SigningPayload::from(ExternalAuthorityPayload {
+            dna_hash,
+            agent,
+            expires_at,
+        })
+    }
+}
 
 #[hdk_entry_helper]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum MembraneProof {
     // No additional membrane.
     None,
@@ -30,28 +106,318 @@ pub enum MembraneProof {
     ProofOfExternalAuthority(ProofOfExternalAuthority),
 }
 
+impl MembraneProof {
+    pub fn kind(&self) -> MembraneProofKind {
+        match self {
+            Self::None => MembraneProofKind::None,
+            Self::ProofOfWork(_) => MembraneProofKind::ProofOfWork,
+            Self::ProofOfStake(_) => MembraneProofKind::ProofOfStake,
+            Self::ProofOfExternalAuthority(_) => MembraneProofKind::ProofOfExternalAuthority,
+        }
+    }
+}
+
+// The variants of MembraneProof, as named in the DNA properties.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum MembraneProofKind {
+    None,
+    ProofOfWork,
+    ProofOfStake,
+    ProofOfExternalAuthority,
+}
+
+impl MembraneProofKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::ProofOfWork => "proof_of_work",
+            Self::ProofOfStake => "proof_of_stake",
+            Self::ProofOfExternalAuthority => "proof_of_external_authority",
+        }
+    }
+}
+
+impl TryFrom<String> for MembraneProofKind {
+    type Error = String;
+    fn try_from(kind: String) -> Result<Self, Self::Error> {
+        match kind.as_str() {
+            "none" => Ok(Self::None),
+            "proof_of_work" => Ok(Self::ProofOfWork),
+            "proof_of_stake" => Ok(Self::ProofOfStake),
+            "proof_of_external_authority" => Ok(Self::ProofOfExternalAuthority),
+            _ => Err(format!("Unknown membrane proof {}", kind)),
+        }
+    }
+}
+
+impl From<MembraneProofKind> for String {
+    fn from(kind: MembraneProofKind) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
 #[hdk_entry_helper]
 #[derive(Clone)]
 pub struct JoiningProof {
-    pub source_of_authority: SourceOfAuthority,
+    // The DeviceInviteAcceptance an invited agent joins with, and otherwise `None` in a
+    // membrane proof. The JoiningProof `init` commits always names the KeysetRoot or
+    // DeviceInviteAcceptance committed after it.
+    #[serde(default)]
+    pub source_of_authority: Option<SourceOfAuthority>,
     pub membrane_proof: MembraneProof,
+    // Set in the membrane proof of an agent joining to recover the keyset of the KeysetRoot
+    // it names, so that `init` leaves the chain without a source of authority for
+    // `recover_keyset` to give it.
+    #[serde(default)]
+    pub recovering: bool,
 }
 
 impl JoiningProof {
     pub fn new(source_of_authority: SourceOfAuthority, membrane_proof: MembraneProof) -> Self {
         Self {
-            source_of_authority,
+            source_of_authority: Some(source_of_authority),
             membrane_proof,
+            recovering: false,
+        }
+    }
+}
+
+/// Decodes the `JoiningProof` an agent presented as its membrane proof, if any.
+pub fn decode_joining_proof(
+    membrane_proof: &Option<hdi::prelude::MembraneProof>,
+) -> Result<Option<JoiningProof>, Error> {
+    match membrane_proof {
+        Some(membrane_proof) => JoiningProof::try_from((**membrane_proof).clone())
+            .map(Some)
+            .map_err(|_| Error::MembraneProofMalformed),
+        None => Ok(None),
+    }
+}
+
+pub fn validate_proof_of_work(
+    properties: &DeepkeyProperties,
+    dna_hash: &DnaHash,
+    agent: &AgentPubKey,
+    proof_of_work: &ProofOfWork,
+) -> ExternResult<ValidateCallbackResult> {
+    if proof_of_work.difficulty(agent, dna_hash) < properties.proof_of_work_difficulty {
+        return Error::InsufficientProofOfWork(properties.proof_of_work_difficulty).into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// The certificate must be issued to `agent` by one of the trusted authorities in the DNA
+/// properties. Expiry is only checked when the time the agent joined is known, as
+/// `genesis_self_check` has no clock; `validate_agent_joining` always checks it.
+pub fn validate_proof_of_external_authority(
+    properties: &DeepkeyProperties,
+    dna_hash: &DnaHash,
+    agent: &AgentPubKey,
+    joined_at: Option<Timestamp>,
+    proof: &ProofOfExternalAuthority,
+) -> ExternResult<ValidateCallbackResult> {
+    if !properties.trusted_authorities.contains(&proof.authority) {
+        return Error::UntrustedExternalAuthority.into();
+    }
+    if proof.agent != *agent {
+        return Error::ExternalAuthorityWrongAgent.into();
+    }
+    if let Some(joined_at) = joined_at {
+        if joined_at > proof.expires_at {
+            return Error::ExternalAuthorityExpired.into();
         }
     }
+    let payload =
+        ProofOfExternalAuthority::payload(dna_hash.clone(), agent.clone(), proof.expires_at);
+    if !verify_signature_raw(
+        proof.authority.clone(),
+        proof.signature.clone(),
+        payload.to_signable_bytes()?,
+    )? {
+        return Error::BadExternalAuthoritySignature.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
 }
 
+/// Admits an agent according to the membrane mode and accepted membrane proofs in the DNA
+/// properties.
+/// In an open network a proof is optional, but one that is presented must still be valid.
+/// A DeviceInviteAcceptance admits the agent to a gated network if the DNA properties accept
+/// device invites; the invite itself is checked by `validate_joining_source_of_authority`.
+/// No other source of authority can be presented, as a KeysetRoot only exists once committed.
+/// Must not depend on the network, as it also runs in `genesis_self_check`.
+pub fn validate_joining_proof(
+    dna_info: &DnaInfo,
+    agent: &AgentPubKey,
+    joined_at: Option<Timestamp>,
+    membrane_proof: &Option<hdi::prelude::MembraneProof>,
+) -> ExternResult<ValidateCallbackResult> {
+    let properties = DeepkeyProperties::from_dna_info(dna_info)?;
+    let joining_proof = match decode_joining_proof(membrane_proof) {
+        Ok(Some(joining_proof)) => joining_proof,
+        Ok(None) => {
+            return match properties.membrane_mode {
+                MembraneMode::Open => Ok(ValidateCallbackResult::Valid),
+                MembraneMode::Gated => Error::MembraneProofMissing.into(),
+            }
+        }
+        Err(e) => return e.into(),
+    };
+    if let Some(SourceOfAuthority::KeysetRoot(_)) = joining_proof.source_of_authority {
+        return Error::MembraneProofNamesKeysetRoot.into();
+    }
+    let kind = joining_proof.membrane_proof.kind();
+    let invited = properties.accept_device_invites && joining_proof.source_of_authority.is_some();
+    if !invited && !properties.accepts(kind) {
+        return Error::MembraneProofNotAccepted(kind.as_str().to_string()).into();
+    }
+    match joining_proof.membrane_proof {
+        MembraneProof::None => Ok(ValidateCallbackResult::Valid),
+        MembraneProof::ProofOfWork(proof_of_work) => {
+            validate_proof_of_work(&properties, &dna_info.hash, agent, &proof_of_work)
+        }
+        MembraneProof::ProofOfExternalAuthority(proof) => validate_proof_of_external_authority(
+            &properties,
+            &dna_info.hash,
+            agent,
+            joined_at,
+            &proof,
+        ),
+        MembraneProof::ProofOfStake(_) => {
+            Error::MembraneProofUnsupported(kind.as_str().to_string()).into()
+        }
+    }
+}
+
+/// Checks a DeviceInviteAcceptance presented as the joining source of authority against its
+/// invite on the DHT. Needs the network, so it only runs in `validate_agent_joining`.
+pub fn validate_joining_source_of_authority(
+    agent: &AgentPubKey,
+    membrane_proof: &Option<hdi::prelude::MembraneProof>,
+) -> ExternResult<ValidateCallbackResult> {
+    match decode_joining_proof(membrane_proof) {
+        Ok(Some(JoiningProof {
+            source_of_authority:
+                Some(SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance)),
+            ..
+        })) => validate_device_invite_acceptance(agent, &device_invite_acceptance),
+        Ok(_) => Ok(ValidateCallbackResult::Valid),
+        Err(e) => e.into(),
+    }
+}
+
+/// A JoiningProof is the first entry after genesis, directly before the KeysetRoot or
+/// DeviceInviteAcceptance it must name. It must record the membrane proof the agent joined with,
+/// or `MembraneProof::None` if it joined without one, and an invite it joined with must be
+/// the source of authority it names.
 pub fn validate_create_joining_proof(
-    _action: EntryCreationAction,
-    _joining_proof: JoiningProof,
+    action: EntryCreationAction,
+    joining_proof: JoiningProof,
 ) -> ExternResult<ValidateCallbackResult> {
+    if *action.action_seq() != JOINING_PROOF_INDEX {
+        return Error::JoiningProofWrongPosition(JOINING_PROOF_INDEX).into();
+    }
+    if joining_proof.source_of_authority.is_none() {
+        return Error::JoiningProofWithoutSourceOfAuthority.into();
+    }
+    // The JoiningProof follows the agent key, which follows the AgentValidationPkg.
+    let create_agent = must_get_action(action.prev_action().clone())?;
+    let agent_validation_pkg = match create_agent.action().prev_action() {
+        Some(prev_action) => must_get_action(prev_action.clone())?,
+        None => return Error::JoiningProofWrongPosition(JOINING_PROOF_INDEX).into(),
+    };
+    let membrane_proof = match agent_validation_pkg.action() {
+        Action::AgentValidationPkg(AgentValidationPkg { membrane_proof, .. }) => membrane_proof,
+        _ => return Error::JoiningProofWrongPosition(JOINING_PROOF_INDEX).into(),
+    };
+    let presented = match decode_joining_proof(membrane_proof) {
+        Ok(presented) => presented,
+        Err(e) => return e.into(),
+    };
+    match presented {
+        Some(presented) => {
+            if presented.membrane_proof != joining_proof.membrane_proof {
+                return Error::JoiningProofMembraneProofMismatch.into();
+            }
+            if presented.source_of_authority.is_some()
+                && presented.source_of_authority != joining_proof.source_of_authority
+            {
+                return Error::JoiningProofSourceOfAuthorityMismatch.into();
+            }
+        }
+        None => {
+            if joining_proof.membrane_proof != MembraneProof::None {
+                return Error::JoiningProofMembraneProofMismatch.into();
+            }
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+/// A KeysetRoot or DeviceInviteAcceptance committed directly after a JoiningProof must be the
+/// source of authority that JoiningProof names.
+pub fn validate_follows_joining_proof(
+    action: &EntryCreationAction,
+    source_of_authority: &SourceOfAuthority,
+) -> ExternResult<ValidateCallbackResult> {
+    let prev_action = must_get_action(action.prev_action().clone())?;
+    if let Some(joining_proof) = get_joining_proof(prev_action.action())? {
+        if joining_proof.source_of_authority.as_ref() != Some(source_of_authority) {
+            return Error::JoiningProofSourceOfAuthorityMismatch.into();
+        }
+    }
     Ok(ValidateCallbackResult::Valid)
 }
+/// Whatever action follows a JoiningProof must create the source of authority it names, so a
+/// chain cannot claim a source of authority it never committed.
+pub fn validate_joining_proof_successor(action: &Action) -> ExternResult<ValidateCallbackResult> {
+    if action.action_seq() != JOINING_PROOF_INDEX + 1 {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+    let prev_action = match action.prev_action() {
+        Some(prev_action) => must_get_action(prev_action.clone())?,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    let joining_proof = match get_joining_proof(prev_action.action())? {
+        Some(joining_proof) => joining_proof,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    let (unit_entry_type, source_of_authority_hash) = match &joining_proof.source_of_authority {
+        Some(SourceOfAuthority::KeysetRoot(keyset_root)) => {
+            (UnitEntryTypes::KeysetRoot, hash_entry(keyset_root)?)
+        }
+        Some(SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance)) => (
+            UnitEntryTypes::DeviceInviteAcceptance,
+            hash_entry(device_invite_acceptance)?,
+        ),
+        None => return Error::JoiningProofWithoutSourceOfAuthority.into(),
+    };
+    match action {
+        Action::Create(Create {
+            entry_type: EntryType::App(app_entry_def),
+            entry_hash,
+            ..
+        }) if *app_entry_def == AppEntryDef::try_from(unit_entry_type)?
+            && *entry_hash == source_of_authority_hash =>
+        {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Error::JoiningProofNotFollowedBySourceOfAuthority.into(),
+    }
+}
+/// The JoiningProof `action` created, if it created one.
+fn get_joining_proof(action: &Action) -> ExternResult<Option<JoiningProof>> {
+    match action.entry_data() {
+        Some((entry_hash, EntryType::App(app_entry_def)))
+            if *app_entry_def == AppEntryDef::try_from(UnitEntryTypes::JoiningProof)? =>
+        {
+            Ok(Some(JoiningProof::try_from(must_get_entry(
+                entry_hash.clone(),
+            )?)?))
+        }
+        _ => Ok(None),
+    }
+}
 pub fn validate_update_joining_proof(
     _action: Update,
     _joining_proof: JoiningProof,
@@ -71,3 +437,146 @@ pub fn validate_delete_joining_proof(
         "Joining Proofs cannot be deleted",
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{membrane_proof, TestKey, TestNetwork};
+    use crate::{EntryTypes, KeysetRoot};
+
+    fn agent() -> AgentPubKey {
+        AgentPubKey::from_raw_36(vec![1; 36])
+    }
+
+    fn dna_hash() -> DnaHash {
+        DnaHash::from_raw_36(vec![2; 36])
+    }
+
+    #[test]
+    fn generated_proof_meets_difficulty() {
+        let proof = ProofOfWork::generate(&agent(), &dna_hash(), 8, 1 << 16).unwrap();
+        assert!(proof.difficulty(&agent(), &dna_hash()) >= 8);
+        // The search stops at the first nonce that meets the difficulty.
+        assert!((0..proof.nonce)
+            .all(|nonce| { ProofOfWork { nonce }.difficulty(&agent(), &dna_hash()) < 8 }));
+    }
+
+    #[test]
+    fn proof_is_bound_to_agent_and_dna() {
+        let proof = ProofOfWork { nonce: 7 };
+        let hash = proof.hash(&agent(), &dna_hash());
+        assert_ne!(
+            hash,
+            proof.hash(&AgentPubKey::from_raw_36(vec![3; 36]), &dna_hash())
+        );
+        assert_ne!(
+            hash,
+            proof.hash(&agent(), &DnaHash::from_raw_36(vec![3; 36]))
+        );
+    }
+
+    #[test]
+    fn difficulty_counts_leading_zero_bits() {
+        let proof = ProofOfWork { nonce: 0 };
+        let hash = proof.hash(&agent(), &dna_hash());
+        let zero_bytes = hash.iter().take_while(|byte| **byte == 0).count() as u32;
+        let expected = zero_bytes * 8
+            + hash
+                .get(zero_bytes as usize)
+                .map_or(0, |byte| byte.leading_zeros());
+        assert_eq!(proof.difficulty(&agent(), &dna_hash()), expected);
+        assert!(proof.difficulty(&agent(), &dna_hash()) <= MAX_PROOF_OF_WORK_DIFFICULTY);
+    }
+
+    #[test]
+    fn zero_difficulty_takes_the_first_nonce() {
+        let proof = ProofOfWork::generate(&agent(), &dna_hash(), 0, 1).unwrap();
+        assert_eq!(proof.nonce, 0);
+    }
+
+    #[test]
+    fn search_gives_up_after_max_attempts() {
+        assert_eq!(ProofOfWork::generate(&agent(), &dna_hash(), 64, 1000), None);
+        assert_eq!(ProofOfWork::generate(&agent(), &dna_hash(), 0, 0), None);
+    }
+
+    #[test]
+    fn difficulty_beyond_the_hash_is_refused() {
+        assert_eq!(
+            ProofOfWork::generate(
+                &agent(),
+                &dna_hash(),
+                MAX_PROOF_OF_WORK_DIFFICULTY + 1,
+                u64::MAX
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn membrane_proof_needs_no_source_of_authority() {
+        let _network = TestNetwork::install(&DeepkeyProperties::default());
+        let joining_proof = JoiningProof {
+            source_of_authority: None,
+            membrane_proof: MembraneProof::None,
+            recovering: false,
+        };
+        assert_eq!(
+            validate_joining_proof(
+                &hdi::prelude::dna_info().unwrap(),
+                &TestKey::new(1).agent(),
+                None,
+                &Some(membrane_proof(joining_proof)),
+            )
+            .unwrap(),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn membrane_proof_naming_a_keyset_root_is_refused() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let keyset_root = keyset
+            .keyset_root
+            .entry()
+            .to_app_option::<KeysetRoot>()
+            .unwrap()
+            .unwrap();
+        let joining_proof = JoiningProof::new(
+            SourceOfAuthority::KeysetRoot(keyset_root),
+            MembraneProof::None,
+        );
+        assert_eq!(
+            validate_joining_proof(
+                &hdi::prelude::dna_info().unwrap(),
+                &TestKey::new(1).agent(),
+                None,
+                &Some(membrane_proof(joining_proof)),
+            )
+            .unwrap(),
+            ValidateCallbackResult::Invalid(Error::MembraneProofNamesKeysetRoot.to_string())
+        );
+    }
+
+    #[test]
+    fn committed_joining_proof_names_its_source_of_authority() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let agent = TestKey::new(1);
+        network.genesis(&agent, None);
+        let joining_proof = network.create(
+            &agent,
+            EntryTypes::JoiningProof(JoiningProof {
+                source_of_authority: None,
+                membrane_proof: MembraneProof::None,
+                recovering: false,
+            }),
+        );
+        assert_eq!(
+            network.validate(&joining_proof).unwrap(),
+            ValidateCallbackResult::Invalid(
+                Error::JoiningProofWithoutSourceOfAuthority.to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use hdi::prelude::*;
+
+use crate::{error::Error, KeyGeneration, KeyRegistration, KeyRevocation, UnitEntryTypes};
+
+pub const KEY_ANCHOR_BYTES: usize = 32;
+
+// The core 32 bytes of a registered key, stripped of the 3 byte multihash prefix
+// and 4 byte DHT location suffix.
+// The same key always hashes to the same KeyAnchor entry, so the anchor is the
+// deterministic place to look up whether a key is registered, replaced or revoked.
+// A KeyAnchor is always written immediately after its KeyRegistration, as
+// `validate_key_registration_successor` requires.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct KeyAnchor {
+    pub bytes: [u8; KEY_ANCHOR_BYTES],
+}
+
+impl KeyAnchor {
+    pub fn new(bytes: [u8; KEY_ANCHOR_BYTES]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl From<&AgentPubKey> for KeyAnchor {
+    fn from(key: &AgentPubKey) -> Self {
+        let mut bytes = [0; KEY_ANCHOR_BYTES];
+        bytes.copy_from_slice(key.get_raw_32());
+        Self { bytes }
+    }
+}
+
+impl From<&KeyGeneration> for KeyAnchor {
+    fn from(key_generation: &KeyGeneration) -> Self {
+        Self::from(&key_generation.new_key)
+    }
+}
+
+// The prior state a KeyGeneration cites must be an update or delete of a KeyAnchor of
+// the same key, wherever it was committed.
+fn validate_key_anchor_released(
+    prior_key_anchor: ActionHash,
+    key_anchor_hash: &EntryHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let released = match must_get_valid_record(prior_key_anchor)?.action() {
+        Action::Update(update) => update.original_action_address.clone(),
+        Action::Delete(delete) => delete.deletes_address.clone(),
+        _ => return Error::PriorKeyAnchorNotReleased.into(),
+    };
+    match must_get_valid_record(released)?.action().entry_hash() {
+        Some(entry_hash) if entry_hash == key_anchor_hash => Ok(ValidateCallbackResult::Valid),
+        _ => Error::PriorKeyAnchorNotReleased.into(),
+    }
+}
+
+// Whether the KeyRegistration committed as `entry_hash` registers `key`, new or as a
+// replacement.
+fn registers_key(entry_hash: &EntryHash, key: &AgentPubKey) -> ExternResult<bool> {
+    Ok(
+        match KeyRegistration::try_from(must_get_entry(entry_hash.clone())?)? {
+            KeyRegistration::Create(key_generation)
+            | KeyRegistration::CreateOnly(key_generation)
+            | KeyRegistration::Update(_, key_generation) => key_generation.new_key == *key,
+            KeyRegistration::Delete(_) => false,
+        },
+    )
+}
+
+/// Refuses the registration of a key unless its KeyGeneration cites the true prior state
+/// of the key's KeyAnchor: the update or delete that released it from a registration on
+/// any chain, checked against that record, or `None` for a key never registered. The new
+/// key signs that state, so a registration cannot be replayed against another one.
+///
+/// Whether a key is live on another chain cannot be decided deterministically, so a
+/// claim of `None` is checked against the author's own chain, where the key must not
+/// already be live, i.e. registered or anchored and neither replaced nor revoked since.
+/// `register_key` refuses a key that it finds live on any chain before anything is
+/// committed.
+pub fn validate_key_not_registered(
+    author: AgentPubKey,
+    chain_top: ActionHash,
+    key_generation: &KeyGeneration,
+) -> ExternResult<ValidateCallbackResult> {
+    let key_anchor_hash = hash_entry(KeyAnchor::from(key_generation))?;
+    if let Some(prior_key_anchor) = key_generation.prior_key_anchor.clone() {
+        let result = validate_key_anchor_released(prior_key_anchor, &key_anchor_hash)?;
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+    }
+    let key_registration_def = AppEntryDef::try_from(UnitEntryTypes::KeyRegistration)?;
+    let activities = must_get_agent_activity(author, ChainFilter::new(chain_top))?;
+
+    // KeyRegistrations of the key, and KeyAnchors of it with the action before them.
+    let mut key_registrations: Vec<ActionHash> = Vec::new();
+    let mut anchors: Vec<(ActionHash, ActionHash)> = Vec::new();
+    let mut invalidated: HashSet<ActionHash> = HashSet::new();
+    for activity in activities.into_iter() {
+        let action_hash = activity.action.as_hash().clone();
+        let action = activity.action.action();
+        if let Some((entry_hash, EntryType::App(app_entry_def))) = action.entry_data() {
+            if *app_entry_def == key_registration_def
+                && registers_key(entry_hash, &key_generation.new_key)?
+            {
+                key_registrations.push(action_hash.clone());
+            }
+        }
+        match action {
+            Action::Create(create) if create.entry_hash == key_anchor_hash => {
+                anchors.push((action_hash, create.prev_action.clone()));
+            }
+            Action::Update(update) => {
+                if update.entry_hash == key_anchor_hash {
+                    anchors.push((action_hash, update.prev_action.clone()));
+                }
+                invalidated.insert(update.original_action_address.clone());
+            }
+            Action::Delete(delete) => {
+                invalidated.insert(delete.deletes_address.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // A KeyRegistration is live until it is updated by a replacement or revocation. The
+    // action preceding a KeyAnchor is always its KeyRegistration.
+    let live_key_registration = key_registrations
+        .into_iter()
+        .find(|key_registration_hash| !invalidated.contains(key_registration_hash))
+        .or_else(|| {
+            anchors
+                .into_iter()
+                .find(|(anchor_hash, _)| !invalidated.contains(anchor_hash))
+                .map(|(_, key_registration_hash)| key_registration_hash)
+        });
+    match live_key_registration {
+        Some(key_registration_hash) => Error::KeyAlreadyRegistered(key_registration_hash).into(),
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// The revoked KeyAnchor must match the key generated by the revoked registration.
+fn validate_revoked_key_anchor(
+    revoked_key_anchor: &KeyAnchor,
+    key_revocation: &KeyRevocation,
+) -> ExternResult<ValidateCallbackResult> {
+    let revoked_record = must_get_valid_record(key_revocation.prior_key_registration.clone())?;
+    match KeyRegistration::try_from(revoked_record) {
+        Ok(KeyRegistration::Create(key_generation))
+        | Ok(KeyRegistration::Update(_, key_generation)) => {
+            if KeyAnchor::from(&key_generation) == *revoked_key_anchor {
+                Ok(ValidateCallbackResult::Valid)
+            } else {
+                Error::RegistrationWrongKey.into()
+            }
+        }
+        _ => Error::RegistrationWrongOp.into(),
+    }
+}
+
+fn get_preceding_key_registration(
+    action_hash: ActionHash,
+) -> ExternResult<Option<KeyRegistration>> {
+    let record = must_get_valid_record(action_hash)?;
+    Ok(KeyRegistration::try_from(record).ok())
+}
+
+pub fn validate_create_key_anchor(
+    action: EntryCreationAction,
+    key_anchor: KeyAnchor,
+) -> ExternResult<ValidateCallbackResult> {
+    let key_registration = match get_preceding_key_registration(action.prev_action().clone())? {
+        Some(key_registration) => key_registration,
+        None => return Error::RegistrationNone.into(),
+    };
+    let key_generation = match (&action, &key_registration) {
+        (
+            EntryCreationAction::Create(_),
+            KeyRegistration::Create(key_generation) | KeyRegistration::CreateOnly(key_generation),
+        ) => key_generation,
+        (EntryCreationAction::Update(_), KeyRegistration::Update(_, key_generation)) => {
+            key_generation
+        }
+        _ => return Error::RegistrationWrongOp.into(),
+    };
+    if KeyAnchor::from(key_generation) != key_anchor {
+        return Error::RegistrationWrongKey.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_key_anchor(
+    action: Update,
+    _key_anchor: KeyAnchor,
+    _original_action: EntryCreationAction,
+    original_key_anchor: KeyAnchor,
+) -> ExternResult<ValidateCallbackResult> {
+    match get_preceding_key_registration(action.prev_action)? {
+        Some(KeyRegistration::Update(key_revocation, _)) => {
+            validate_revoked_key_anchor(&original_key_anchor, &key_revocation)
+        }
+        Some(_) => Error::RegistrationWrongOp.into(),
+        None => Error::RegistrationNone.into(),
+    }
+}
+pub fn validate_delete_key_anchor(
+    action: Delete,
+    _original_action: EntryCreationAction,
+    original_key_anchor: KeyAnchor,
+) -> ExternResult<ValidateCallbackResult> {
+    match get_preceding_key_registration(action.prev_action)? {
+        Some(KeyRegistration::Delete(key_revocation)) => {
+            validate_revoked_key_anchor(&original_key_anchor, &key_revocation)
+        }
+        Some(_) => Error::RegistrationWrongOp.into(),
+        None => Error::RegistrationNone.into(),
+    }
+}
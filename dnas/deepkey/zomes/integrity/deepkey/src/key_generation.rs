@@ -1,18 +1,63 @@
 use hdi::prelude::*;
-#[hdk_entry_helper]
-#[derive(Clone, PartialEq)]
-pub struct KeyGeneration {
-    pub new_key: AgentPubKey,
-    pub new_key_signing_of_author: Signature,
-    // TODO
-    // generator: ActionHash, // This is the key authorized to generate new keys on this chain
-    // generator_signature: Signature, // The generator key signing the new key
+
+use crate::{
+    error::Error, get_keyset_root_and_leaf, validate_keyset_not_frozen, KeyGeneration,
+    KeyGenerationPayload, SigningPayload,
+};
+
+/// How long a proof of possession stays usable after the new key signed it.
+pub const KEY_GENERATION_SIGNATURE_MAX_AGE: std::time::Duration =
+    std::time::Duration::from_secs(60 * 60);
+
+/// The new key must have signed the author, the keyset leaf their chain is under and a
+/// timestamp shortly before the action, and the keyset must not be frozen.
+pub fn validate_key_generation(
+    action: &EntryCreationAction,
+    key_generation: &KeyGeneration,
+) -> ExternResult<ValidateCallbackResult> {
+    if key_generation.timestamp > *action.timestamp() {
+        return Error::KeyGenerationFromFuture.into();
+    }
+    if key_generation.timestamp
+        < action
+            .timestamp()
+            .saturating_sub(&KEY_GENERATION_SIGNATURE_MAX_AGE)
+    {
+        return Error::KeyGenerationExpired.into();
+    }
+    let (keyset_root, keyset_leaf) =
+        get_keyset_root_and_leaf(action.author().clone(), action.prev_action().clone())?;
+    let result = validate_keyset_not_frozen(
+        action.author().clone(),
+        action.prev_action().clone(),
+        &keyset_root,
+        &key_generation.keyset_freezes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let payload = SigningPayload::from(KeyGenerationPayload {
+        keyset_root,
+        dna_hash: dna_info()?.hash,
+        keyset_leaf,
+        author: action.author().clone(),
+        timestamp: key_generation.timestamp,
+        prior_key_anchor: key_generation.prior_key_anchor.clone(),
+    });
+    if !verify_signature_raw(
+        key_generation.new_key.clone(),
+        key_generation.new_key_signing_of_author.clone(),
+        payload.to_signable_bytes()?,
+    )? {
+        return Error::BadKeyGenerationSignature.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
 }
 pub fn validate_create_key_generation(
-    _action: EntryCreationAction,
-    _key_generation: KeyGeneration,
+    action: EntryCreationAction,
+    key_generation: KeyGeneration,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    validate_key_generation(&action, &key_generation)
 }
 pub fn validate_update_key_generation(
     _action: Update,
@@ -1,28 +1,84 @@
 use hdi::prelude::*;
 
-use crate::{KeyGeneration, KeyRevocation};
+use crate::{
+    error::Error, validate_key_generation, validate_key_not_registered, validate_key_revocation,
+    KeyAnchor, KeyRegistration, UnitEntryTypes,
+};
 
-#[hdk_entry_helper]
-#[derive(Clone, PartialEq)]
-pub enum KeyRegistration {
-    Create(KeyGeneration), // Creates a key under management of current KSR on this chain
-    CreateOnly(KeyGeneration), // Keys for hosted web users may be of this type, cannot replace/revoke
-    Update(KeyRevocation, KeyGeneration), // revokes a key and replaces it with a newly generated one
-    Delete(KeyRevocation) // permanently revokes a key (Note: still uses an update action.)
-}
 pub fn validate_create_key_registration(
-    _action: EntryCreationAction,
-    _key_registration: KeyRegistration,
+    action: EntryCreationAction,
+    key_registration: KeyRegistration,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    match (&action, &key_registration) {
+        (
+            EntryCreationAction::Create(create),
+            KeyRegistration::Create(key_generation) | KeyRegistration::CreateOnly(key_generation),
+        ) => {
+            let result = validate_key_generation(&action, key_generation)?;
+            if let ValidateCallbackResult::Valid = result {
+                validate_key_not_registered(
+                    create.author.clone(),
+                    create.prev_action.clone(),
+                    key_generation,
+                )
+            } else {
+                Ok(result)
+            }
+        }
+        // The revocations in replacements and revocations are validated against the
+        // registration they update.
+        (EntryCreationAction::Update(_), KeyRegistration::Update(_, key_generation)) => {
+            validate_key_generation(&action, key_generation)
+        }
+        (EntryCreationAction::Update(_), KeyRegistration::Delete(_)) => {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Error::RegistrationWrongOp.into(),
+    }
 }
 pub fn validate_update_key_registration(
-    _action: Update,
-    _key_registration: KeyRegistration,
+    action: Update,
+    key_registration: KeyRegistration,
     _original_action: EntryCreationAction,
-    _original_key_registration: KeyRegistration,
+    original_key_registration: KeyRegistration,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    let key_revocation = match &key_registration {
+        KeyRegistration::Update(key_revocation, _) | KeyRegistration::Delete(key_revocation) => {
+            key_revocation
+        }
+        _ => return Error::RegistrationWrongOp.into(),
+    };
+    if key_revocation.prior_key_registration != action.original_action_address {
+        return Error::RevocationWrongRegistration.into();
+    }
+    // CreateOnly keys cannot be replaced or revoked, and revoked keys stay revoked.
+    match original_key_registration {
+        KeyRegistration::Create(_) | KeyRegistration::Update(..) => {}
+        _ => return Error::RegistrationWrongOp.into(),
+    }
+    match &key_registration {
+        KeyRegistration::Update(key_revocation, key_generation) => {
+            let result = validate_key_revocation(
+                action.author.clone(),
+                action.prev_action.clone(),
+                action.timestamp,
+                key_revocation,
+                Some(&key_generation.new_key),
+            )?;
+            if let ValidateCallbackResult::Valid = result {
+                validate_key_not_registered(action.author, action.prev_action, key_generation)
+            } else {
+                Ok(result)
+            }
+        }
+        _ => validate_key_revocation(
+            action.author,
+            action.prev_action,
+            action.timestamp,
+            key_revocation,
+            None,
+        ),
+    }
 }
 pub fn validate_delete_key_registration(
     _action: Delete,
@@ -31,3 +87,56 @@ pub fn validate_delete_key_registration(
 ) -> ExternResult<ValidateCallbackResult> {
     Ok(ValidateCallbackResult::Valid)
 }
+/// Whatever action follows a KeyRegistration must be the KeyAnchor create, update or delete
+/// it stands for, so a key is never registered without the anchor its revocation needs.
+pub fn validate_key_registration_successor(
+    action: &Action,
+) -> ExternResult<ValidateCallbackResult> {
+    let prev_action = match action.prev_action() {
+        Some(prev_action) => must_get_action(prev_action.clone())?,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+    let key_registration = match prev_action.action().entry_data() {
+        Some((entry_hash, EntryType::App(app_entry_def)))
+            if *app_entry_def == AppEntryDef::try_from(UnitEntryTypes::KeyRegistration)? =>
+        {
+            KeyRegistration::try_from(must_get_entry(entry_hash.clone())?)?
+        }
+        _ => return Ok(ValidateCallbackResult::Valid),
+    };
+    let key_anchor_def = AppEntryDef::try_from(UnitEntryTypes::KeyAnchor)?;
+    let followed_by_key_anchor = match (&key_registration, action) {
+        (
+            KeyRegistration::Create(key_generation) | KeyRegistration::CreateOnly(key_generation),
+            Action::Create(Create {
+                entry_type: EntryType::App(app_entry_def),
+                entry_hash,
+                ..
+            }),
+        )
+        | (
+            KeyRegistration::Update(_, key_generation),
+            Action::Update(Update {
+                entry_type: EntryType::App(app_entry_def),
+                entry_hash,
+                ..
+            }),
+        ) => {
+            *app_entry_def == key_anchor_def
+                && *entry_hash == hash_entry(KeyAnchor::from(key_generation))?
+        }
+        // Which KeyAnchor is deleted is checked when the delete is validated.
+        (KeyRegistration::Delete(_), Action::Delete(delete)) => {
+            must_get_action(delete.deletes_address.clone())?
+                .action()
+                .entry_type()
+                == Some(&EntryType::App(key_anchor_def))
+        }
+        _ => false,
+    };
+    if followed_by_key_anchor {
+        Ok(ValidateCallbackResult::Valid)
+    } else {
+        Error::KeyRegistrationNotFollowedByKeyAnchor.into()
+    }
+}
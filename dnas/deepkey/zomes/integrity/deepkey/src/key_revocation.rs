@@ -1,15 +1,104 @@
 use hdi::prelude::*;
-#[hdk_entry_helper]
-#[derive(Clone, PartialEq)]
-pub struct KeyRevocation {
-    pub prior_key_registration: ActionHash,
-    pub revocation_authorization: Vec<ActionHash>,
+
+use crate::{
+    authorize_change_rule, error::Error, get_keyset_root_and_leaf, validate_change_rule_in_force,
+    validate_keyset_not_frozen, AuthorityOperation, ChangeRule, KeyRevocation,
+    KeyRevocationPayload, RevocationReason, SigningPayload,
+};
+
+/// The revocation must be authorized by a ChangeRule of the keyset the revoked key was
+/// registered under, over the revoked registration and the replacement key, if any.
+/// That ChangeRule must be in force at `timestamp`, when the revocation is committed, and
+/// the keyset must not be frozen.
+/// Only a compromised key can carry a compromise time, which cannot be later than
+/// `timestamp`, and only a replaced key can be superseded.
+pub fn validate_key_revocation(
+    author: AgentPubKey,
+    chain_top: ActionHash,
+    timestamp: Timestamp,
+    key_revocation: &KeyRevocation,
+    new_key: Option<&AgentPubKey>,
+) -> ExternResult<ValidateCallbackResult> {
+    if let Some(compromised_since) = key_revocation.compromised_since {
+        if key_revocation.reason != RevocationReason::Compromised {
+            return Error::CompromiseTimeWithoutCompromise.into();
+        }
+        if compromised_since > timestamp {
+            return Error::CompromiseAfterRevocation.into();
+        }
+    }
+    if key_revocation.reason == RevocationReason::Superseded && new_key.is_none() {
+        return Error::SupersededWithoutReplacement.into();
+    }
+
+    let prior_action = must_get_action(key_revocation.prior_key_registration.clone())?;
+    let (keyset_root, _) = get_keyset_root_and_leaf(
+        prior_action.action().author().clone(),
+        key_revocation.prior_key_registration.clone(),
+    )?;
+
+    let change_rule =
+        ChangeRule::try_from(must_get_valid_record(key_revocation.change_rule.clone())?)?;
+    if change_rule.keyset_root != keyset_root {
+        return Error::RevocationKeysetRootMismatch.into();
+    }
+    let result = validate_change_rule_in_force(
+        author.clone(),
+        chain_top.clone(),
+        timestamp,
+        &key_revocation.change_rule,
+        &change_rule,
+        &key_revocation.vetoes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let result = validate_keyset_not_frozen(
+        author,
+        chain_top,
+        &keyset_root,
+        &key_revocation.keyset_freezes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    let payload = SigningPayload::from(KeyRevocationPayload {
+        keyset_root,
+        dna_hash: dna_info()?.hash,
+        prior_key_registration: key_revocation.prior_key_registration.clone(),
+        change_rule: key_revocation.change_rule.clone(),
+        new_key: new_key.cloned(),
+        reason: key_revocation.reason,
+        compromised_since: key_revocation.compromised_since,
+    });
+    // Replacing a key and revoking it outright may need different signers.
+    let operation = match new_key {
+        Some(_) => AuthorityOperation::KeyReplacement,
+        None => AuthorityOperation::KeyRevocation,
+    };
+    match authorize_change_rule(
+        &change_rule,
+        operation,
+        &key_revocation.revocation_authorization,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(Error::BadUpdateSignature) => Error::BadRevocationSignature.into(),
+        Err(e) => e.into(),
+    }
 }
 pub fn validate_create_key_revocation(
-    _action: EntryCreationAction,
-    _key_revocation: KeyRevocation,
+    action: EntryCreationAction,
+    key_revocation: KeyRevocation,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    validate_key_revocation(
+        action.author().clone(),
+        action.prev_action().clone(),
+        *action.timestamp(),
+        &key_revocation,
+        None,
+    )
 }
 pub fn validate_update_key_revocation(
     _action: Update,
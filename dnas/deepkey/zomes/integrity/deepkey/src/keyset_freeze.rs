@@ -0,0 +1,366 @@
+use hdi::prelude::*;
+
+use crate::{
+    authorize_change_rule, error::Error, get_keyset_root_and_leaf, has_source_of_authority,
+    validate_change_rule_in_force, AuthorityOperation, Authorization, ChangeRule,
+    KeysetUnfreezePayload, SigningPayload, UnitEntryTypes,
+};
+
+// Committed by any device of a keyset that suspects a compromise. Until it is lifted, the
+// keyset accepts no new keys, invites, acceptances, revocations, vetoes or ChangeRule
+// updates. Like a veto, it only binds chains that commit it and entries that cite it.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct KeysetFreeze {
+    pub keyset_root: ActionHash,
+}
+
+// Lifts a KeysetFreeze with the approval of the keyset's ChangeRule signers.
+// The approval names the freeze, not the device, so the same signatures can lift it on
+// the chain of every device that learns of it.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct KeysetUnfreeze {
+    pub keyset_root: ActionHash,
+    pub keyset_freeze: ActionHash,
+    // The ChangeRule of the keyset whose signers authorize lifting the freeze.
+    pub change_rule: ActionHash,
+    // Signatures of a KeysetUnfreezePayload by the ChangeRule's authorized signers.
+    pub authorization: Vec<Authorization>,
+    // ChangeRuleVetoes of `change_rule` or of its updates.
+    #[serde(default)]
+    pub vetoes: Vec<ActionHash>,
+}
+
+/// The KeysetFreezes of `keyset_root` that no KeysetUnfreeze has lifted, among those an
+/// acting entry cites in `keyset_freezes` and those committed on an agent's chain as of
+/// `chain_top`. Cited KeysetUnfreezes lift freezes just as those on the chain do.
+pub fn get_unlifted_keyset_freezes(
+    agent: AgentPubKey,
+    chain_top: ActionHash,
+    keyset_root: &ActionHash,
+    keyset_freezes: &[ActionHash],
+) -> ExternResult<Vec<ActionHash>> {
+    let keyset_freeze_def = AppEntryDef::try_from(UnitEntryTypes::KeysetFreeze)?;
+    let keyset_unfreeze_def = AppEntryDef::try_from(UnitEntryTypes::KeysetUnfreeze)?;
+    let mut freezes = Vec::new();
+    let mut lifted = Vec::new();
+    for keyset_freeze_hash in keyset_freezes {
+        let record = must_get_valid_record(keyset_freeze_hash.clone())?;
+        match record.action().entry_type() {
+            Some(EntryType::App(app_entry_def)) if *app_entry_def == keyset_freeze_def => {
+                if KeysetFreeze::try_from(record)?.keyset_root == *keyset_root {
+                    freezes.push(keyset_freeze_hash.clone());
+                }
+            }
+            Some(EntryType::App(app_entry_def)) if *app_entry_def == keyset_unfreeze_def => {
+                lifted.push(KeysetUnfreeze::try_from(record)?.keyset_freeze);
+            }
+            _ => {
+                return Err(wasm_error!(WasmErrorInner::Guest(String::from(
+                    "Cited action does not commit a KeysetFreeze or KeysetUnfreeze"
+                ))))
+            }
+        }
+    }
+    for activity in must_get_agent_activity(agent, ChainFilter::new(chain_top))? {
+        if let Some((entry_hash, EntryType::App(app_entry_def))) =
+            activity.action.action().entry_data()
+        {
+            if *app_entry_def == keyset_freeze_def {
+                let keyset_freeze = KeysetFreeze::try_from(must_get_entry(entry_hash.clone())?)?;
+                if keyset_freeze.keyset_root == *keyset_root {
+                    freezes.push(activity.action.as_hash().clone());
+                }
+            } else if *app_entry_def == keyset_unfreeze_def {
+                let keyset_unfreeze =
+                    KeysetUnfreeze::try_from(must_get_entry(entry_hash.clone())?)?;
+                lifted.push(keyset_unfreeze.keyset_freeze);
+            }
+        }
+    }
+    freezes.retain(|keyset_freeze| !lifted.contains(keyset_freeze));
+    Ok(freezes)
+}
+
+/// Refuses anything for `keyset_root` while a KeysetFreeze of it, cited by the acting entry
+/// or on the author's chain, has not been lifted.
+///
+/// Freezes committed on other chains can only be seen deterministically if the acting
+/// entry cites them; the coordinator cites every freeze of the keyset and every unfreeze
+/// lifting one, and also refuses to commit while any of them is unlifted on the DHT.
+pub fn validate_keyset_not_frozen(
+    author: AgentPubKey,
+    chain_top: ActionHash,
+    keyset_root: &ActionHash,
+    keyset_freezes: &[ActionHash],
+) -> ExternResult<ValidateCallbackResult> {
+    if get_unlifted_keyset_freezes(author, chain_top, keyset_root, keyset_freezes)?.is_empty() {
+        Ok(ValidateCallbackResult::Valid)
+    } else {
+        Error::KeysetFrozen.into()
+    }
+}
+
+/// The keyset leaf the author's chain is under must belong to `keyset_root` and be valid,
+/// as for an inviter, so an acceptance of a forged invite does not make a device.
+fn validate_author_in_keyset(
+    action: &EntryCreationAction,
+    keyset_root: &ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if !has_source_of_authority(action.author(), action.prev_action())? {
+        return Error::FreezeAuthorNotInKeyset.into();
+    }
+    let (author_keyset_root, keyset_leaf) =
+        get_keyset_root_and_leaf(action.author().clone(), action.prev_action().clone())?;
+    if author_keyset_root != *keyset_root {
+        return Error::FreezeAuthorNotInKeyset.into();
+    }
+    must_get_valid_record(keyset_leaf)?;
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Any single device of the keyset can freeze it.
+pub fn validate_create_keyset_freeze(
+    action: EntryCreationAction,
+    keyset_freeze: KeysetFreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    validate_author_in_keyset(&action, &keyset_freeze.keyset_root)
+}
+pub fn validate_update_keyset_freeze(
+    _action: Update,
+    _keyset_freeze: KeysetFreeze,
+    _original_action: EntryCreationAction,
+    _original_keyset_freeze: KeysetFreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Keyset Freezes cannot be updated",
+    )))
+}
+pub fn validate_delete_keyset_freeze(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_keyset_freeze: KeysetFreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Keyset Freezes cannot be deleted; commit a KeysetUnfreeze",
+    )))
+}
+
+/// Lifting a freeze must be authorized by the keyset's ChangeRule in force, as for a
+/// ChangeRule update, and committed by a device of the keyset.
+pub fn validate_create_keyset_unfreeze(
+    action: EntryCreationAction,
+    keyset_unfreeze: KeysetUnfreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    let result = validate_author_in_keyset(&action, &keyset_unfreeze.keyset_root)?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+    let keyset_freeze = KeysetFreeze::try_from(must_get_valid_record(
+        keyset_unfreeze.keyset_freeze.clone(),
+    )?)?;
+    if keyset_freeze.keyset_root != keyset_unfreeze.keyset_root {
+        return Error::UnfreezeKeysetRootMismatch.into();
+    }
+    let change_rule =
+        ChangeRule::try_from(must_get_valid_record(keyset_unfreeze.change_rule.clone())?)?;
+    if change_rule.keyset_root != keyset_unfreeze.keyset_root {
+        return Error::UnfreezeKeysetRootMismatch.into();
+    }
+    let result = validate_change_rule_in_force(
+        action.author().clone(),
+        action.prev_action().clone(),
+        *action.timestamp(),
+        &keyset_unfreeze.change_rule,
+        &change_rule,
+        &keyset_unfreeze.vetoes,
+    )?;
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    let payload = SigningPayload::from(KeysetUnfreezePayload {
+        keyset_root: keyset_unfreeze.keyset_root,
+        dna_hash: dna_info()?.hash,
+        keyset_freeze: keyset_unfreeze.keyset_freeze,
+        change_rule: keyset_unfreeze.change_rule,
+    });
+    match authorize_change_rule(
+        &change_rule,
+        AuthorityOperation::ChangeRuleUpdate,
+        &keyset_unfreeze.authorization,
+        &payload,
+    ) {
+        Ok(()) => Ok(ValidateCallbackResult::Valid),
+        Err(Error::BadUpdateSignature) => Error::BadUnfreezeSignature.into(),
+        Err(e) => e.into(),
+    }
+}
+pub fn validate_update_keyset_unfreeze(
+    _action: Update,
+    _keyset_unfreeze: KeysetUnfreeze,
+    _original_action: EntryCreationAction,
+    _original_keyset_unfreeze: KeysetUnfreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Keyset Unfreezes cannot be updated",
+    )))
+}
+pub fn validate_delete_keyset_unfreeze(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_keyset_unfreeze: KeysetUnfreeze,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Keyset Unfreezes cannot be deleted",
+    )))
+}
+pub fn validate_create_link_keyset_root_to_keyset_freezes(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let keyset_freeze: crate::KeysetFreeze = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(keyset_freeze.keyset_root) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A KeysetFreeze must be linked from the KeysetRoot it freezes",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_keyset_root_to_keyset_freezes(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "KeysetRootToKeysetFreezes links cannot be deleted",
+    )))
+}
+pub fn validate_create_link_keyset_freeze_to_keyset_unfreezes(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let keyset_unfreeze: crate::KeysetUnfreeze = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(keyset_unfreeze.keyset_freeze) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A KeysetUnfreeze must be linked from the KeysetFreeze it lifts",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_keyset_freeze_to_keyset_unfreezes(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "KeysetFreezeToKeysetUnfreezes links cannot be deleted",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{TestKey, TestNetwork};
+    use crate::{DeepkeyProperties, EntryTypes};
+
+    fn freeze(network: &TestNetwork, author: &TestKey, keyset_root: &Record) -> Record {
+        network.create(
+            author,
+            EntryTypes::KeysetFreeze(KeysetFreeze {
+                keyset_root: keyset_root.action_address().clone(),
+            }),
+        )
+    }
+
+    #[test]
+    fn devices_of_the_keyset_can_freeze_it() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let device = TestKey::new(1);
+        network.genesis(&device, None);
+        let device_invite = network.invite(
+            &keyset.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &device,
+        );
+        network.accept(&device, &device_invite);
+        for author in [&keyset.fda, &device] {
+            assert_eq!(
+                network
+                    .validate(&freeze(&network, author, &keyset.keyset_root))
+                    .unwrap(),
+                ValidateCallbackResult::Valid
+            );
+        }
+    }
+
+    #[test]
+    fn outsider_cannot_freeze_a_keyset() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let outsider = network.keyset(20);
+        assert_eq!(
+            network
+                .validate(&freeze(&network, &outsider.fda, &keyset.keyset_root))
+                .unwrap(),
+            Error::FreezeAuthorNotInKeyset.into()
+        );
+        let newcomer = TestKey::new(1);
+        network.genesis(&newcomer, None);
+        assert_eq!(
+            network
+                .validate(&freeze(&network, &newcomer, &keyset.keyset_root))
+                .unwrap(),
+            Error::FreezeAuthorNotInKeyset.into()
+        );
+    }
+
+    #[test]
+    fn self_invited_outsider_cannot_freeze_a_keyset() {
+        let network = TestNetwork::install(&DeepkeyProperties::default());
+        let keyset = network.keyset(10);
+        let outsider = network.keyset(20);
+        let invitee = TestKey::new(1);
+        network.genesis(&invitee, None);
+        let device_invite = network.invite(
+            &outsider.fda,
+            &keyset,
+            keyset.keyset_root.action_address(),
+            &invitee,
+        );
+        network.accept(&invitee, &device_invite);
+        let error = network
+            .validate(&freeze(&network, &invitee, &keyset.keyset_root))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&Error::InviteNotByParentDevice.to_string()));
+    }
+}
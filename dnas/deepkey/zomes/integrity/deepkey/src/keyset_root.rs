@@ -1,40 +1,61 @@
 use hdi::prelude::*;
 
+use crate::{
+    error::Error, validate_follows_joining_proof, KeysetRoot, KeysetRootPayload, SigningPayload,
+    SourceOfAuthority, UnitEntryTypes,
+};
+
 pub const KEYSET_ROOT_INDEX: u32 = POST_GENESIS_SEQ_THRESHOLD + 1;
 
-#[hdk_entry_helper]
-#[derive(Clone)]
-pub struct KeysetRoot {
-    pub first_deepkey_agent: AgentPubKey,
-    /// The private key is thrown away.
-    root_pub_key: AgentPubKey,
-    fda_pubkey_signed_by_root_key: Signature,
-}
-impl KeysetRoot {
-    pub fn new(
-        first_deepkey_agent: AgentPubKey,
-        root_pub_key: AgentPubKey,
-        fda_pubkey_signed_by_root_key: Signature,
-    ) -> Self {
-        Self {
-            first_deepkey_agent,
-            root_pub_key,
-            fda_pubkey_signed_by_root_key,
-        }
-    }
+/// Whether the chain up to `chain_top` has a KeysetRoot or DeviceInviteAcceptance.
+pub fn has_source_of_authority(author: &AgentPubKey, chain_top: &ActionHash) -> ExternResult<bool> {
+    let keyset_root_def = AppEntryDef::try_from(UnitEntryTypes::KeysetRoot)?;
+    let device_invite_acceptance_def =
+        AppEntryDef::try_from(UnitEntryTypes::DeviceInviteAcceptance)?;
+    Ok(
+        must_get_agent_activity(author.clone(), ChainFilter::new(chain_top.clone()))?
+            .into_iter()
+            .any(|activity| match activity.action.action().entry_type() {
+                Some(EntryType::App(app_entry_def)) => {
+                    *app_entry_def == keyset_root_def
+                        || *app_entry_def == device_invite_acceptance_def
+                }
+                _ => false,
+            }),
+    )
 }
 
 pub fn validate_create_keyset_root(
-    _action: EntryCreationAction,
-    _keyset_root: KeysetRoot,
+    action: EntryCreationAction,
+    keyset_root: KeysetRoot,
 ) -> ExternResult<ValidateCallbackResult> {
+    if *action.author() != keyset_root.first_deepkey_agent {
+        return Error::KeysetRootAuthorNotFda.into();
+    }
+    let payload = SigningPayload::from(KeysetRootPayload {
+        dna_hash: dna_info()?.hash,
+        first_deepkey_agent: keyset_root.first_deepkey_agent.clone(),
+    });
+    if !verify_signature_raw(
+        keyset_root.root_pub_key().clone(),
+        keyset_root.fda_pubkey_signed_by_root_key().clone(),
+        payload.to_signable_bytes()?,
+    )? {
+        return Error::BadKeysetRootSignature.into();
+    }
+    // A chain has a single source of authority to start from; it may only move to another
+    // keyset later, through a DeviceInviteAcceptance.
+    if has_source_of_authority(action.author(), action.prev_action())? {
+        return Error::SourceOfAuthorityExists.into();
+    }
+
     // if *action.action_seq() != KEYSET_ROOT_INDEX {
     //     return Ok(ValidateCallbackResult::Invalid(
     //         "KeysetRoot must be the 4th entry on `FirstDeepkeyAgent`'s chain.".to_string(),
     //     ));
     // }
 
-    Ok(ValidateCallbackResult::Valid)
+    validate_follows_joining_proof(&action, &SourceOfAuthority::KeysetRoot(keyset_root))
 }
 pub fn validate_update_keyset_root(
     _action: Update,
@@ -1,5 +1,9 @@
 pub mod joining_proof;
 pub use joining_proof::*;
+// Named explicitly, as the glob above would be ambiguous with hdi's `MembraneProof` alias.
+pub use joining_proof::MembraneProof;
+pub mod key_anchor;
+pub use key_anchor::*;
 pub mod key_registration;
 pub use key_registration::*;
 pub mod key_revocation;
@@ -12,15 +16,30 @@ pub mod device_invite;
 pub use device_invite::*;
 pub mod change_rule;
 pub use change_rule::*;
+pub mod change_rule_veto;
+pub use change_rule_veto::*;
+pub mod keyset_freeze;
+pub use keyset_freeze::*;
+pub mod guardian_acceptance;
+pub use guardian_acceptance::*;
+pub mod proposal;
+pub use proposal::*;
+pub mod recovery_share;
+pub use recovery_share::*;
 pub mod authorized_spec_change;
 pub use authorized_spec_change::*;
 pub mod authority_spec;
 pub use authority_spec::*;
 pub use keyset_root::*;
 pub mod error;
+#[cfg(test)]
+mod fixtures;
 pub mod keyset_root;
 pub use error::*;
+pub mod properties;
+pub use properties::*;
 pub mod source_of_authority;
+pub use deepkey_types::*;
 use hdi::prelude::*;
 pub use source_of_authority::*;
 #[derive(Serialize, Deserialize)]
@@ -34,10 +53,19 @@ pub enum EntryTypes {
     ChangeRule(ChangeRule),
     DeviceInvite(DeviceInvite),
     DeviceInviteAcceptance(DeviceInviteAcceptance),
-    // JoiningProof(JoiningProof),
+    JoiningProof(JoiningProof),
+    ChangeRuleVeto(ChangeRuleVeto),
+    KeysetFreeze(KeysetFreeze),
+    KeysetUnfreeze(KeysetUnfreeze),
+    GuardianAcceptance(GuardianAcceptance),
+    Proposal(Proposal),
+    ProposalSignature(ProposalSignature),
+    RecoveryEncryptionKey(RecoveryEncryptionKey),
+    RecoveryShare(RecoveryShare),
     KeyGeneration(KeyGeneration),
     KeyRevocation(KeyRevocation),
     KeyRegistration(KeyRegistration),
+    KeyAnchor(KeyAnchor),
 }
 #[derive(Serialize, Deserialize)]
 #[hdk_link_types]
@@ -47,19 +75,49 @@ pub enum LinkTypes {
     KeysetRootToDeviceInvites,
     InviteeToDeviceInvites,
     DeviceInviteToDeviceInviteAcceptances,
+    ChangeRuleUpdateToVetoes,
+    KeysetRootToKeysetFreezes,
+    KeysetFreezeToKeysetUnfreezes,
+    AuthoritySpecToGuardianAcceptances,
+    SignerToProposals,
+    ProposalToSignatures,
+    AuthoritySpecToChangeRules,
+    AgentToRecoveryEncryptionKeys,
+    RecipientToRecoveryShares,
 }
 #[hdk_extern]
-pub fn genesis_self_check(_data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+pub fn genesis_self_check(data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    validate_joining_proof(&data.dna_info, &data.agent_key, None, &data.membrane_proof)
 }
 pub fn validate_agent_joining(
-    _agent_pub_key: AgentPubKey,
-    _membrane_proof: &Option<MembraneProof>,
+    agent_pub_key: AgentPubKey,
+    joined_at: Timestamp,
+    membrane_proof: &Option<hdi::prelude::MembraneProof>,
 ) -> ExternResult<ValidateCallbackResult> {
-    Ok(ValidateCallbackResult::Valid)
+    match validate_joining_proof(
+        &dna_info()?,
+        &agent_pub_key,
+        Some(joined_at),
+        membrane_proof,
+    )? {
+        ValidateCallbackResult::Valid => {
+            validate_joining_source_of_authority(&agent_pub_key, membrane_proof)
+        }
+        result => Ok(result),
+    }
 }
 #[hdk_extern]
 pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    if let Op::RegisterAgentActivity(RegisterAgentActivity { action, .. }) = &op {
+        let result = validate_joining_proof_successor(action.action())?;
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+        let result = validate_key_registration_successor(action.action())?;
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+    }
     match op.to_type::<EntryTypes, LinkTypes>()? {
         OpType::StoreEntry(store_entry) => match store_entry {
             OpEntry::CreateEntry { app_entry, action } => match app_entry {
@@ -101,6 +159,50 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     EntryCreationAction::Create(action),
                     key_registration,
                 ),
+                EntryTypes::KeyAnchor(key_anchor) => {
+                    validate_create_key_anchor(EntryCreationAction::Create(action), key_anchor)
+                }
+                EntryTypes::JoiningProof(joining_proof) => validate_create_joining_proof(
+                    EntryCreationAction::Create(action),
+                    joining_proof,
+                ),
+                EntryTypes::ChangeRuleVeto(change_rule_veto) => validate_create_change_rule_veto(
+                    EntryCreationAction::Create(action),
+                    change_rule_veto,
+                ),
+                EntryTypes::KeysetFreeze(keyset_freeze) => validate_create_keyset_freeze(
+                    EntryCreationAction::Create(action),
+                    keyset_freeze,
+                ),
+                EntryTypes::KeysetUnfreeze(keyset_unfreeze) => validate_create_keyset_unfreeze(
+                    EntryCreationAction::Create(action),
+                    keyset_unfreeze,
+                ),
+                EntryTypes::GuardianAcceptance(guardian_acceptance) => {
+                    validate_create_guardian_acceptance(
+                        EntryCreationAction::Create(action),
+                        guardian_acceptance,
+                    )
+                }
+                EntryTypes::Proposal(proposal) => {
+                    validate_create_proposal(EntryCreationAction::Create(action), proposal)
+                }
+                EntryTypes::ProposalSignature(proposal_signature) => {
+                    validate_create_proposal_signature(
+                        EntryCreationAction::Create(action),
+                        proposal_signature,
+                    )
+                }
+                EntryTypes::RecoveryEncryptionKey(recovery_encryption_key) => {
+                    validate_create_recovery_encryption_key(
+                        EntryCreationAction::Create(action),
+                        recovery_encryption_key,
+                    )
+                }
+                EntryTypes::RecoveryShare(recovery_share) => validate_create_recovery_share(
+                    EntryCreationAction::Create(action),
+                    recovery_share,
+                ),
             },
             OpEntry::UpdateEntry {
                 app_entry, action, ..
@@ -143,6 +245,50 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     EntryCreationAction::Update(action),
                     key_registration,
                 ),
+                EntryTypes::KeyAnchor(key_anchor) => {
+                    validate_create_key_anchor(EntryCreationAction::Update(action), key_anchor)
+                }
+                EntryTypes::JoiningProof(joining_proof) => validate_create_joining_proof(
+                    EntryCreationAction::Update(action),
+                    joining_proof,
+                ),
+                EntryTypes::ChangeRuleVeto(change_rule_veto) => validate_create_change_rule_veto(
+                    EntryCreationAction::Update(action),
+                    change_rule_veto,
+                ),
+                EntryTypes::KeysetFreeze(keyset_freeze) => validate_create_keyset_freeze(
+                    EntryCreationAction::Update(action),
+                    keyset_freeze,
+                ),
+                EntryTypes::KeysetUnfreeze(keyset_unfreeze) => validate_create_keyset_unfreeze(
+                    EntryCreationAction::Update(action),
+                    keyset_unfreeze,
+                ),
+                EntryTypes::GuardianAcceptance(guardian_acceptance) => {
+                    validate_create_guardian_acceptance(
+                        EntryCreationAction::Update(action),
+                        guardian_acceptance,
+                    )
+                }
+                EntryTypes::Proposal(proposal) => {
+                    validate_create_proposal(EntryCreationAction::Update(action), proposal)
+                }
+                EntryTypes::ProposalSignature(proposal_signature) => {
+                    validate_create_proposal_signature(
+                        EntryCreationAction::Update(action),
+                        proposal_signature,
+                    )
+                }
+                EntryTypes::RecoveryEncryptionKey(recovery_encryption_key) => {
+                    validate_create_recovery_encryption_key(
+                        EntryCreationAction::Update(action),
+                        recovery_encryption_key,
+                    )
+                }
+                EntryTypes::RecoveryShare(recovery_share) => validate_create_recovery_share(
+                    EntryCreationAction::Update(action),
+                    recovery_share,
+                ),
             },
             _ => Ok(ValidateCallbackResult::Valid),
         },
@@ -234,6 +380,89 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     original_action,
                     original_keyset_root,
                 ),
+                (EntryTypes::KeyAnchor(key_anchor), EntryTypes::KeyAnchor(original_key_anchor)) => {
+                    validate_update_key_anchor(
+                        action,
+                        key_anchor,
+                        original_action,
+                        original_key_anchor,
+                    )
+                }
+                (
+                    EntryTypes::JoiningProof(joining_proof),
+                    EntryTypes::JoiningProof(original_joining_proof),
+                ) => validate_update_joining_proof(
+                    action,
+                    joining_proof,
+                    original_action,
+                    original_joining_proof,
+                ),
+                (
+                    EntryTypes::ChangeRuleVeto(change_rule_veto),
+                    EntryTypes::ChangeRuleVeto(original_change_rule_veto),
+                ) => validate_update_change_rule_veto(
+                    action,
+                    change_rule_veto,
+                    original_action,
+                    original_change_rule_veto,
+                ),
+                (
+                    EntryTypes::KeysetFreeze(keyset_freeze),
+                    EntryTypes::KeysetFreeze(original_keyset_freeze),
+                ) => validate_update_keyset_freeze(
+                    action,
+                    keyset_freeze,
+                    original_action,
+                    original_keyset_freeze,
+                ),
+                (
+                    EntryTypes::KeysetUnfreeze(keyset_unfreeze),
+                    EntryTypes::KeysetUnfreeze(original_keyset_unfreeze),
+                ) => validate_update_keyset_unfreeze(
+                    action,
+                    keyset_unfreeze,
+                    original_action,
+                    original_keyset_unfreeze,
+                ),
+                (
+                    EntryTypes::GuardianAcceptance(guardian_acceptance),
+                    EntryTypes::GuardianAcceptance(original_guardian_acceptance),
+                ) => validate_update_guardian_acceptance(
+                    action,
+                    guardian_acceptance,
+                    original_action,
+                    original_guardian_acceptance,
+                ),
+                (EntryTypes::Proposal(proposal), EntryTypes::Proposal(original_proposal)) => {
+                    validate_update_proposal(action, proposal, original_action, original_proposal)
+                }
+                (
+                    EntryTypes::ProposalSignature(proposal_signature),
+                    EntryTypes::ProposalSignature(original_proposal_signature),
+                ) => validate_update_proposal_signature(
+                    action,
+                    proposal_signature,
+                    original_action,
+                    original_proposal_signature,
+                ),
+                (
+                    EntryTypes::RecoveryEncryptionKey(recovery_encryption_key),
+                    EntryTypes::RecoveryEncryptionKey(original_recovery_encryption_key),
+                ) => validate_update_recovery_encryption_key(
+                    action,
+                    recovery_encryption_key,
+                    original_action,
+                    original_recovery_encryption_key,
+                ),
+                (
+                    EntryTypes::RecoveryShare(recovery_share),
+                    EntryTypes::RecoveryShare(original_recovery_share),
+                ) => validate_update_recovery_share(
+                    action,
+                    recovery_share,
+                    original_action,
+                    original_recovery_share,
+                ),
                 _ => Ok(ValidateCallbackResult::Invalid(
                     "Original and updated entry types must be the same".to_string(),
                 )),
@@ -281,6 +510,44 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                 EntryTypes::KeyRegistration(key_registration) => {
                     validate_delete_key_registration(action, original_action, key_registration)
                 }
+                EntryTypes::KeyAnchor(key_anchor) => {
+                    validate_delete_key_anchor(action, original_action, key_anchor)
+                }
+                EntryTypes::JoiningProof(joining_proof) => {
+                    validate_delete_joining_proof(action, original_action, joining_proof)
+                }
+                EntryTypes::ChangeRuleVeto(change_rule_veto) => {
+                    validate_delete_change_rule_veto(action, original_action, change_rule_veto)
+                }
+                EntryTypes::KeysetFreeze(keyset_freeze) => {
+                    validate_delete_keyset_freeze(action, original_action, keyset_freeze)
+                }
+                EntryTypes::KeysetUnfreeze(keyset_unfreeze) => {
+                    validate_delete_keyset_unfreeze(action, original_action, keyset_unfreeze)
+                }
+                EntryTypes::GuardianAcceptance(guardian_acceptance) => {
+                    validate_delete_guardian_acceptance(
+                        action,
+                        original_action,
+                        guardian_acceptance,
+                    )
+                }
+                EntryTypes::Proposal(proposal) => {
+                    validate_delete_proposal(action, original_action, proposal)
+                }
+                EntryTypes::ProposalSignature(proposal_signature) => {
+                    validate_delete_proposal_signature(action, original_action, proposal_signature)
+                }
+                EntryTypes::RecoveryEncryptionKey(recovery_encryption_key) => {
+                    validate_delete_recovery_encryption_key(
+                        action,
+                        original_action,
+                        recovery_encryption_key,
+                    )
+                }
+                EntryTypes::RecoveryShare(recovery_share) => {
+                    validate_delete_recovery_share(action, original_action, recovery_share)
+                }
             },
             _ => Ok(ValidateCallbackResult::Valid),
         },
@@ -322,6 +589,71 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     tag,
                 )
             }
+            LinkTypes::ChangeRuleUpdateToVetoes => {
+                validate_create_link_change_rule_update_to_vetoes(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::KeysetRootToKeysetFreezes => {
+                validate_create_link_keyset_root_to_keyset_freezes(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::KeysetFreezeToKeysetUnfreezes => {
+                validate_create_link_keyset_freeze_to_keyset_unfreezes(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::AuthoritySpecToGuardianAcceptances => {
+                validate_create_link_authority_spec_to_guardian_acceptances(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::SignerToProposals => {
+                validate_create_link_signer_to_proposals(action, base_address, target_address, tag)
+            }
+            LinkTypes::ProposalToSignatures => validate_create_link_proposal_to_signatures(
+                action,
+                base_address,
+                target_address,
+                tag,
+            ),
+            LinkTypes::AgentToRecoveryEncryptionKeys => {
+                validate_create_link_agent_to_recovery_encryption_keys(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::RecipientToRecoveryShares => {
+                validate_create_link_recipient_to_recovery_shares(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::AuthoritySpecToChangeRules => {
+                validate_create_link_authority_spec_to_change_rules(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
         },
         OpType::RegisterDeleteLink {
             link_type,
@@ -370,6 +702,83 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     tag,
                 )
             }
+            LinkTypes::ChangeRuleUpdateToVetoes => {
+                validate_delete_link_change_rule_update_to_vetoes(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::KeysetRootToKeysetFreezes => {
+                validate_delete_link_keyset_root_to_keyset_freezes(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::KeysetFreezeToKeysetUnfreezes => {
+                validate_delete_link_keyset_freeze_to_keyset_unfreezes(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::AuthoritySpecToGuardianAcceptances => {
+                validate_delete_link_authority_spec_to_guardian_acceptances(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::SignerToProposals => validate_delete_link_signer_to_proposals(
+                action,
+                original_action,
+                base_address,
+                target_address,
+                tag,
+            ),
+            LinkTypes::ProposalToSignatures => validate_delete_link_proposal_to_signatures(
+                action,
+                original_action,
+                base_address,
+                target_address,
+                tag,
+            ),
+            LinkTypes::AgentToRecoveryEncryptionKeys => {
+                validate_delete_link_agent_to_recovery_encryption_keys(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::RecipientToRecoveryShares => {
+                validate_delete_link_recipient_to_recovery_shares(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
+            LinkTypes::AuthoritySpecToChangeRules => {
+                validate_delete_link_authority_spec_to_change_rules(
+                    action,
+                    original_action,
+                    base_address,
+                    target_address,
+                    tag,
+                )
+            }
         },
         OpType::StoreRecord(store_record) => match store_record {
             OpRecord::CreateEntry { app_entry, action } => match app_entry {
@@ -411,6 +820,50 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     EntryCreationAction::Create(action),
                     key_registration,
                 ),
+                EntryTypes::KeyAnchor(key_anchor) => {
+                    validate_create_key_anchor(EntryCreationAction::Create(action), key_anchor)
+                }
+                EntryTypes::JoiningProof(joining_proof) => validate_create_joining_proof(
+                    EntryCreationAction::Create(action),
+                    joining_proof,
+                ),
+                EntryTypes::ChangeRuleVeto(change_rule_veto) => validate_create_change_rule_veto(
+                    EntryCreationAction::Create(action),
+                    change_rule_veto,
+                ),
+                EntryTypes::KeysetFreeze(keyset_freeze) => validate_create_keyset_freeze(
+                    EntryCreationAction::Create(action),
+                    keyset_freeze,
+                ),
+                EntryTypes::KeysetUnfreeze(keyset_unfreeze) => validate_create_keyset_unfreeze(
+                    EntryCreationAction::Create(action),
+                    keyset_unfreeze,
+                ),
+                EntryTypes::GuardianAcceptance(guardian_acceptance) => {
+                    validate_create_guardian_acceptance(
+                        EntryCreationAction::Create(action),
+                        guardian_acceptance,
+                    )
+                }
+                EntryTypes::Proposal(proposal) => {
+                    validate_create_proposal(EntryCreationAction::Create(action), proposal)
+                }
+                EntryTypes::ProposalSignature(proposal_signature) => {
+                    validate_create_proposal_signature(
+                        EntryCreationAction::Create(action),
+                        proposal_signature,
+                    )
+                }
+                EntryTypes::RecoveryEncryptionKey(recovery_encryption_key) => {
+                    validate_create_recovery_encryption_key(
+                        EntryCreationAction::Create(action),
+                        recovery_encryption_key,
+                    )
+                }
+                EntryTypes::RecoveryShare(recovery_share) => validate_create_recovery_share(
+                    EntryCreationAction::Create(action),
+                    recovery_share,
+                ),
             },
             OpRecord::UpdateEntry {
                 original_action_hash,
@@ -715,35 +1168,349 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             Ok(result)
                         }
                     }
-                }
-            }
-            OpRecord::DeleteEntry {
-                original_action_hash,
-                action,
-                ..
-            } => {
-                let original_record = must_get_valid_record(original_action_hash)?;
-                let original_action = original_record.action().clone();
-                let original_action = match original_action {
-                    Action::Create(create) => EntryCreationAction::Create(create),
-                    Action::Update(update) => EntryCreationAction::Update(update),
-                    _ => {
-                        return Ok(ValidateCallbackResult::Invalid(
-                            "Original action for a delete must be a Create or Update action"
-                                .to_string(),
-                        ));
-                    }
-                };
-                let app_entry_type = match original_action.entry_type() {
-                    EntryType::App(app_entry_type) => app_entry_type,
-                    _ => {
-                        return Ok(ValidateCallbackResult::Valid);
-                    }
-                };
-                let entry = match original_record.entry().as_option() {
-                    Some(entry) => entry,
-                    None => {
-                        if original_action.entry_type().visibility().is_public() {
+                    EntryTypes::KeyAnchor(key_anchor) => {
+                        let result = validate_create_key_anchor(
+                            EntryCreationAction::Update(action.clone()),
+                            key_anchor.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_key_anchor: Option<KeyAnchor> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_key_anchor = match original_key_anchor {
+                                Some(key_anchor) => key_anchor,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_key_anchor(
+                                action,
+                                key_anchor,
+                                original_action,
+                                original_key_anchor,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::JoiningProof(joining_proof) => {
+                        let result = validate_create_joining_proof(
+                            EntryCreationAction::Update(action.clone()),
+                            joining_proof.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_joining_proof: Option<JoiningProof> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_joining_proof = match original_joining_proof {
+                                Some(joining_proof) => joining_proof,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_joining_proof(
+                                action,
+                                joining_proof,
+                                original_action,
+                                original_joining_proof,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::ChangeRuleVeto(change_rule_veto) => {
+                        let result = validate_create_change_rule_veto(
+                            EntryCreationAction::Update(action.clone()),
+                            change_rule_veto.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_change_rule_veto: Option<ChangeRuleVeto> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_change_rule_veto = match original_change_rule_veto {
+                                Some(change_rule_veto) => change_rule_veto,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_change_rule_veto(
+                                action,
+                                change_rule_veto,
+                                original_action,
+                                original_change_rule_veto,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::KeysetFreeze(keyset_freeze) => {
+                        let result = validate_create_keyset_freeze(
+                            EntryCreationAction::Update(action.clone()),
+                            keyset_freeze.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_keyset_freeze: Option<KeysetFreeze> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_keyset_freeze = match original_keyset_freeze {
+                                Some(keyset_freeze) => keyset_freeze,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_keyset_freeze(
+                                action,
+                                keyset_freeze,
+                                original_action,
+                                original_keyset_freeze,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::KeysetUnfreeze(keyset_unfreeze) => {
+                        let result = validate_create_keyset_unfreeze(
+                            EntryCreationAction::Update(action.clone()),
+                            keyset_unfreeze.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_keyset_unfreeze: Option<KeysetUnfreeze> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_keyset_unfreeze = match original_keyset_unfreeze {
+                                Some(keyset_unfreeze) => keyset_unfreeze,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_keyset_unfreeze(
+                                action,
+                                keyset_unfreeze,
+                                original_action,
+                                original_keyset_unfreeze,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::GuardianAcceptance(guardian_acceptance) => {
+                        let result = validate_create_guardian_acceptance(
+                            EntryCreationAction::Update(action.clone()),
+                            guardian_acceptance.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_guardian_acceptance: Option<GuardianAcceptance> =
+                                original_record
+                                    .entry()
+                                    .to_app_option()
+                                    .map_err(|e| wasm_error!(e))?;
+                            let original_guardian_acceptance = match original_guardian_acceptance {
+                                Some(guardian_acceptance) => guardian_acceptance,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_guardian_acceptance(
+                                action,
+                                guardian_acceptance,
+                                original_action,
+                                original_guardian_acceptance,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::Proposal(proposal) => {
+                        let result = validate_create_proposal(
+                            EntryCreationAction::Update(action.clone()),
+                            proposal.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_proposal: Option<Proposal> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_proposal = match original_proposal {
+                                Some(proposal) => proposal,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_proposal(
+                                action,
+                                proposal,
+                                original_action,
+                                original_proposal,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::ProposalSignature(proposal_signature) => {
+                        let result = validate_create_proposal_signature(
+                            EntryCreationAction::Update(action.clone()),
+                            proposal_signature.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_proposal_signature: Option<ProposalSignature> =
+                                original_record
+                                    .entry()
+                                    .to_app_option()
+                                    .map_err(|e| wasm_error!(e))?;
+                            let original_proposal_signature = match original_proposal_signature {
+                                Some(proposal_signature) => proposal_signature,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_proposal_signature(
+                                action,
+                                proposal_signature,
+                                original_action,
+                                original_proposal_signature,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::RecoveryEncryptionKey(recovery_encryption_key) => {
+                        let result = validate_create_recovery_encryption_key(
+                            EntryCreationAction::Update(action.clone()),
+                            recovery_encryption_key.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_recovery_encryption_key: Option<RecoveryEncryptionKey> =
+                                original_record
+                                    .entry()
+                                    .to_app_option()
+                                    .map_err(|e| wasm_error!(e))?;
+                            let original_recovery_encryption_key =
+                                match original_recovery_encryption_key {
+                                    Some(recovery_encryption_key) => recovery_encryption_key,
+                                    None => {
+                                        return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                    }
+                                };
+                            validate_update_recovery_encryption_key(
+                                action,
+                                recovery_encryption_key,
+                                original_action,
+                                original_recovery_encryption_key,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                    EntryTypes::RecoveryShare(recovery_share) => {
+                        let result = validate_create_recovery_share(
+                            EntryCreationAction::Update(action.clone()),
+                            recovery_share.clone(),
+                        )?;
+                        if let ValidateCallbackResult::Valid = result {
+                            let original_recovery_share: Option<RecoveryShare> = original_record
+                                .entry()
+                                .to_app_option()
+                                .map_err(|e| wasm_error!(e))?;
+                            let original_recovery_share = match original_recovery_share {
+                                Some(recovery_share) => recovery_share,
+                                None => {
+                                    return Ok(
+                                            ValidateCallbackResult::Invalid(
+                                                "The updated entry type must be the same as the original entry type"
+                                                    .to_string(),
+                                            ),
+                                        );
+                                }
+                            };
+                            validate_update_recovery_share(
+                                action,
+                                recovery_share,
+                                original_action,
+                                original_recovery_share,
+                            )
+                        } else {
+                            Ok(result)
+                        }
+                    }
+                }
+            }
+            OpRecord::DeleteEntry {
+                original_action_hash,
+                action,
+                ..
+            } => {
+                let original_record = must_get_valid_record(original_action_hash)?;
+                let original_action = original_record.action().clone();
+                let original_action = match original_action {
+                    Action::Create(create) => EntryCreationAction::Create(create),
+                    Action::Update(update) => EntryCreationAction::Update(update),
+                    _ => {
+                        return Ok(ValidateCallbackResult::Invalid(
+                            "Original action for a delete must be a Create or Update action"
+                                .to_string(),
+                        ));
+                    }
+                };
+                let app_entry_type = match original_action.entry_type() {
+                    EntryType::App(app_entry_type) => app_entry_type,
+                    _ => {
+                        return Ok(ValidateCallbackResult::Valid);
+                    }
+                };
+                let entry = match original_record.entry().as_option() {
+                    Some(entry) => entry,
+                    None => {
+                        if original_action.entry_type().visibility().is_public() {
                             return Ok(
                                     ValidateCallbackResult::Invalid(
                                         "Original record for a delete of a public entry must contain an entry"
@@ -756,9 +1523,9 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     }
                 };
                 let original_app_entry = match EntryTypes::deserialize_from_type(
-                    app_entry_type.zome_index.clone(),
-                    app_entry_type.entry_index.clone(),
-                    &entry,
+                    app_entry_type.zome_index,
+                    app_entry_type.entry_index,
+                    entry,
                 )? {
                     Some(app_entry) => app_entry,
                     None => {
@@ -826,6 +1593,68 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             original_key_registration,
                         )
                     }
+                    EntryTypes::KeyAnchor(original_key_anchor) => {
+                        validate_delete_key_anchor(action, original_action, original_key_anchor)
+                    }
+                    EntryTypes::JoiningProof(original_joining_proof) => {
+                        validate_delete_joining_proof(
+                            action,
+                            original_action,
+                            original_joining_proof,
+                        )
+                    }
+                    EntryTypes::ChangeRuleVeto(original_change_rule_veto) => {
+                        validate_delete_change_rule_veto(
+                            action,
+                            original_action,
+                            original_change_rule_veto,
+                        )
+                    }
+                    EntryTypes::KeysetFreeze(original_keyset_freeze) => {
+                        validate_delete_keyset_freeze(
+                            action,
+                            original_action,
+                            original_keyset_freeze,
+                        )
+                    }
+                    EntryTypes::KeysetUnfreeze(original_keyset_unfreeze) => {
+                        validate_delete_keyset_unfreeze(
+                            action,
+                            original_action,
+                            original_keyset_unfreeze,
+                        )
+                    }
+                    EntryTypes::GuardianAcceptance(original_guardian_acceptance) => {
+                        validate_delete_guardian_acceptance(
+                            action,
+                            original_action,
+                            original_guardian_acceptance,
+                        )
+                    }
+                    EntryTypes::Proposal(original_proposal) => {
+                        validate_delete_proposal(action, original_action, original_proposal)
+                    }
+                    EntryTypes::ProposalSignature(original_proposal_signature) => {
+                        validate_delete_proposal_signature(
+                            action,
+                            original_action,
+                            original_proposal_signature,
+                        )
+                    }
+                    EntryTypes::RecoveryEncryptionKey(original_recovery_encryption_key) => {
+                        validate_delete_recovery_encryption_key(
+                            action,
+                            original_action,
+                            original_recovery_encryption_key,
+                        )
+                    }
+                    EntryTypes::RecoveryShare(original_recovery_share) => {
+                        validate_delete_recovery_share(
+                            action,
+                            original_action,
+                            original_recovery_share,
+                        )
+                    }
                 }
             }
             OpRecord::CreateLink {
@@ -873,6 +1702,74 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                         tag,
                     )
                 }
+                LinkTypes::ChangeRuleUpdateToVetoes => {
+                    validate_create_link_change_rule_update_to_vetoes(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::KeysetRootToKeysetFreezes => {
+                    validate_create_link_keyset_root_to_keyset_freezes(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::KeysetFreezeToKeysetUnfreezes => {
+                    validate_create_link_keyset_freeze_to_keyset_unfreezes(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::AuthoritySpecToGuardianAcceptances => {
+                    validate_create_link_authority_spec_to_guardian_acceptances(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::SignerToProposals => validate_create_link_signer_to_proposals(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                ),
+                LinkTypes::ProposalToSignatures => validate_create_link_proposal_to_signatures(
+                    action,
+                    base_address,
+                    target_address,
+                    tag,
+                ),
+                LinkTypes::AgentToRecoveryEncryptionKeys => {
+                    validate_create_link_agent_to_recovery_encryption_keys(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::RecipientToRecoveryShares => {
+                    validate_create_link_recipient_to_recovery_shares(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
+                LinkTypes::AuthoritySpecToChangeRules => {
+                    validate_create_link_authority_spec_to_change_rules(
+                        action,
+                        base_address,
+                        target_address,
+                        tag,
+                    )
+                }
             },
             OpRecord::DeleteLink {
                 original_action_hash,
@@ -889,8 +1786,8 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                     }
                 };
                 let link_type = match LinkTypes::from_type(
-                    create_link.zome_index.clone(),
-                    create_link.link_type.clone(),
+                    create_link.zome_index,
+                    create_link.link_type,
                 )? {
                     Some(lt) => lt,
                     None => {
@@ -941,6 +1838,83 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                             create_link.tag,
                         )
                     }
+                    LinkTypes::ChangeRuleUpdateToVetoes => {
+                        validate_delete_link_change_rule_update_to_vetoes(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::KeysetRootToKeysetFreezes => {
+                        validate_delete_link_keyset_root_to_keyset_freezes(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::KeysetFreezeToKeysetUnfreezes => {
+                        validate_delete_link_keyset_freeze_to_keyset_unfreezes(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::AuthoritySpecToGuardianAcceptances => {
+                        validate_delete_link_authority_spec_to_guardian_acceptances(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::SignerToProposals => validate_delete_link_signer_to_proposals(
+                        action,
+                        create_link.clone(),
+                        base_address,
+                        create_link.target_address,
+                        create_link.tag,
+                    ),
+                    LinkTypes::ProposalToSignatures => validate_delete_link_proposal_to_signatures(
+                        action,
+                        create_link.clone(),
+                        base_address,
+                        create_link.target_address,
+                        create_link.tag,
+                    ),
+                    LinkTypes::AgentToRecoveryEncryptionKeys => {
+                        validate_delete_link_agent_to_recovery_encryption_keys(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::RecipientToRecoveryShares => {
+                        validate_delete_link_recipient_to_recovery_shares(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
+                    LinkTypes::AuthoritySpecToChangeRules => {
+                        validate_delete_link_authority_spec_to_change_rules(
+                            action,
+                            create_link.clone(),
+                            base_address,
+                            create_link.target_address,
+                            create_link.tag,
+                        )
+                    }
                 }
             }
             OpRecord::CreatePrivateEntry { .. } => Ok(ValidateCallbackResult::Valid),
@@ -960,8 +1934,8 @@ pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
                 let previous_action = must_get_action(action.prev_action)?;
                 match previous_action.action() {
                         Action::AgentValidationPkg(
-                            AgentValidationPkg { membrane_proof, .. },
-                        ) => validate_agent_joining(agent, membrane_proof),
+                            AgentValidationPkg { membrane_proof, timestamp, .. },
+                        ) => validate_agent_joining(agent, *timestamp, membrane_proof),
                         _ => {
                             Ok(
                                 ValidateCallbackResult::Invalid(
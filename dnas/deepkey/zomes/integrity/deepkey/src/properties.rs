@@ -0,0 +1,119 @@
+use hdi::prelude::*;
+
+use crate::{error::Error, MembraneProofKind, MAX_PROOF_OF_WORK_DIFFICULTY};
+
+/// Leading zero bits required of a ProofOfWork when the DNA properties don't say.
+pub const DEFAULT_PROOF_OF_WORK_DIFFICULTY: u32 = 20;
+
+// Network-wide settings, read from the `properties` of the DNA manifest.
+// Every field has a default, so `properties: ~` gives an open network.
+//
+// ```yaml
+// properties:
+//   membrane_mode: gated
+//   accepted_membrane_proofs: [proof_of_work]
+//   proof_of_work_difficulty: 20
+//   trusted_authorities: []
+//   accept_device_invites: true
+//   bootstrap_keyset: true
+//   change_rule_update_delay_secs: 0
+// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DeepkeyProperties {
+    pub membrane_mode: MembraneMode,
+    // The membrane proofs that admit an agent to a gated network.
+    pub accepted_membrane_proofs: Vec<MembraneProofKind>,
+    // Leading zero bits required of a ProofOfWork hash, at most 256.
+    pub proof_of_work_difficulty: u32,
+    // The keys whose ProofOfExternalAuthority certificates admit an agent.
+    pub trusted_authorities: Vec<AgentPubKey>,
+    // Whether a JoiningProof carrying a DeviceInviteAcceptance admits an agent to a gated
+    // network without an accepted membrane proof.
+    pub accept_device_invites: bool,
+    // Whether `init` creates a KeysetRoot and its ChangeRule for agents that did not join
+    // with a DeviceInviteAcceptance. When false the client must call `create_keyset_root`.
+    pub bootstrap_keyset: bool,
+    // The least time a ChangeRule update waits before it takes effect, leaving a window to
+    // veto it. A ChangeRule may ask for longer.
+    pub change_rule_update_delay_secs: u64,
+}
+
+impl Default for DeepkeyProperties {
+    fn default() -> Self {
+        Self {
+            membrane_mode: MembraneMode::default(),
+            accepted_membrane_proofs: Vec::new(),
+            proof_of_work_difficulty: DEFAULT_PROOF_OF_WORK_DIFFICULTY,
+            trusted_authorities: Vec::new(),
+            accept_device_invites: false,
+            bootstrap_keyset: true,
+            change_rule_update_delay_secs: 0,
+        }
+    }
+}
+
+impl DeepkeyProperties {
+    pub fn from_dna_info(dna_info: &DnaInfo) -> ExternResult<Self> {
+        if dna_info.properties.bytes().is_empty() {
+            return Ok(Self::default());
+        }
+        let properties: Option<Self> =
+            decode(dna_info.properties.bytes()).map_err(|e| wasm_error!(e))?;
+        let properties = properties.unwrap_or_default();
+        if properties.proof_of_work_difficulty > MAX_PROOF_OF_WORK_DIFFICULTY {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                Error::ProofOfWorkDifficultyOutOfRange(properties.proof_of_work_difficulty)
+                    .to_string()
+            )));
+        }
+        Ok(properties)
+    }
+
+    pub fn get() -> ExternResult<Self> {
+        Self::from_dna_info(&dna_info()?)
+    }
+
+    pub fn accepts(&self, kind: MembraneProofKind) -> bool {
+        match self.membrane_mode {
+            MembraneMode::Open => true,
+            MembraneMode::Gated => self.accepted_membrane_proofs.contains(&kind),
+        }
+    }
+}
+
+// Whether agents may join without presenting an accepted membrane proof.
+// Written as a plain string in the DNA properties.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "String", into = "String")]
+pub enum MembraneMode {
+    #[default]
+    Open,
+    Gated,
+}
+
+impl MembraneMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Gated => "gated",
+        }
+    }
+}
+
+impl TryFrom<String> for MembraneMode {
+    type Error = String;
+    fn try_from(mode: String) -> Result<Self, Self::Error> {
+        match mode.as_str() {
+            "open" => Ok(Self::Open),
+            "gated" => Ok(Self::Gated),
+            _ => Err(format!("Unknown membrane mode {}", mode)),
+        }
+    }
+}
+
+impl From<MembraneMode> for String {
+    fn from(mode: MembraneMode) -> Self {
+        mode.as_str().to_string()
+    }
+}
@@ -0,0 +1,224 @@
+use hdi::prelude::*;
+
+use crate::{
+    error::Error, get_keyset_root_and_leaf, AuthorityOperation, Authorization, ChangeRule,
+    SigningPayload,
+};
+
+// A pending request for the signers of a keyset's ChangeRule to approve an operation,
+// carrying the exact payload they must sign.
+// Only spec changes and key revocations are proposed this way.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct Proposal {
+    pub keyset_root: ActionHash,
+    // The ChangeRule whose signers are asked, and which the operation would replace or use.
+    pub change_rule: ActionHash,
+    pub payload: SigningPayload,
+}
+
+impl Proposal {
+    /// The operation whose spec governs the proposal, if it is of a kind that can be
+    /// proposed.
+    pub fn operation(&self) -> Option<AuthorityOperation> {
+        match &self.payload {
+            SigningPayload::SpecChange(_) => Some(AuthorityOperation::ChangeRuleUpdate),
+            SigningPayload::KeyRevocation(payload) => match payload.new_key {
+                Some(_) => Some(AuthorityOperation::KeyReplacement),
+                None => Some(AuthorityOperation::KeyRevocation),
+            },
+            _ => None,
+        }
+    }
+}
+
+// One signer's contribution to a Proposal.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct ProposalSignature {
+    pub proposal: ActionHash,
+    // The signer's position in the governing spec, and their signature of the payload.
+    pub authorization: Authorization,
+}
+
+/// A proposal is made by a device of the keyset, for a payload bound to that keyset, this
+/// network and the named ChangeRule.
+pub fn validate_create_proposal(
+    action: EntryCreationAction,
+    proposal: Proposal,
+) -> ExternResult<ValidateCallbackResult> {
+    if proposal.operation().is_none() {
+        return Error::UnsupportedProposal.into();
+    }
+    let (keyset_root, _) =
+        get_keyset_root_and_leaf(action.author().clone(), action.prev_action().clone())?;
+    if keyset_root != proposal.keyset_root {
+        return Error::ProposerNotInKeyset.into();
+    }
+    let change_rule = ChangeRule::try_from(must_get_valid_record(proposal.change_rule.clone())?)?;
+    if change_rule.keyset_root != proposal.keyset_root {
+        return Error::ProposalPayloadMismatch.into();
+    }
+    let dna_hash = dna_info()?.hash;
+    let bound = match &proposal.payload {
+        SigningPayload::SpecChange(payload) => {
+            payload.keyset_root.as_ref() == Some(&proposal.keyset_root)
+                && payload.dna_hash == dna_hash
+                && payload.prev_change_rule.as_ref() == Some(&proposal.change_rule)
+                && Some(payload.revision) == change_rule.revision.checked_add(1)
+        }
+        SigningPayload::KeyRevocation(payload) => {
+            payload.keyset_root == proposal.keyset_root
+                && payload.dna_hash == dna_hash
+                && payload.change_rule == proposal.change_rule
+        }
+        _ => false,
+    };
+    if !bound {
+        return Error::ProposalPayloadMismatch.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_proposal(
+    _action: Update,
+    _proposal: Proposal,
+    _original_action: EntryCreationAction,
+    _original_proposal: Proposal,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Proposals cannot be updated",
+    )))
+}
+pub fn validate_delete_proposal(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_proposal: Proposal,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Proposals cannot be deleted",
+    )))
+}
+
+/// A signature must be by the signer at its position in the spec governing the proposal,
+/// over the proposal's payload. It may be relayed by any agent, e.g. for a hardware key.
+pub fn validate_create_proposal_signature(
+    _action: EntryCreationAction,
+    proposal_signature: ProposalSignature,
+) -> ExternResult<ValidateCallbackResult> {
+    let proposal = Proposal::try_from(must_get_valid_record(proposal_signature.proposal.clone())?)?;
+    let operation = match proposal.operation() {
+        Some(operation) => operation,
+        None => return Error::UnsupportedProposal.into(),
+    };
+    let change_rule = ChangeRule::try_from(must_get_valid_record(proposal.change_rule.clone())?)?;
+    let (position, signature) = proposal_signature.authorization;
+    let signer = match change_rule
+        .spec_change
+        .spec_for(operation)
+        .authorized_signers
+        .get(position as usize)
+    {
+        Some(signer) => signer.clone(),
+        None => return Error::AuthorizedPositionOutOfBounds.into(),
+    };
+    if !verify_signature_raw(signer, signature, proposal.payload.to_signable_bytes()?)? {
+        return Error::BadProposalSignature.into();
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_proposal_signature(
+    _action: Update,
+    _proposal_signature: ProposalSignature,
+    _original_action: EntryCreationAction,
+    _original_proposal_signature: ProposalSignature,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Proposal Signatures cannot be updated",
+    )))
+}
+pub fn validate_delete_proposal_signature(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_proposal_signature: ProposalSignature,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Proposal Signatures cannot be deleted",
+    )))
+}
+pub fn validate_create_link_signer_to_proposals(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let proposal: crate::Proposal = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    let operation = match proposal.operation() {
+        Some(operation) => operation,
+        None => return Error::UnsupportedProposal.into(),
+    };
+    let change_rule = ChangeRule::try_from(must_get_valid_record(proposal.change_rule)?)?;
+    let is_signer = change_rule
+        .spec_change
+        .spec_for(operation)
+        .authorized_signers
+        .iter()
+        .any(|signer| AnyLinkableHash::from(signer.clone()) == base_address);
+    if !is_signer {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A Proposal can only be linked from the signers asked to sign it",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_signer_to_proposals(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "SignerToProposals links cannot be deleted",
+    )))
+}
+pub fn validate_create_link_proposal_to_signatures(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let proposal_signature: crate::ProposalSignature = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(proposal_signature.proposal) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A ProposalSignature must be linked from the Proposal it signs",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_proposal_to_signatures(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "ProposalToSignatures links cannot be deleted",
+    )))
+}
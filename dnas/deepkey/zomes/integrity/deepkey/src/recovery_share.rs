@@ -0,0 +1,141 @@
+use hdi::prelude::*;
+
+use crate::{error::Error, RecoveryEncryptionKey, RecoveryShare};
+
+pub fn validate_create_recovery_encryption_key(
+    _action: EntryCreationAction,
+    _recovery_encryption_key: RecoveryEncryptionKey,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_recovery_encryption_key(
+    _action: Update,
+    _recovery_encryption_key: RecoveryEncryptionKey,
+    _original_action: EntryCreationAction,
+    _original_recovery_encryption_key: RecoveryEncryptionKey,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Recovery Encryption Keys cannot be updated",
+    )))
+}
+pub fn validate_delete_recovery_encryption_key(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_recovery_encryption_key: RecoveryEncryptionKey,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Recovery Encryption Keys cannot be deleted",
+    )))
+}
+
+/// Both encryption keys must belong to the parties they stand for, and a released share
+/// can only come from the guardian it was entrusted to.
+pub fn validate_create_recovery_share(
+    action: EntryCreationAction,
+    recovery_share: RecoveryShare,
+) -> ExternResult<ValidateCallbackResult> {
+    let sender_key_record = must_get_valid_record(recovery_share.sender_encryption_key)?;
+    RecoveryEncryptionKey::try_from(sender_key_record.clone())?;
+    if sender_key_record.action().author() != action.author() {
+        return Error::RecoveryShareSenderKeyMismatch.into();
+    }
+    let recipient_key_record = must_get_valid_record(recovery_share.recipient_encryption_key)?;
+    RecoveryEncryptionKey::try_from(recipient_key_record.clone())?;
+    if *recipient_key_record.action().author() != recovery_share.recipient {
+        return Error::RecoveryShareRecipientKeyMismatch.into();
+    }
+    if let Some(released_from) = recovery_share.released_from {
+        let entrusted_share = RecoveryShare::try_from(must_get_valid_record(released_from)?)?;
+        if entrusted_share.recipient != *action.author() {
+            return Error::RecoveryShareNotEntrusted.into();
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_update_recovery_share(
+    _action: Update,
+    _recovery_share: RecoveryShare,
+    _original_action: EntryCreationAction,
+    _original_recovery_share: RecoveryShare,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Recovery Shares cannot be updated",
+    )))
+}
+pub fn validate_delete_recovery_share(
+    _action: Delete,
+    _original_action: EntryCreationAction,
+    _original_recovery_share: RecoveryShare,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "Recovery Shares cannot be deleted",
+    )))
+}
+pub fn validate_create_link_agent_to_recovery_encryption_keys(
+    action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let _recovery_encryption_key: crate::RecoveryEncryptionKey = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(record.action().author().clone()) != base_address
+        || action.author != *record.action().author()
+    {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A RecoveryEncryptionKey can only be linked from its author, by its author",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_agent_to_recovery_encryption_keys(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "AgentToRecoveryEncryptionKeys links cannot be deleted",
+    )))
+}
+pub fn validate_create_link_recipient_to_recovery_shares(
+    _action: CreateLink,
+    base_address: AnyLinkableHash,
+    target_address: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    let action_hash = ActionHash::from(target_address);
+    let record = must_get_valid_record(action_hash)?;
+    let recovery_share: crate::RecoveryShare = record
+        .entry()
+        .to_app_option()
+        .map_err(|e| wasm_error!(e))?
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Linked action must reference an entry"
+        ))))?;
+    if AnyLinkableHash::from(recovery_share.recipient) != base_address {
+        return Ok(ValidateCallbackResult::Invalid(String::from(
+            "A RecoveryShare must be linked from its recipient",
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+pub fn validate_delete_link_recipient_to_recovery_shares(
+    _action: DeleteLink,
+    _original_action: CreateLink,
+    _base: AnyLinkableHash,
+    _target: AnyLinkableHash,
+    _tag: LinkTag,
+) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(String::from(
+        "RecipientToRecoveryShares links cannot be deleted",
+    )))
+}
@@ -2,7 +2,7 @@ use crate::*;
 use hdi::prelude::*;
 
 #[hdk_entry_helper]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum SourceOfAuthority {
     KeysetRoot(KeysetRoot),
     DeviceInviteAcceptance(DeviceInviteAcceptance),
@@ -31,6 +31,37 @@ pub fn get_remote_source_of_authority_action_hash(
 }
 
 
+/// The KeysetRoot and the keyset leaf (the KeysetRoot itself or the latest
+/// DeviceInviteAcceptance) that an agent's chain is under as of `chain_top`.
+pub fn get_keyset_root_and_leaf(
+    agent: AgentPubKey,
+    chain_top: ActionHash,
+) -> ExternResult<(ActionHash, ActionHash)> {
+    let keyset_root_def = AppEntryDef::try_from(UnitEntryTypes::KeysetRoot)?;
+    let device_invite_acceptance_def =
+        AppEntryDef::try_from(UnitEntryTypes::DeviceInviteAcceptance)?;
+    let keyset_leaf = must_get_agent_activity(agent, ChainFilter::new(chain_top))?
+        .into_iter()
+        .filter(|activity| match activity.action.action().entry_type() {
+            Some(EntryType::App(app_entry_def)) => {
+                *app_entry_def == keyset_root_def || *app_entry_def == device_invite_acceptance_def
+            }
+            _ => false,
+        })
+        .max_by_key(|activity| activity.action.action().action_seq())
+        .map(|activity| activity.action.as_hash().clone())
+        .ok_or(wasm_error!(WasmErrorInner::Guest(String::from(
+            "Could not find the source of authority: Deepkey agent has no Keyset Root!"
+        ))))?;
+    let keyset_root = match hydrate_source_of_authority(keyset_leaf.clone())? {
+        SourceOfAuthority::KeysetRoot(_) => keyset_leaf.clone(),
+        SourceOfAuthority::DeviceInviteAcceptance(device_invite_acceptance) => {
+            device_invite_acceptance.keyset_root_authority
+        }
+    };
+    Ok((keyset_root, keyset_leaf))
+}
+
 /// Takes the action hash of a KeysetRoot or DeviceInviteAcceptance and returns the corresponding
 /// SourceOfAuthority
 pub fn hydrate_source_of_authority(soa_action_hash: ActionHash) -> ExternResult<SourceOfAuthority> {